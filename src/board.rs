@@ -2,8 +2,13 @@ use crate::flood_fill::*;
 use crate::util::calculate_hash;
 use std::collections::HashSet;
 
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
 /// Represents a location on a [Board]
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json", serde(rename_all = "snake_case"))]
 #[repr(u8)]
 pub enum Stone {
     Empty,
@@ -23,6 +28,11 @@ impl std::ops::Not for Stone {
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "json",
+    serde(tag = "type", content = "value", rename_all = "snake_case")
+)]
 pub enum Markup {
     Empty,
     Circle,
@@ -37,13 +47,56 @@ pub enum Markup {
 }
 
 /// The state of a go board at a point in time
+#[derive(Clone)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json", serde(try_from = "UncheckedBoard"))]
 pub struct Board {
     stones: Vec<Stone>,
     markup: Vec<Markup>,
     size: (usize, usize),
 
+    /// The positions seen so far, for ko, which aren't kept in json
+    #[cfg_attr(feature = "json", serde(skip))]
     hashes: HashSet<u64>,
 }
+
+/// A `Board` read from json, before its size is checked
+#[cfg(feature = "json")]
+#[derive(Deserialize)]
+struct UncheckedBoard {
+    stones: Vec<Stone>,
+    markup: Vec<Markup>,
+    size: (usize, usize),
+}
+
+#[cfg(feature = "json")]
+impl TryFrom<UncheckedBoard> for Board {
+    type Error = String;
+
+    fn try_from(b: UncheckedBoard) -> Result<Self, String> {
+        let (w, h) = b.size;
+        let n = w
+            .checked_mul(h)
+            .ok_or_else(|| format!("{}x{} is too big for a board", w, h))?;
+        if b.stones.len() != n || b.markup.len() != n {
+            return Err(format!(
+                "a {}x{} board needs {} stones and markup, found {} and {}",
+                w,
+                h,
+                n,
+                b.stones.len(),
+                b.markup.len()
+            ));
+        }
+
+        Ok(Self {
+            stones: b.stones,
+            markup: b.markup,
+            size: b.size,
+            hashes: HashSet::new(),
+        })
+    }
+}
 impl Board {
     pub fn new(w: usize, h: usize) -> Self {
         Self {
@@ -204,6 +257,7 @@ impl Board {
 #[cfg(test)]
 mod board_tests {
     use super::*;
+    use crate::diagram::ascii;
 
     #[test]
     fn correct_index() {
@@ -236,4 +290,40 @@ mod board_tests {
 
         board.index(19, 5);
     }
+
+    #[test]
+    fn capture() {
+        let mut board = ascii::board(
+            "$$
+             $$ +-----------
+             $$ | . X O . .
+             $$ | X O . . .
+             $$ | O . . . .",
+        );
+        assert!(board.attempt_set(0, 0, Stone::White));
+
+        let captured = ascii::board(
+            "$$
+             $$ +-----------
+             $$ | O . O . .
+             $$ | . O . . .
+             $$ | O . . . .",
+        );
+        assert_eq!(board.stones(), captured.stones());
+    }
+
+    #[test]
+    fn suicide() {
+        let mut board = ascii::board(
+            "$$
+             $$ +-------
+             $$ | . O .
+             $$ | O . O
+             $$ | . O .",
+        );
+
+        assert!(!board.attempt_set(0, 0, Stone::Black));
+        assert!(!board.attempt_set(1, 1, Stone::Black));
+        assert!(board.attempt_set(2, 0, Stone::White));
+    }
 }
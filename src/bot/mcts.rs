@@ -0,0 +1,155 @@
+use std::sync::atomic::AtomicBool;
+
+use super::playout::{candidates, playout};
+use super::{BotConfig, Budget, Rng};
+use crate::scoring::area_score;
+use crate::{Board, Stone};
+
+/// `None` means pass
+type Move = Option<(usize, usize)>;
+
+struct Node {
+    mv: Move,
+    /// The player who played `mv`
+    color: Stone,
+    /// Consecutive passes that led to this node
+    passes: u8,
+
+    visits: u32,
+    /// Wins for `color`, draws count as half
+    wins: f32,
+
+    children: Vec<usize>,
+    /// Moves that haven't been expanded yet, popped from the back
+    untried: Vec<Move>,
+}
+impl Node {
+    fn new(mv: Move, color: Stone, passes: u8, board: &Board, rng: &mut Rng) -> Self {
+        let untried = if passes >= 2 {
+            Vec::new()
+        } else {
+            untried_moves(board, !color, rng)
+        };
+
+        Self {
+            mv,
+            color,
+            passes,
+            visits: 0,
+            wins: 0.0,
+            children: Vec::new(),
+            untried,
+        }
+    }
+
+    fn uct(&self, parent_visits: u32, exploration: f32) -> f32 {
+        let n = self.visits as f32;
+        self.wins / n + exploration * ((parent_visits as f32).ln() / n).sqrt()
+    }
+}
+
+/// Candidate moves in random order, with the pass at the front so it is expanded last
+fn untried_moves(board: &Board, color: Stone, rng: &mut Rng) -> Vec<Move> {
+    let mut c = candidates(board, color);
+
+    // fisher-yates
+    for i in (1..c.len()).rev() {
+        c.swap(i, rng.below(i + 1));
+    }
+
+    let mut moves = vec![None];
+    moves.extend(c.into_iter().map(Some));
+    moves
+}
+
+fn play(board: &mut Board, mv: Move, color: Stone) -> bool {
+    match mv {
+        Some((x, y)) => board.attempt_set(x, y, color),
+        None => true,
+    }
+}
+
+/// Runs UCT from `board` with `color` to play, until the budget runs out.
+/// Returns the most visited move, or `None` to pass.
+pub fn search(
+    board: &Board,
+    color: Stone,
+    komi: f32,
+    config: &BotConfig,
+    stop: &AtomicBool,
+) -> Move {
+    let mut rng = Rng::new();
+    let mut budget = Budget::new(config.limit, stop);
+
+    let mut nodes = vec![Node::new(None, !color, 0, board, &mut rng)];
+
+    while !budget.exhausted() {
+        let mut b = board.clone();
+        let mut path = vec![0];
+        let mut current = 0;
+
+        // selection
+        while nodes[current].untried.is_empty() && !nodes[current].children.is_empty() {
+            let parent_visits = nodes[current].visits;
+            let best = *nodes[current]
+                .children
+                .iter()
+                .max_by(|a, b| {
+                    let a = nodes[**a].uct(parent_visits, config.exploration);
+                    let b = nodes[**b].uct(parent_visits, config.exploration);
+                    a.total_cmp(&b)
+                })
+                .unwrap();
+
+            play(&mut b, nodes[best].mv, nodes[best].color);
+            path.push(best);
+            current = best;
+        }
+
+        // expansion
+        let to_play = !nodes[current].color;
+        while let Some(mv) = nodes[current].untried.pop() {
+            if !play(&mut b, mv, to_play) {
+                continue;
+            }
+
+            let passes = match mv {
+                Some(_) => 0,
+                None => nodes[current].passes + 1,
+            };
+
+            let child = Node::new(mv, to_play, passes, &b, &mut rng);
+            nodes.push(child);
+
+            let i = nodes.len() - 1;
+            nodes[current].children.push(i);
+            path.push(i);
+            current = i;
+            break;
+        }
+
+        // simulation
+        let passes = nodes[current].passes;
+        playout(&mut b, !nodes[current].color, passes, &mut rng);
+        let winner = area_score(&b).winner(komi);
+        budget.playouts += 1;
+
+        // backpropagation
+        for i in path {
+            let n = &mut nodes[i];
+            n.visits += 1;
+
+            if winner == n.color {
+                n.wins += 1.0;
+            } else if winner == Stone::Empty {
+                n.wins += 0.5;
+            }
+        }
+    }
+
+    nodes[0]
+        .children
+        .iter()
+        .max_by_key(|c| nodes[**c].visits)
+        .and_then(|c| nodes[*c].mv)
+}
@@ -0,0 +1,221 @@
+//! A built-in Monte Carlo tree search opponent.
+//!
+//! The search runs on a background thread so the ui never blocks:
+//! start it with [Thinking::start] and call [Thinking::poll] every frame.
+
+mod mcts;
+mod playout;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::sgf::Action;
+use crate::util::calculate_hash;
+use crate::{Board, Stone};
+
+/// When the bot should stop searching and play its best move
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SearchLimit {
+    /// Stop after this many playouts
+    Playouts(u32),
+    /// Stop after thinking for this long
+    Time(Duration),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BotConfig {
+    pub limit: SearchLimit,
+    /// The exploration constant used by UCT
+    pub exploration: f32,
+}
+impl Default for BotConfig {
+    fn default() -> Self {
+        Self {
+            limit: SearchLimit::Playouts(2000),
+            exploration: 0.7,
+        }
+    }
+}
+
+/// A search running on a background thread
+pub struct Thinking {
+    receiver: Receiver<Action>,
+    stop: Arc<AtomicBool>,
+    /// Hash of the stones the search was started from, so a result can be
+    /// discarded if the position changed in the meantime
+    pub position: u64,
+    pub color: Stone,
+}
+impl Thinking {
+    pub fn start(board: &Board, color: Stone, komi: f32, config: BotConfig) -> Self {
+        let (sender, receiver) = channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let position = calculate_hash(board.stones());
+
+        let board = board.clone();
+        let thread_stop = stop.clone();
+        std::thread::spawn(move || {
+            let mv = mcts::search(&board, color, komi, &config, &thread_stop);
+
+            let a = match (mv, color) {
+                (Some((x, y)), Stone::Black) => Action::PlayBlack(x, y),
+                (Some((x, y)), _) => Action::PlayWhite(x, y),
+                (None, Stone::Black) => Action::PassBlack,
+                (None, _) => Action::PassWhite,
+            };
+
+            // the receiver is gone if the search was cancelled
+            let _ = sender.send(a);
+        });
+
+        Self {
+            receiver,
+            stop,
+            position,
+            color,
+        }
+    }
+
+    /// Returns the chosen move once the search has finished
+    pub fn poll(&self) -> Option<Action> {
+        self.receiver.try_recv().ok()
+    }
+}
+impl Drop for Thinking {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// A small xorshift generator; playouts need speed, not quality
+pub(crate) struct Rng(u64);
+impl Rng {
+    pub fn new() -> Self {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0x2545_F491_4F6C_DD1D);
+
+        Self::with_seed(seed)
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        // xorshift gets stuck on 0
+        Self(seed | 1)
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A random number in `0..n`
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// Keeps track of whether the search should stop
+struct Budget<'a> {
+    limit: SearchLimit,
+    start: Instant,
+    playouts: u32,
+    stop: &'a AtomicBool,
+}
+impl<'a> Budget<'a> {
+    fn new(limit: SearchLimit, stop: &'a AtomicBool) -> Self {
+        Self {
+            limit,
+            start: Instant::now(),
+            playouts: 0,
+            stop,
+        }
+    }
+
+    fn exhausted(&self) -> bool {
+        if self.stop.load(Ordering::Relaxed) {
+            return true;
+        }
+
+        match self.limit {
+            SearchLimit::Playouts(n) => self.playouts >= n,
+            SearchLimit::Time(d) => self.start.elapsed() >= d,
+        }
+    }
+}
+
+#[cfg(test)]
+mod bot_tests {
+    use super::*;
+
+    #[test]
+    fn passes_instead_of_filling_eyes() {
+        let mut board = Board::new(5, 5);
+
+        // + b + b +
+        // b b b b b
+        // b b b b b
+        // b b b b b
+        // b b b b b
+        for y in 0..5 {
+            for x in 0..5 {
+                board.set(x, y, Stone::Black);
+            }
+        }
+        board.set(0, 0, Stone::Empty);
+        board.set(2, 0, Stone::Empty);
+        board.set(4, 0, Stone::Empty);
+
+        let config = BotConfig {
+            limit: SearchLimit::Playouts(100),
+            ..Default::default()
+        };
+        let stop = AtomicBool::new(false);
+
+        assert_eq!(
+            mcts::search(&board, Stone::Black, 0.5, &config, &stop),
+            None
+        );
+    }
+
+    #[test]
+    fn plays_a_legal_move() {
+        let mut board = Board::new(5, 5);
+        board.set(2, 2, Stone::Black);
+
+        let config = BotConfig {
+            limit: SearchLimit::Playouts(200),
+            ..Default::default()
+        };
+        let stop = AtomicBool::new(false);
+
+        let (x, y) = mcts::search(&board, Stone::White, 0.5, &config, &stop).unwrap();
+        assert!(board.attempt_set(x, y, Stone::White));
+    }
+
+    #[test]
+    fn background_search_finishes() {
+        let board = Board::new(5, 5);
+        let config = BotConfig {
+            limit: SearchLimit::Playouts(50),
+            ..Default::default()
+        };
+
+        let thinking = Thinking::start(&board, Stone::White, 0.5, config);
+
+        let start = Instant::now();
+        let a = loop {
+            if let Some(a) = thinking.poll() {
+                break a;
+            }
+            assert!(start.elapsed() < Duration::from_secs(30));
+            std::thread::sleep(Duration::from_millis(5));
+        };
+
+        assert!(matches!(a, Action::PlayWhite(_, _) | Action::PassWhite));
+    }
+}
@@ -0,0 +1,193 @@
+use super::Rng;
+use crate::flood_fill::find_group;
+use crate::{Board, Stone};
+
+/// Returns the points orthogonally adjacent to (`x`, `y`)
+pub fn neighbors(board: &Board, x: usize, y: usize) -> Vec<(usize, usize)> {
+    let (w, h) = board.size();
+    let mut n = Vec::with_capacity(4);
+
+    if x > 0 {
+        n.push((x - 1, y));
+    }
+    if x < w - 1 {
+        n.push((x + 1, y));
+    }
+    if y > 0 {
+        n.push((x, y - 1));
+    }
+    if y < h - 1 {
+        n.push((x, y + 1));
+    }
+
+    n
+}
+
+/// A single-point eye of `color`: every neighbor is `color`, and the
+/// opponent holds at most one diagonal (none on the edge).
+pub fn is_eye(board: &Board, x: usize, y: usize, color: Stone) -> bool {
+    if board.get(x, y) != Stone::Empty {
+        return false;
+    }
+
+    let n = neighbors(board, x, y);
+    if n.iter().any(|p| board.get(p.0, p.1) != color) {
+        return false;
+    }
+
+    let (w, h) = board.size();
+    let mut diagonals = 0;
+    let mut enemy = 0;
+    for (dx, dy) in [(-1, -1), (-1, 1), (1, -1), (1, 1)] {
+        let (dx, dy): (isize, isize) = (dx, dy);
+        let (px, py) = (x as isize + dx, y as isize + dy);
+        if px < 0 || py < 0 || px >= w as isize || py >= h as isize {
+            continue;
+        }
+
+        diagonals += 1;
+        if board.get(px as usize, py as usize) == !color {
+            enemy += 1;
+        }
+    }
+
+    if diagonals < 4 {
+        enemy == 0
+    } else {
+        enemy <= 1
+    }
+}
+
+/// Every empty point that isn't one of `color`'s own eyes
+pub fn candidates(board: &Board, color: Stone) -> Vec<(usize, usize)> {
+    let (w, h) = board.size();
+    let mut c = Vec::new();
+
+    for y in 0..h {
+        for x in 0..w {
+            if board.get(x, y) == Stone::Empty && !is_eye(board, x, y, color) {
+                c.push((x, y));
+            }
+        }
+    }
+
+    c
+}
+
+/// Looks around the opponent's last move for an urgent reply:
+/// capturing a group in atari, or saving one of our own.
+fn urgent_move(board: &mut Board, last: (usize, usize), color: Stone) -> Option<(usize, usize)> {
+    if board.get(last.0, last.1) == !color {
+        let g = find_group(board, last.0, last.1, !color);
+        if g.liberties.len() == 1 {
+            let p = *g.liberties.iter().next().unwrap();
+            if board.attempt_set(p.0, p.1, color) {
+                return Some(p);
+            }
+        }
+    }
+
+    for n in neighbors(board, last.0, last.1) {
+        if board.get(n.0, n.1) != color {
+            continue;
+        }
+
+        let g = find_group(board, n.0, n.1, color);
+        if g.liberties.len() == 1 {
+            let p = *g.liberties.iter().next().unwrap();
+            if !is_eye(board, p.0, p.1, color) && board.attempt_set(p.0, p.1, color) {
+                return Some(p);
+            }
+        }
+    }
+
+    None
+}
+
+/// Plays one move for `color` using the playout policy, or passes.
+fn play_one(
+    board: &mut Board,
+    color: Stone,
+    last: Option<(usize, usize)>,
+    rng: &mut Rng,
+) -> Option<(usize, usize)> {
+    if let Some(l) = last {
+        if let Some(p) = urgent_move(board, l, color) {
+            return Some(p);
+        }
+    }
+
+    let mut c = candidates(board, color);
+    while !c.is_empty() {
+        let p = c.swap_remove(rng.below(c.len()));
+
+        if board.attempt_set(p.0, p.1, color) {
+            return Some(p);
+        }
+    }
+
+    None
+}
+
+/// Plays random moves on `board` until both players pass, starting with `turn`.
+/// `passes` is the number of consecutive passes that led to this position.
+pub fn playout(board: &mut Board, mut turn: Stone, mut passes: u8, rng: &mut Rng) {
+    let (w, h) = board.size();
+    let mut last = None;
+
+    for _ in 0..(3 * w * h) {
+        if passes >= 2 {
+            return;
+        }
+
+        last = play_one(board, turn, last, rng);
+
+        if last.is_some() {
+            passes = 0;
+        } else {
+            passes += 1;
+        }
+
+        turn = !turn;
+    }
+}
+
+#[cfg(test)]
+mod playout_tests {
+    use super::*;
+
+    #[test]
+    fn eye_detection() {
+        let mut board = Board::new(5, 5);
+
+        // + b + + +
+        // b + + + +
+        // + + + + +
+        // + + + + +
+        // + + + + +
+        board.set(1, 0, Stone::Black);
+        board.set(0, 1, Stone::Black);
+
+        assert!(is_eye(&board, 0, 0, Stone::Black));
+        assert!(!is_eye(&board, 0, 0, Stone::White));
+        assert!(!is_eye(&board, 1, 1, Stone::Black));
+
+        board.set(1, 1, Stone::White);
+        assert!(!is_eye(&board, 0, 0, Stone::Black));
+    }
+
+    #[test]
+    fn playout_fills_board() {
+        let mut board = Board::new(5, 5);
+        let mut rng = Rng::with_seed(7);
+
+        playout(&mut board, Stone::Black, 0, &mut rng);
+
+        let empty = board
+            .stones()
+            .iter()
+            .filter(|s| **s == Stone::Empty)
+            .count();
+        assert!(empty < 25 / 2);
+    }
+}
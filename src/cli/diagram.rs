@@ -0,0 +1,133 @@
+//! `go diagram`: draws a position of an sgf file as an svg, png or ascii diagram
+
+use std::path::PathBuf;
+
+use super::Options;
+use crate::diagram::{self, ascii, Diagram, Region};
+use crate::game::Numbering;
+use crate::ui::{BoardStyle, Coordinates, Overlays};
+
+const USAGE: &str = "\
+usage: go diagram --sgf <file> --out <file.svg|file.png|file.txt> [options]
+
+options:
+    --move <n>             the position after n moves of the main line (default: the last)
+    --region <aa:jj>       only draw the points between two sgf corners
+    --numbers <which>      off, all, since-variation, or the last n (default off);
+                           ascii diagrams number at most the last 10
+    --coordinates <kind>   off, gtp or sgf (default off)
+    --spacing <px>         pixels between lines (default 40)
+    --theme <name>         one of the built in themes (default Classic)
+";
+
+struct DiagramConfig {
+    sgf: PathBuf,
+    out: PathBuf,
+    moves: Option<usize>,
+    style: BoardStyle,
+    options: diagram::Options,
+}
+impl DiagramConfig {
+    fn from_options(o: &Options) -> Result<Self, String> {
+        let region = match o.get("region") {
+            Some(r) => Some(Region::parse(r).ok_or_else(|| format!("invalid region `{}`", r))?),
+            None => None,
+        };
+
+        let numbering = match o.get("numbers").unwrap_or("off") {
+            "off" => Numbering::Off,
+            "all" => Numbering::All,
+            "since-variation" => Numbering::SinceVariation,
+            n => Numbering::Last(
+                n.parse()
+                    .map_err(|_| format!("invalid value `{}` for `--numbers`", n))?,
+            ),
+        };
+        let coordinates = match o.get("coordinates").unwrap_or("off") {
+            "off" => Coordinates::Off,
+            "gtp" => Coordinates::Gtp,
+            "sgf" => Coordinates::Sgf,
+            c => return Err(format!("invalid value `{}` for `--coordinates`", c)),
+        };
+
+        let theme = o.get("theme").unwrap_or("Classic");
+        let style = crate::ui::presets()
+            .into_iter()
+            .find(|t| t.name.eq_ignore_ascii_case(theme))
+            .ok_or_else(|| format!("unknown theme `{}`", theme))?;
+
+        let spacing: f32 = o.parse_or("spacing", diagram::Options::default().spacing)?;
+        if !(1.0..=1000.0).contains(&spacing) {
+            return Err(String::from("the spacing must be from 1 to 1000 pixels"));
+        }
+
+        Ok(Self {
+            sgf: PathBuf::from(o.require("sgf")?),
+            out: PathBuf::from(o.require("out")?),
+            moves: match o.get("move") {
+                Some(_) => Some(o.parse_or("move", 0)?),
+                None => None,
+            },
+            style,
+            options: diagram::Options {
+                overlays: Overlays {
+                    numbering,
+                    last_move: true,
+                    coordinates,
+                },
+                region,
+                spacing,
+            },
+        })
+    }
+}
+
+pub fn run(args: &[String]) -> i32 {
+    let config = match Options::parse(args).and_then(|o| DiagramConfig::from_options(&o)) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{}\n", e);
+            eprint!("{}", USAGE);
+            return 2;
+        }
+    };
+
+    match draw(&config) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("failed to draw the diagram: {}", e);
+            1
+        }
+    }
+}
+
+fn draw(config: &DiagramConfig) -> Result<(), String> {
+    let mut game =
+        crate::game::build_game_from_path(config.sgf.clone()).map_err(|e| format!("{:?}", e))?;
+
+    // follow the main line
+    let mut played = 0;
+    while config.moves.is_none_or(|m| played < m) && game.tree.select_child(0).is_ok() {
+        let node = game.tree.current_node();
+        if node.actions.iter().any(|a| crate::util::mover(a).is_some()) {
+            played += 1;
+        }
+    }
+    game.do_to_now();
+
+    let ascii = config
+        .out
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("txt"));
+    if ascii {
+        let moves = match config.options.overlays.numbering {
+            Numbering::Off => 0,
+            Numbering::Last(n) => n,
+            Numbering::All | Numbering::SinceVariation => ascii::MAX_MOVES,
+        };
+        let text = ascii::to_ascii(&game, moves, config.options.region);
+        return std::fs::write(&config.out, text).map_err(|e| e.to_string());
+    }
+
+    Diagram::new(&game, &config.style, &config.options).write(&config.out)
+}
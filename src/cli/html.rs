@@ -0,0 +1,37 @@
+//! `go html`: writes an sgf file as a web page with a board viewer
+
+use std::path::PathBuf;
+
+use super::Options;
+
+const USAGE: &str = "\
+usage: go html --sgf <file> --out <file.html>
+";
+
+pub fn run(args: &[String]) -> i32 {
+    let paths = Options::parse(args).and_then(|o| {
+        Ok((
+            PathBuf::from(o.require("sgf")?),
+            PathBuf::from(o.require("out")?),
+        ))
+    });
+    let (sgf, out) = match paths {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{}\n", e);
+            eprint!("{}", USAGE);
+            return 2;
+        }
+    };
+
+    let written = crate::game::build_game_from_path(sgf)
+        .map_err(|e| format!("{:?}", e))
+        .and_then(|game| crate::html::write(&game.tree, &out));
+    match written {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("failed to write the page: {}", e);
+            1
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! `go json`: converts a game between sgf and the json of `crate::sgf::json`
+
+use std::path::{Path, PathBuf};
+
+use super::Options;
+use crate::SgfTree;
+
+const USAGE: &str = "\
+usage: go json --in <file> --out <file>
+
+converts sgf to json, or json to sgf when --out ends in .sgf
+";
+
+pub fn run(args: &[String]) -> i32 {
+    let paths = Options::parse(args).and_then(|o| {
+        Ok((
+            PathBuf::from(o.require("in")?),
+            PathBuf::from(o.require("out")?),
+        ))
+    });
+    let (input, out) = match paths {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{}\n", e);
+            eprint!("{}", USAGE);
+            return 2;
+        }
+    };
+
+    match convert(&input, &out) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("failed to convert the game: {}", e);
+            1
+        }
+    }
+}
+
+fn convert(input: &Path, out: &Path) -> Result<(), String> {
+    let text = std::fs::read_to_string(input).map_err(|e| e.to_string())?;
+    let to_sgf = out
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("sgf"));
+
+    let converted = if to_sgf {
+        let tree: SgfTree = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+        tree.to_text()
+    } else {
+        let tree = SgfTree::parse(text).map_err(|e| format!("{:?}", e))?;
+        serde_json::to_string(&tree).map_err(|e| e.to_string())?
+    };
+
+    std::fs::write(out, converted).map_err(|e| e.to_string())
+}
@@ -0,0 +1,52 @@
+//! `go kifu`: prints the main line of an sgf file as pages of figures
+
+use std::path::PathBuf;
+
+use super::Options;
+use crate::diagram::Kifu;
+
+const USAGE: &str = "\
+usage: go kifu --sgf <file> --out <file.pdf|file.svg> [options]
+
+svg pages after the first are written next to it, as file-2.svg and so on
+
+options:
+    --moves <n>    moves in each figure, unless the sgf marks figures with FG (default 50)
+";
+
+pub fn run(args: &[String]) -> i32 {
+    let parsed = Options::parse(args).and_then(|o| {
+        let sgf = PathBuf::from(o.require("sgf")?);
+        let out = PathBuf::from(o.require("out")?);
+        let per_figure = o.parse_or("moves", Kifu::default().per_figure)?;
+        if per_figure == 0 {
+            return Err(String::from("a figure needs at least one move"));
+        }
+
+        Ok((sgf, out, Kifu { per_figure }))
+    });
+    let (sgf, out, kifu) = match parsed {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{}\n", e);
+            eprint!("{}", USAGE);
+            return 2;
+        }
+    };
+
+    let written = crate::game::build_game_from_path(sgf)
+        .map_err(|e| format!("{:?}", e))
+        .and_then(|game| kifu.write(&game, &out));
+    match written {
+        Ok(files) => {
+            for f in files {
+                println!("{}", f.display());
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("failed to print the game: {}", e);
+            1
+        }
+    }
+}
@@ -0,0 +1,452 @@
+//! `go match`: plays a series of games between two gtp engines
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::Options;
+use crate::gtp::{GtpEngine, GtpError, GtpResult, Vertex};
+use crate::scoring::{area_score_with_dead, GameResult};
+use crate::sgf::Action;
+use crate::{Board, SgfTree, Stone};
+
+const USAGE: &str = "\
+usage: go match --engine-a <command> --engine-b <command> [options]
+
+options:
+    --games <n>        number of games, colours alternate (default 10)
+    --size <n>         board size (default 19)
+    --komi <komi>      (default 7.5)
+    --out <dir>        where to save the sgf files (default ./match)
+    --max-moves <n>    score the game after this many moves (default 3 * size * size)
+    --timeout <secs>   how long an engine may take to answer, before the match stops (default 120)
+";
+
+struct MatchConfig {
+    engine_a: String,
+    engine_b: String,
+    games: usize,
+    size: usize,
+    komi: f32,
+    out: PathBuf,
+    max_moves: usize,
+    timeout: Duration,
+}
+impl MatchConfig {
+    fn from_options(o: &Options) -> Result<Self, String> {
+        let size = o.parse_or("size", 19)?;
+        if !(2..=25).contains(&size) {
+            return Err(String::from("gtp only supports sizes from 2 to 25"));
+        }
+
+        Ok(Self {
+            engine_a: o.require("engine-a")?.to_string(),
+            engine_b: o.require("engine-b")?.to_string(),
+            games: o.parse_or("games", 10)?,
+            size,
+            komi: o.parse_or("komi", 7.5)?,
+            out: PathBuf::from(o.get("out").unwrap_or("match")),
+            max_moves: o.parse_or("max-moves", 3 * size * size)?,
+            timeout: Duration::try_from_secs_f32(
+                o.parse_or("timeout", GtpEngine::DEFAULT_TIMEOUT.as_secs_f32())?,
+            )
+            .map_err(|_| String::from("the timeout should be a number of seconds"))?,
+        })
+    }
+}
+
+struct Player {
+    engine: GtpEngine,
+    name: String,
+}
+
+pub fn run(args: &[String]) -> i32 {
+    let config = match Options::parse(args).and_then(|o| MatchConfig::from_options(&o)) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{}\n", e);
+            eprint!("{}", USAGE);
+            return 2;
+        }
+    };
+
+    match play_match(&config) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("match aborted: {}", e);
+            1
+        }
+    }
+}
+
+fn play_match(config: &MatchConfig) -> GtpResult<()> {
+    fs::create_dir_all(&config.out)?;
+
+    let mut a = GtpEngine::spawn(&config.engine_a)?;
+    a.timeout = config.timeout;
+    let name = a.name("engine A");
+    let mut a = Player { engine: a, name };
+
+    let mut b = GtpEngine::spawn(&config.engine_b)?;
+    b.timeout = config.timeout;
+    let name = b.name("engine B");
+    let mut b = Player { engine: b, name };
+
+    let mut tally = Tally::default();
+
+    println!("{:>5}  {:<24} {:<24} result", "game", "black", "white");
+    for i in 0..config.games {
+        let a_is_black = i % 2 == 0;
+        let (black, white) = if a_is_black {
+            (&mut a, &mut b)
+        } else {
+            (&mut b, &mut a)
+        };
+
+        let (tree, result, disputed) = play_game(black, white, config)?;
+
+        let path = config.out.join(format!("game-{:03}.sgf", i + 1));
+        fs::write(&path, tree.to_text())?;
+
+        println!(
+            "{:>5}  {:<24} {:<24} {}{}",
+            i + 1,
+            black.name,
+            white.name,
+            result,
+            if disputed { " (disputed)" } else { "" }
+        );
+        if disputed {
+            tally.disputed += 1;
+        } else {
+            tally.add(result.winner(), a_is_black);
+        }
+    }
+
+    println!();
+    tally.print(&a.name, &b.name);
+
+    Ok(())
+}
+
+/// Plays a game, returning its record, its result,
+/// and whether the engines disagreed about who won it when counting
+fn play_game(
+    black: &mut Player,
+    white: &mut Player,
+    config: &MatchConfig,
+) -> GtpResult<(SgfTree, GameResult, bool)> {
+    let size = config.size;
+
+    for p in [&mut *black, &mut *white] {
+        p.engine.send(&format!("boardsize {}", size))?;
+        p.engine.send("clear_board")?;
+        p.engine.send(&format!("komi {}", config.komi))?;
+    }
+
+    let mut tree = SgfTree::default();
+    let root = format!(
+        "FF[4]CA[UTF-8]GM[1]{}KM[{}]PB[{}]PW[{}]",
+        Action::Size(size, size),
+        config.komi,
+        sgf_text(&black.name),
+        sgf_text(&white.name),
+    );
+    // `set_root` can't fail
+    let _ = tree.set_root(root);
+
+    let mut board = Board::new(size, size);
+    let mut turn = Stone::Black;
+    let mut passes = 0;
+    let mut disputed = false;
+
+    let result = loop {
+        if passes >= 2 || tree_depth(&tree) >= config.max_moves {
+            let count = count(black, white, &board, config.komi)?;
+            if let Some(note) = count.note {
+                eprintln!("{}", note);
+                tree.handle_new_action(Action::Comment(sgf_text(&note)), false);
+            }
+            disputed = count.disputed;
+            break count.result;
+        }
+
+        let (mover, other) = match turn {
+            Stone::Black => (&mut *black, &mut *white),
+            _ => (&mut *white, &mut *black),
+        };
+
+        let v = match mover.engine.genmove(turn, size) {
+            Ok(Some(v)) => v,
+            // an unreadable move or a failed `genmove` loses the game
+            Ok(None) | Err(GtpError::Failure(_)) => break GameResult::Forfeit(!turn),
+            Err(e) => return Err(e),
+        };
+
+        let a = match v {
+            Vertex::Resign => break GameResult::Resign(!turn),
+            Vertex::Pass => {
+                passes += 1;
+                match turn {
+                    Stone::Black => Action::PassBlack,
+                    _ => Action::PassWhite,
+                }
+            }
+            Vertex::Point(x, y) => {
+                if x >= size || !board.attempt_set(x, y, turn) {
+                    eprintln!("{} played an illegal move: {}", mover.name, v.to_gtp(size));
+                    break GameResult::Forfeit(!turn);
+                }
+
+                passes = 0;
+                match turn {
+                    Stone::Black => Action::PlayBlack(x, y),
+                    _ => Action::PlayWhite(x, y),
+                }
+            }
+        };
+        tree.handle_new_action(a, true);
+
+        match other.engine.play(turn, v, size) {
+            Ok(()) => {}
+            // the opponent disagrees about the rules; it loses
+            Err(GtpError::Failure(e)) => {
+                eprintln!("{} rejected {}: {}", other.name, v.to_gtp(size), e);
+                break GameResult::Forfeit(turn);
+            }
+            Err(e) => return Err(e),
+        }
+
+        turn = !turn;
+    };
+
+    tree.root_mut().set_prop("RE", result.to_string());
+    tree.select_root();
+
+    Ok((tree, result, disputed))
+}
+
+/// The result of counting a finished game
+struct Count {
+    result: GameResult,
+    /// Whether the engines' dead stones give different winners
+    disputed: bool,
+    /// What the engines disagreed about, for the record
+    note: Option<String>,
+}
+
+/// Counts the board without the stones both engines say are dead.
+/// Engines that don't support `final_status_list` are left out.
+fn count(black: &mut Player, white: &mut Player, board: &Board, komi: f32) -> GtpResult<Count> {
+    let size = board.size().1;
+    let by_black = dead_stones(black, size)?;
+    let by_white = dead_stones(white, size)?;
+    let result = |dead: &HashSet<(usize, usize)>| {
+        GameResult::from_score(area_score_with_dead(board, dead), komi)
+    };
+
+    let count = match (by_black, by_white) {
+        (Some(b), Some(w)) if b != w => {
+            let (with_b, with_w) = (result(&b), result(&w));
+            let describe = |p: &Player, dead: &HashSet<(usize, usize)>, r: GameResult| {
+                let mut points: Vec<String> = dead
+                    .iter()
+                    .map(|&(x, y)| Vertex::Point(x, y).to_gtp(size))
+                    .collect();
+                points.sort();
+                format!("{} counts {} dead ({})", p.name, points.join(" "), r)
+            };
+            let note = format!(
+                "The engines disagree about dead stones: {}; {}",
+                describe(black, &b, with_b),
+                describe(white, &w, with_w)
+            );
+
+            // only the stones both agree on are removed
+            Count {
+                result: result(&b.intersection(&w).copied().collect()),
+                disputed: with_b.winner() != with_w.winner(),
+                note: Some(note),
+            }
+        }
+        (Some(dead), _) | (_, Some(dead)) => Count {
+            result: result(&dead),
+            disputed: false,
+            note: None,
+        },
+        (None, None) => Count {
+            result: result(&HashSet::new()),
+            disputed: false,
+            note: Some(String::from(
+                "Neither engine lists dead stones, so every stone was counted as alive",
+            )),
+        },
+    };
+
+    Ok(count)
+}
+
+/// The stones `p` thinks are dead, or `None` if it can't say
+fn dead_stones(p: &mut Player, size: usize) -> GtpResult<Option<HashSet<(usize, usize)>>> {
+    let list = match p.engine.send("final_status_list dead") {
+        Ok(l) => l,
+        Err(GtpError::Failure(_)) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    Ok(parse_points(&list, size))
+}
+
+/// The points in a gtp vertex list, one or more per line
+fn parse_points(list: &str, size: usize) -> Option<HashSet<(usize, usize)>> {
+    list.split_whitespace()
+        .map(|v| match Vertex::parse(v, size)? {
+            Vertex::Point(x, y) if x < size => Some((x, y)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The number of moves played so far, ie. the depth of the current node
+fn tree_depth(tree: &SgfTree) -> usize {
+    tree.get_all_parent_action().len()
+}
+
+/// Removes characters that would end an sgf property value early
+fn sgf_text(s: &str) -> String {
+    s.replace(['[', ']'], "")
+}
+
+#[derive(Default, Debug, PartialEq)]
+struct Tally {
+    a_black_wins: usize,
+    a_white_wins: usize,
+    b_black_wins: usize,
+    b_white_wins: usize,
+    draws: usize,
+    /// Counted games the engines disagreed about, which aren't scored
+    disputed: usize,
+}
+impl Tally {
+    fn add(&mut self, winner: Stone, a_is_black: bool) {
+        match (winner, a_is_black) {
+            (Stone::Empty, _) => self.draws += 1,
+            (Stone::Black, true) => self.a_black_wins += 1,
+            (Stone::White, false) => self.a_white_wins += 1,
+            (Stone::Black, false) => self.b_black_wins += 1,
+            (Stone::White, true) => self.b_white_wins += 1,
+        }
+    }
+
+    fn games(&self) -> usize {
+        self.a_black_wins + self.a_white_wins + self.b_black_wins + self.b_white_wins + self.draws
+    }
+
+    /// Engine A's score as a fraction of the games played
+    fn score(&self) -> f64 {
+        let a = (self.a_black_wins + self.a_white_wins) as f64 + 0.5 * self.draws as f64;
+        a / self.games() as f64
+    }
+
+    fn print(&self, a: &str, b: &str) {
+        let n = self.games();
+        if n == 0 {
+            return;
+        }
+
+        println!(
+            "{:<24} {:>6} {:>9} {:>9}",
+            "engine", "wins", "as black", "as white"
+        );
+        println!(
+            "{:<24} {:>6} {:>9} {:>9}",
+            a,
+            self.a_black_wins + self.a_white_wins,
+            self.a_black_wins,
+            self.a_white_wins
+        );
+        println!(
+            "{:<24} {:>6} {:>9} {:>9}",
+            b,
+            self.b_black_wins + self.b_white_wins,
+            self.b_black_wins,
+            self.b_white_wins
+        );
+        if self.draws > 0 {
+            println!("draws: {}", self.draws);
+        }
+        if self.disputed > 0 {
+            println!("disputed, and not counted: {}", self.disputed);
+        }
+
+        let p = self.score();
+        let (low, high) = confidence_interval(p, n);
+        println!(
+            "\nElo difference ({} - {}): {} (95% confidence: {} to {})",
+            a,
+            b,
+            format_elo(elo(p)),
+            format_elo(elo(low)),
+            format_elo(elo(high)),
+        );
+    }
+}
+
+/// The Elo difference implied by scoring `p` of the points
+fn elo(p: f64) -> f64 {
+    -400.0 * (1.0 / p - 1.0).log10()
+}
+
+fn format_elo(e: f64) -> String {
+    if e.is_infinite() {
+        String::from(if e > 0.0 { "+inf" } else { "-inf" })
+    } else {
+        format!("{:+.0}", e)
+    }
+}
+
+/// 95% confidence bounds on a score of `p` over `n` games
+fn confidence_interval(p: f64, n: usize) -> (f64, f64) {
+    let error = 1.96 * (p * (1.0 - p) / n as f64).sqrt();
+
+    ((p - error).max(0.0), (p + error).min(1.0))
+}
+
+#[cfg(test)]
+mod match_tests {
+    use super::*;
+
+    #[test]
+    fn elo_values() {
+        assert_eq!(elo(0.5), 0.0);
+        assert!((elo(0.75) - 190.8).abs() < 0.1);
+        assert!((elo(0.25) + 190.8).abs() < 0.1);
+        assert_eq!(format_elo(elo(1.0)), "+inf");
+        assert_eq!(format_elo(elo(0.0)), "-inf");
+    }
+
+    #[test]
+    fn points() {
+        let dead = parse_points("A1 B2\nT19\n", 19).unwrap();
+        assert_eq!(dead, HashSet::from([(0, 18), (1, 17), (18, 0)]));
+        assert_eq!(parse_points("", 19), Some(HashSet::new()));
+        assert_eq!(parse_points("A1 nonsense", 19), None);
+    }
+
+    #[test]
+    fn tally() {
+        let mut t = Tally::default();
+
+        t.add(Stone::Black, true);
+        t.add(Stone::Black, false);
+        t.add(Stone::White, false);
+        t.add(Stone::Empty, true);
+
+        assert_eq!(t.games(), 4);
+        assert_eq!(t.score(), 0.625);
+
+        let (low, high) = confidence_interval(t.score(), t.games());
+        assert!(low < 0.625 && 0.625 < high);
+    }
+}
@@ -0,0 +1,121 @@
+//! Headless commands, run when the program is started with arguments
+
+mod diagram;
+mod html;
+#[cfg(feature = "json")]
+mod json;
+mod kifu;
+mod match_runner;
+
+const USAGE: &str = "\
+usage: go [command] [options]
+
+with no command, opens the board editor
+
+commands:
+    match    play a series of games between two gtp engines
+    diagram  draw a position as an svg, png or ascii diagram
+    kifu     print a game as pdf or svg pages of numbered figures
+    html     write a game as a web page with a board viewer
+    json     convert a game between sgf and json, if built with the json feature
+    help     show this message
+";
+
+/// Runs the command in `args` (not including the program name)
+/// and returns the exit code.
+pub fn run(args: &[String]) -> i32 {
+    let (command, rest) = match args.split_first() {
+        Some(s) => s,
+        None => {
+            eprint!("{}", USAGE);
+            return 2;
+        }
+    };
+
+    match command.as_str() {
+        "match" => match_runner::run(rest),
+        "diagram" => diagram::run(rest),
+        "kifu" => kifu::run(rest),
+        "html" => html::run(rest),
+        #[cfg(feature = "json")]
+        "json" => json::run(rest),
+        "help" | "--help" | "-h" => {
+            print!("{}", USAGE);
+            0
+        }
+        c => {
+            eprintln!("unknown command `{}`\n", c);
+            eprint!("{}", USAGE);
+            2
+        }
+    }
+}
+
+/// Options of the form `--name value`, in order
+pub struct Options {
+    pairs: Vec<(String, String)>,
+}
+impl Options {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut pairs = Vec::new();
+        let mut iter = args.iter();
+
+        while let Some(a) = iter.next() {
+            let name = a
+                .strip_prefix("--")
+                .ok_or_else(|| format!("expected an option, found `{}`", a))?;
+            let value = iter
+                .next()
+                .ok_or_else(|| format!("missing value for `--{}`", name))?;
+
+            pairs.push((name.to_string(), value.clone()));
+        }
+
+        Ok(Self { pairs })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .rev()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn require(&self, name: &str) -> Result<&str, String> {
+        self.get(name)
+            .ok_or_else(|| format!("missing required option `--{}`", name))
+    }
+
+    /// Parses the value of `name`, or returns `default` if it wasn't given
+    pub fn parse_or<T: std::str::FromStr>(&self, name: &str, default: T) -> Result<T, String> {
+        match self.get(name) {
+            Some(v) => v
+                .parse()
+                .map_err(|_| format!("invalid value `{}` for `--{}`", v, name)),
+            None => Ok(default),
+        }
+    }
+}
+
+#[cfg(test)]
+mod cli_tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn options() {
+        let o = Options::parse(&args("--games 10 --size 9 --games 12")).unwrap();
+
+        assert_eq!(o.get("games"), Some("12"));
+        assert_eq!(o.parse_or("size", 19).unwrap(), 9);
+        assert_eq!(o.parse_or("komi", 7.5).unwrap(), 7.5);
+        assert!(o.require("engine-a").is_err());
+
+        assert!(Options::parse(&args("--games")).is_err());
+        assert!(Options::parse(&args("games 10")).is_err());
+    }
+}
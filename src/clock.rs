@@ -0,0 +1,390 @@
+use std::time::{Duration, Instant};
+
+use crate::Stone;
+
+/// How much time each player gets
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TimeControl {
+    /// The game is lost when the main time runs out
+    Absolute { main: Duration },
+    /// After the main time, `periods` periods of `period` each.
+    /// A period is only used up if a move takes longer than it.
+    ByoYomi {
+        main: Duration,
+        periods: u32,
+        period: Duration,
+    },
+    /// After the main time, `stones` moves must be played in each `period`
+    Canadian {
+        main: Duration,
+        stones: u32,
+        period: Duration,
+    },
+    /// `increment` is added after every move
+    Fischer { main: Duration, increment: Duration },
+}
+impl TimeControl {
+    pub fn main(&self) -> Duration {
+        match self {
+            Self::Absolute { main }
+            | Self::ByoYomi { main, .. }
+            | Self::Canadian { main, .. }
+            | Self::Fischer { main, .. } => *main,
+        }
+    }
+
+    /// The value of the sgf `OT` property
+    pub fn overtime_text(&self) -> Option<String> {
+        match self {
+            Self::Absolute { .. } => None,
+            Self::ByoYomi {
+                periods, period, ..
+            } => Some(format!("{}x{} byo-yomi", periods, period.as_secs())),
+            Self::Canadian { stones, period, .. } => {
+                Some(format!("{}/{} Canadian", stones, period.as_secs()))
+            }
+            Self::Fischer { increment, .. } => Some(format!("Fischer {}", increment.as_secs())),
+        }
+    }
+}
+
+/// The time one player has left
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PlayerClock {
+    pub main: Duration,
+    /// Time left in the current overtime period
+    pub period: Duration,
+    /// byo-yomi periods left, or stones left to play in the current Canadian period
+    pub overtime_count: u32,
+    pub in_overtime: bool,
+    pub flagged: bool,
+}
+impl PlayerClock {
+    fn new(control: &TimeControl) -> Self {
+        Self {
+            main: control.main(),
+            period: Duration::ZERO,
+            overtime_count: 0,
+            in_overtime: false,
+            flagged: false,
+        }
+    }
+
+    /// Takes `elapsed` off the clock
+    fn consume(&mut self, control: &TimeControl, mut elapsed: Duration) {
+        if !self.in_overtime {
+            if elapsed < self.main {
+                self.main -= elapsed;
+                return;
+            }
+
+            elapsed -= self.main;
+            self.main = Duration::ZERO;
+            self.enter_overtime(control);
+        }
+
+        if self.flagged {
+            return;
+        }
+
+        match control {
+            TimeControl::ByoYomi { period, .. } => {
+                while elapsed >= self.period {
+                    elapsed -= self.period;
+                    self.overtime_count = self.overtime_count.saturating_sub(1);
+                    self.period = *period;
+
+                    if self.overtime_count == 0 {
+                        self.flagged = true;
+                        return;
+                    }
+                }
+
+                self.period -= elapsed;
+            }
+            TimeControl::Canadian { .. } => {
+                if elapsed >= self.period {
+                    self.period = Duration::ZERO;
+                    self.flagged = true;
+                } else {
+                    self.period -= elapsed;
+                }
+            }
+            // there is no overtime, so running out of main time loses
+            _ => self.flagged = true,
+        }
+    }
+
+    fn enter_overtime(&mut self, control: &TimeControl) {
+        self.in_overtime = true;
+
+        match control {
+            TimeControl::ByoYomi {
+                periods, period, ..
+            } => {
+                self.overtime_count = *periods;
+                self.period = *period;
+                self.flagged = *periods == 0;
+            }
+            TimeControl::Canadian { stones, period, .. } => {
+                self.overtime_count = *stones;
+                self.period = *period;
+            }
+            _ => self.flagged = true,
+        }
+    }
+
+    /// Called when a move has been played
+    fn finish_move(&mut self, control: &TimeControl) {
+        if self.flagged {
+            return;
+        }
+
+        match control {
+            TimeControl::ByoYomi { period, .. } if self.in_overtime => {
+                self.period = *period;
+            }
+            TimeControl::Canadian { stones, period, .. } if self.in_overtime => {
+                // no stones, as a clock built in code might have, resets the period every move
+                self.overtime_count = self.overtime_count.saturating_sub(1);
+                if self.overtime_count == 0 {
+                    self.overtime_count = *stones;
+                    self.period = *period;
+                }
+            }
+            TimeControl::Fischer { increment, .. } => {
+                self.main += *increment;
+            }
+            _ => {}
+        }
+    }
+
+    /// The time shown on the clock: the main time, or the current period in overtime
+    pub fn time_left(&self) -> Duration {
+        if self.in_overtime {
+            self.period
+        } else {
+            self.main
+        }
+    }
+
+    /// The sgf `OB`/`OW` value, which only exists in overtime
+    pub fn overtime_left(&self) -> Option<u32> {
+        if self.in_overtime {
+            Some(self.overtime_count)
+        } else {
+            None
+        }
+    }
+}
+
+/// Both players' clocks
+#[derive(Clone, Debug)]
+pub struct GameClock {
+    pub control: TimeControl,
+    black: PlayerClock,
+    white: PlayerClock,
+    /// The player whose clock is running, and when it was last started
+    running: Option<(Stone, Instant)>,
+}
+impl GameClock {
+    pub fn new(control: TimeControl) -> Self {
+        Self {
+            control,
+            black: PlayerClock::new(&control),
+            white: PlayerClock::new(&control),
+            running: None,
+        }
+    }
+
+    pub fn running(&self) -> Option<Stone> {
+        self.running.map(|(s, _)| s)
+    }
+
+    /// Starts `color`'s clock, stopping the other
+    pub fn start(&mut self, color: Stone, now: Instant) {
+        self.stop(now);
+        self.running = Some((color, now));
+    }
+
+    pub fn stop(&mut self, now: Instant) {
+        if let Some((s, start)) = self.running.take() {
+            let control = self.control;
+            self.get_mut(s).consume(&control, now - start);
+        }
+    }
+
+    /// `color` finished their move: stops their clock and starts the opponent's.
+    /// Returns `color`'s clock, for recording in the sgf.
+    pub fn press(&mut self, color: Stone, now: Instant) -> PlayerClock {
+        self.stop(now);
+
+        let control = self.control;
+        let c = self.get_mut(color);
+        c.finish_move(&control);
+        let c = *c;
+
+        if !c.flagged {
+            self.running = Some((!color, now));
+        }
+
+        c
+    }
+
+    /// `color`'s clock as of `now`, including the time used by a move in progress
+    pub fn get(&self, color: Stone, now: Instant) -> PlayerClock {
+        let mut c = match color {
+            Stone::White => self.white,
+            _ => self.black,
+        };
+
+        if let Some((s, start)) = self.running {
+            if s == color {
+                c.consume(&self.control, now - start);
+            }
+        }
+
+        c
+    }
+
+    fn get_mut(&mut self, color: Stone) -> &mut PlayerClock {
+        match color {
+            Stone::White => &mut self.white,
+            _ => &mut self.black,
+        }
+    }
+
+    /// The player who has run out of time, if any
+    pub fn flagged(&self, now: Instant) -> Option<Stone> {
+        [Stone::Black, Stone::White]
+            .into_iter()
+            .find(|s| self.get(*s, now).flagged)
+    }
+}
+
+/// Formats a duration as `h:mm:ss` or `m:ss`
+pub fn format_duration(d: Duration) -> String {
+    let s = d.as_secs();
+
+    if s >= 3600 {
+        format!("{}:{:02}:{:02}", s / 3600, (s / 60) % 60, s % 60)
+    } else {
+        format!("{}:{:02}", s / 60, s % 60)
+    }
+}
+
+#[cfg(test)]
+mod clock_tests {
+    use super::*;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    #[test]
+    fn absolute() {
+        let start = Instant::now();
+        let mut clock = GameClock::new(TimeControl::Absolute { main: secs(60) });
+
+        clock.start(Stone::Black, start);
+        let b = clock.press(Stone::Black, start + secs(20));
+        assert_eq!(b.main, secs(40));
+        assert_eq!(clock.running(), Some(Stone::White));
+
+        assert_eq!(clock.flagged(start + secs(79)), None);
+        assert_eq!(clock.flagged(start + secs(81)), Some(Stone::White));
+    }
+
+    #[test]
+    fn byo_yomi() {
+        let start = Instant::now();
+        let mut clock = GameClock::new(TimeControl::ByoYomi {
+            main: secs(10),
+            periods: 3,
+            period: secs(30),
+        });
+
+        // 10s of main time, then 25s into the first period
+        clock.start(Stone::Black, start);
+        let b = clock.press(Stone::Black, start + secs(35));
+        assert!(b.in_overtime);
+        assert_eq!(b.overtime_left(), Some(3));
+        // the period is reset after the move
+        assert_eq!(b.time_left(), secs(30));
+
+        // using up two whole periods
+        clock.press(Stone::White, start + secs(35));
+        let b = clock.press(Stone::Black, start + secs(35 + 70));
+        assert_eq!(b.overtime_left(), Some(1));
+        assert!(!b.flagged);
+
+        clock.press(Stone::White, start + secs(105));
+        assert_eq!(clock.flagged(start + secs(105 + 29)), None);
+        assert_eq!(clock.flagged(start + secs(105 + 30)), Some(Stone::Black));
+    }
+
+    #[test]
+    fn canadian() {
+        let start = Instant::now();
+        let mut clock = GameClock::new(TimeControl::Canadian {
+            main: secs(0),
+            stones: 2,
+            period: secs(60),
+        });
+
+        clock.start(Stone::Black, start);
+        let b = clock.press(Stone::Black, start + secs(20));
+        assert_eq!(b.overtime_left(), Some(1));
+        assert_eq!(b.time_left(), secs(40));
+
+        clock.press(Stone::White, start + secs(20));
+        let b = clock.press(Stone::Black, start + secs(50));
+        // the period starts over after 2 stones
+        assert_eq!(b.overtime_left(), Some(2));
+        assert_eq!(b.time_left(), secs(60));
+
+        clock.press(Stone::White, start + secs(50));
+        assert_eq!(clock.flagged(start + secs(111)), Some(Stone::Black));
+    }
+
+    #[test]
+    fn canadian_without_stones() {
+        let start = Instant::now();
+        let mut clock = GameClock::new(TimeControl::Canadian {
+            main: secs(0),
+            stones: 0,
+            period: secs(60),
+        });
+
+        clock.start(Stone::Black, start);
+        let b = clock.press(Stone::Black, start + secs(20));
+        assert_eq!(b.time_left(), secs(60));
+        assert_eq!(clock.flagged(start + secs(70)), None);
+    }
+
+    #[test]
+    fn fischer() {
+        let start = Instant::now();
+        let mut clock = GameClock::new(TimeControl::Fischer {
+            main: secs(60),
+            increment: secs(10),
+        });
+
+        clock.start(Stone::Black, start);
+        let b = clock.press(Stone::Black, start + secs(5));
+        assert_eq!(b.main, secs(65));
+        assert_eq!(b.overtime_left(), None);
+    }
+
+    #[test]
+    fn sgf_text() {
+        let c = TimeControl::ByoYomi {
+            main: secs(600),
+            periods: 5,
+            period: secs(30),
+        };
+        assert_eq!(c.overtime_text().unwrap(), "5x30 byo-yomi");
+        assert_eq!(format_duration(secs(3725)), "1:02:05");
+        assert_eq!(format_duration(secs(65)), "1:05");
+    }
+}
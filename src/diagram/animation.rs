@@ -0,0 +1,237 @@
+//! A sequence of nodes as an animated gif or apng, one diagram per node
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use super::{raster, Diagram, Options};
+use crate::sgf::Action;
+use crate::ui::BoardStyle;
+use crate::{Game, SgfTree};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    Gif,
+    Apng,
+}
+impl Format {
+    /// Apng for paths ending with `.png`, otherwise gif
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(e) if e.eq_ignore_ascii_case("png") => Self::Apng,
+            _ => Self::Gif,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Animation {
+    pub diagram: Options,
+    /// How long each position is shown, in milliseconds
+    pub delay: u32,
+    /// How long the last position is shown before starting again
+    pub last_delay: u32,
+    /// The number of lines under the board for each node's comment, or 0 for no captions
+    pub caption_lines: usize,
+}
+impl Default for Animation {
+    fn default() -> Self {
+        Self {
+            diagram: Options::default(),
+            delay: 1000,
+            last_delay: 3000,
+            caption_lines: 0,
+        }
+    }
+}
+impl Animation {
+    /// A diagram of each node in `nodes`, which should be indices on `tree`,
+    /// drawn only as the iterator reaches it
+    pub fn frames<'a>(
+        &'a self,
+        tree: SgfTree,
+        nodes: &[usize],
+        style: &'a BoardStyle,
+    ) -> impl ExactSizeIterator<Item = Diagram> + 'a {
+        let nodes: Vec<usize> = nodes
+            .iter()
+            .copied()
+            .filter(|&n| tree.node(n).is_some())
+            .collect();
+        let mut game = Game::from_tree(tree, None);
+
+        nodes.into_iter().map(move |n| {
+            if game.tree.select_node(n).is_ok() {
+                game.do_to_now();
+            }
+            let mut d = Diagram::new(&game, style, &self.diagram);
+
+            if self.caption_lines > 0 {
+                let comment = game
+                    .tree
+                    .current_node()
+                    .actions
+                    .iter()
+                    .find_map(|a| match a {
+                        Action::Comment(c) => Some(c.as_str()),
+                        _ => None,
+                    })
+                    .unwrap_or("");
+                d.add_caption(comment, self.caption_lines, style, self.diagram.spacing);
+            }
+
+            d
+        })
+    }
+
+    /// Rasterizes the frames one at a time into `out`, so that only one is ever in memory
+    pub fn encode(
+        &self,
+        frames: impl ExactSizeIterator<Item = Diagram>,
+        format: Format,
+        out: impl Write,
+    ) -> Result<(), String> {
+        let count = frames.len();
+        let mut images = frames.enumerate().map(|(i, d)| {
+            let delay = if i + 1 == count {
+                self.last_delay
+            } else {
+                self.delay
+            };
+            raster::to_rgba(&d).map(|image| (image, delay))
+        });
+
+        let first = match images.next() {
+            Some(f) => f?,
+            None => return Err(String::from("there are no frames")),
+        };
+        // the first frame sets the size of the animation
+        let size = (first.0 .0, first.0 .1);
+        let images = std::iter::once(Ok(first)).chain(images);
+
+        match format {
+            Format::Gif => gif(size, images, out),
+            Format::Apng => apng(size, count, images, out),
+        }
+    }
+
+    /// Draws `nodes` of `tree`, and writes them to `path` as apng if it ends with `.png`,
+    /// or gif otherwise
+    pub fn write(
+        &self,
+        tree: SgfTree,
+        nodes: &[usize],
+        style: &BoardStyle,
+        path: &Path,
+    ) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        let frames = self.frames(tree, nodes, style);
+        let written = self.encode(frames, Format::from_path(path), BufWriter::new(file));
+
+        // rather than leave half an animation
+        if written.is_err() {
+            let _ = std::fs::remove_file(path);
+        }
+        written
+    }
+}
+
+type Image = (u32, u32, Vec<u8>);
+/// An image and how long it is shown, in milliseconds
+type Frame = (Image, u32);
+
+fn gif(
+    (w, h): (u32, u32),
+    images: impl Iterator<Item = Result<Frame, String>>,
+    out: impl Write,
+) -> Result<(), String> {
+    let (w, h) = (
+        u16::try_from(w).map_err(|_| "too wide for a gif")?,
+        u16::try_from(h).map_err(|_| "too tall for a gif")?,
+    );
+
+    let mut encoder = gif::Encoder::new(out, w, h, &[]).map_err(|e| e.to_string())?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(|e| e.to_string())?;
+
+    for image in images {
+        let ((_, _, mut rgba), delay) = image?;
+        let mut frame = gif::Frame::from_rgba_speed(w, h, &mut rgba, 10);
+        // in hundredths of a second
+        frame.delay = (delay / 10).min(u16::MAX as u32) as u16;
+        encoder.write_frame(&frame).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+fn apng(
+    (w, h): (u32, u32),
+    count: usize,
+    images: impl Iterator<Item = Result<Frame, String>>,
+    out: impl Write,
+) -> Result<(), String> {
+    let mut encoder = png::Encoder::new(out, w, h);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .set_animated(count as u32, 0)
+        .map_err(|e| e.to_string())?;
+
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    for image in images {
+        let ((_, _, rgba), delay) = image?;
+        let delay = delay.min(u16::MAX as u32) as u16;
+        writer
+            .set_frame_delay(delay, 1000)
+            .map_err(|e| e.to_string())?;
+        writer.write_image_data(&rgba).map_err(|e| e.to_string())?;
+    }
+    writer.finish().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod animation_tests {
+    use super::*;
+
+    #[test]
+    fn frames() {
+        let tree = SgfTree::parse(String::from(
+            "(;GM[1]SZ[5];B[bb]C[a good start];W[cc]C[a long comment that doesn't fit])",
+        ))
+        .unwrap();
+        let game = Game::from_tree(tree, None);
+        let animation = Animation {
+            caption_lines: 2,
+            ..Default::default()
+        };
+
+        let nodes = game.tree.main_line_from(0);
+        let style = BoardStyle::default();
+        let frames: Vec<Diagram> = animation
+            .frames(game.tree.clone(), &nodes, &style)
+            .collect();
+        assert_eq!(frames.len(), 3);
+        // every frame has room for the captions
+        assert!(frames.iter().all(|f| f.height == frames[0].height));
+        assert!(frames[0].height > frames[0].width);
+
+        let encode = |format| {
+            let mut bytes = Vec::new();
+            let frames = animation.frames(game.tree.clone(), &nodes, &style);
+            animation.encode(frames, format, &mut bytes).map(|_| bytes)
+        };
+        let gif = encode(Format::Gif).unwrap();
+        assert_eq!(&gif[..6], b"GIF89a");
+
+        let apng = encode(Format::Apng).unwrap();
+        // the animation control chunk
+        assert!(apng.windows(4).any(|w| w == b"acTL"));
+        let decoded = png::Decoder::new(apng.as_slice()).read_info().unwrap();
+        assert_eq!(decoded.info().animation_control().unwrap().num_frames, 3);
+
+        let none = animation.frames(game.tree.clone(), &[], &style);
+        assert!(animation.encode(none, Format::Gif, Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,506 @@
+//! Sensei's Library's `$$` ascii diagrams:
+//!
+//! ```text
+//! $$B
+//! $$ +-----------
+//! $$ | . . . . .
+//! $$ | . . X O .
+//! $$ | . 1 O 2 .
+//! $$ | . . B , .
+//! ```
+//!
+//! `X` and `O` are stones, `1` to `9` and `0` are the moves of the diagram,
+//! the first played by the colour after `$$`, and the edges of the board are
+//! drawn with `|`, `-` and `+`. Marked stones and points are:
+//!
+//! | markup   | black | white | empty |
+//! |----------|-------|-------|-------|
+//! | circle   | `B`   | `W`   | `C`   |
+//! | square   | `#`   | `@`   | `S`   |
+//! | triangle | `Y`   | `Q`   | `T`   |
+//! | cross    | `Z`   | `P`   | `M`   |
+//!
+//! and a lowercase letter is a label on an empty point.
+
+use super::Region;
+use crate::board::Markup;
+use crate::sgf::Action;
+use crate::{Game, SgfTree, Stone};
+
+/// Diagrams show at most this many moves, as 1 to 9 and 0
+pub const MAX_MOVES: usize = 10;
+
+/// Boards without all four edges shown are this size when imported
+const DEFAULT_SIZE: usize = 19;
+/// The largest board sgf can write
+const MAX_SIZE: usize = 52;
+
+/// Writes the position of `game` with its markup, numbering its last `moves` moves from 1
+pub fn to_ascii(game: &Game, moves: usize, region: Option<Region>) -> String {
+    let board = &game.board;
+    let (w, h) = board.size();
+    let region = region.unwrap_or(Region::whole(w, h)).clamp(w, h);
+
+    // who played each move, passes included, as they are numbered
+    let path = game
+        .tree
+        .path_between(0, game.tree.current_index())
+        .unwrap_or_default();
+    let movers: Vec<Stone> = path
+        .iter()
+        .filter_map(|&i| game.tree.node(i))
+        .flat_map(|n| n.actions.iter().filter_map(crate::util::mover))
+        .collect();
+
+    let shown = moves.min(MAX_MOVES).min(movers.len());
+    let first = movers.len() - shown + 1;
+
+    let mut s = String::from("$$");
+    s.push(match movers.get(first - 1) {
+        Some(Stone::White) => 'W',
+        Some(_) => 'B',
+        // no moves are shown, so the colour to play is given
+        None => match game.turn {
+            Stone::White => 'W',
+            _ => 'B',
+        },
+    });
+    if shown > 0 && first > 1 {
+        s.push_str(&format!("m{}", first));
+    }
+    s.push('\n');
+
+    let numbers = game.move_numbers(crate::game::Numbering::Last(shown));
+    let number = |x: usize, y: usize| {
+        numbers
+            .iter()
+            .find(|&&(nx, ny, _)| (nx, ny) == (x, y))
+            .map(|&(_, _, n)| n + 1 - first)
+    };
+    let stars = crate::util::star_points(w, h);
+
+    let (left, right) = (region.min.0 == 0, region.max.0 == w - 1);
+    let border = {
+        let mut b = String::from("$$ ");
+        if left {
+            b.push('+');
+        }
+        b.push_str(&"-".repeat(2 * (region.max.0 - region.min.0) + 3));
+        if right {
+            b.push('+');
+        }
+        b.push('\n');
+        b
+    };
+
+    if region.min.1 == 0 {
+        s.push_str(&border);
+    }
+    for y in region.min.1..=region.max.1 {
+        s.push_str("$$ ");
+        if left {
+            s.push_str("| ");
+        }
+
+        let points: Vec<String> = (region.min.0..=region.max.0)
+            .map(|x| {
+                let c = match number(x, y) {
+                    Some(n) => char::from_digit((n % 10) as u32, 10).unwrap_or('?'),
+                    None => symbol(board.get(x, y), &board.get_markup(x, y))
+                        .unwrap_or(if stars.contains(&(x, y)) { ',' } else { '.' }),
+                };
+                c.to_string()
+            })
+            .collect();
+        s.push_str(&points.join(" "));
+
+        if right {
+            s.push_str(" |");
+        }
+        s.push('\n');
+    }
+    if region.max.1 == h - 1 {
+        s.push_str(&border);
+    }
+
+    s
+}
+
+/// The character for `stone` with `markup`, or None for an empty point
+fn symbol(stone: Stone, markup: &Markup) -> Option<char> {
+    let c = match (stone, markup) {
+        (Stone::Black, Markup::Circle) => 'B',
+        (Stone::Black, Markup::Square) => '#',
+        (Stone::Black, Markup::Triangle) => 'Y',
+        (Stone::Black, Markup::Cross) => 'Z',
+        (Stone::Black, _) => 'X',
+        (Stone::White, Markup::Circle) => 'W',
+        (Stone::White, Markup::Square) => '@',
+        (Stone::White, Markup::Triangle) => 'Q',
+        (Stone::White, Markup::Cross) => 'P',
+        (Stone::White, _) => 'O',
+        (Stone::Empty, Markup::Circle) => 'C',
+        (Stone::Empty, Markup::Square) => 'S',
+        (Stone::Empty, Markup::Triangle) => 'T',
+        (Stone::Empty, Markup::Cross) => 'M',
+        (Stone::Empty, Markup::Label(l)) => {
+            let mut chars = l.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_ascii_alphabetic() => c.to_ascii_lowercase(),
+                _ => return None,
+            }
+        }
+        (Stone::Empty, _) => return None,
+    };
+
+    Some(c)
+}
+
+/// What a character of a diagram means
+#[derive(Clone, PartialEq, Debug)]
+enum Point {
+    Empty,
+    Stone(Stone, Markup),
+    Markup(Markup),
+    /// The nth move of the diagram, from 1
+    Move(usize),
+}
+impl Point {
+    fn parse(c: char) -> Option<Self> {
+        let p = match c {
+            '.' | ',' => Self::Empty,
+            'X' => Self::Stone(Stone::Black, Markup::Empty),
+            'B' => Self::Stone(Stone::Black, Markup::Circle),
+            '#' => Self::Stone(Stone::Black, Markup::Square),
+            'Y' => Self::Stone(Stone::Black, Markup::Triangle),
+            'Z' => Self::Stone(Stone::Black, Markup::Cross),
+            'O' => Self::Stone(Stone::White, Markup::Empty),
+            'W' => Self::Stone(Stone::White, Markup::Circle),
+            '@' => Self::Stone(Stone::White, Markup::Square),
+            'Q' => Self::Stone(Stone::White, Markup::Triangle),
+            'P' => Self::Stone(Stone::White, Markup::Cross),
+            'C' => Self::Markup(Markup::Circle),
+            'S' => Self::Markup(Markup::Square),
+            'T' => Self::Markup(Markup::Triangle),
+            'M' => Self::Markup(Markup::Cross),
+            // labels are uppercase, like the letter tool's
+            'a'..='z' => Self::Markup(Markup::Label(c.to_ascii_uppercase().to_string())),
+            '0' => Self::Move(10),
+            '1'..='9' => Self::Move(c.to_digit(10)? as usize),
+            _ => return None,
+        };
+
+        Some(p)
+    }
+}
+
+/// A parsed diagram, before it is placed on a board
+struct Parsed {
+    first: Stone,
+    title: String,
+    rows: Vec<Vec<Point>>,
+    /// Which of the left, right, top and bottom edges are drawn
+    edges: [bool; 4],
+}
+impl Parsed {
+    fn parse(s: &str) -> Result<Self, String> {
+        let mut lines = s
+            .lines()
+            .map(str::trim)
+            .filter_map(|l| l.strip_prefix("$$"));
+
+        let header = lines.next().ok_or("there is no `$$` line")?;
+        let first = match header.chars().next() {
+            Some('W') => Stone::White,
+            _ => Stone::Black,
+        };
+        // the colour, and the `c` and `m<n>` options
+        let title = header
+            .trim_start_matches(['B', 'W'])
+            .trim_start_matches(|c: char| c == 'c' || c == 'm' || c.is_ascii_digit())
+            .trim()
+            .to_string();
+
+        let mut parsed = Self {
+            first,
+            title,
+            rows: Vec::new(),
+            edges: [false; 4],
+        };
+
+        for line in lines {
+            let line: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+            // sensei's library links and arrows, which aren't supported
+            if line.is_empty() || line.starts_with(['{', '[']) {
+                continue;
+            }
+
+            if line.chars().all(|c| c == '-' || c == '+') {
+                if parsed.rows.is_empty() {
+                    parsed.edges[2] = true;
+                } else {
+                    parsed.edges[3] = true;
+                }
+                continue;
+            }
+
+            let mut line = line.as_str();
+            if let Some(l) = line.strip_prefix('|') {
+                parsed.edges[0] = true;
+                line = l;
+            }
+            if let Some(l) = line.strip_suffix('|') {
+                parsed.edges[1] = true;
+                line = l;
+            }
+
+            let row = line
+                .chars()
+                .map(|c| Point::parse(c).ok_or_else(|| format!("unknown point `{}`", c)))
+                .collect::<Result<Vec<_>, String>>()?;
+            if parsed.rows.first().is_some_and(|r| r.len() != row.len()) {
+                return Err(String::from("the rows aren't all the same length"));
+            }
+            parsed.rows.push(row);
+        }
+
+        if parsed.rows.first().is_none_or(|r| r.is_empty()) {
+            return Err(String::from("the diagram has no points"));
+        }
+        let (w, h) = (parsed.rows[0].len(), parsed.rows.len());
+        if w > MAX_SIZE || h > MAX_SIZE {
+            return Err(format!(
+                "the diagram is {}x{}, but boards are at most {}x{}",
+                w, h, MAX_SIZE, MAX_SIZE
+            ));
+        }
+
+        Ok(parsed)
+    }
+
+    /// The board's size, and where the top left of the diagram is on it
+    fn placement(&self) -> ((usize, usize), (usize, usize)) {
+        let place = |len: usize, start: bool, end: bool| {
+            let size = if start && end {
+                len
+            } else {
+                len.max(DEFAULT_SIZE)
+            };
+            let offset = if end && !start { size - len } else { 0 };
+
+            (size, offset)
+        };
+
+        let (w, x) = place(self.rows[0].len(), self.edges[0], self.edges[1]);
+        let (h, y) = place(self.rows.len(), self.edges[2], self.edges[3]);
+
+        ((w, h), (x, y))
+    }
+
+    /// Each point that isn't empty, on the board
+    fn points(&self) -> impl Iterator<Item = (usize, usize, &Point)> {
+        let (_, (ox, oy)) = self.placement();
+
+        self.rows.iter().enumerate().flat_map(move |(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, p)| **p != Point::Empty)
+                .map(move |(x, p)| (x + ox, y + oy, p))
+        })
+    }
+}
+
+/// A tree with the stones of a diagram as `AB` and `AW`, its markup,
+/// and a node for each of its numbered moves
+pub fn parse(s: &str) -> Result<SgfTree, String> {
+    let parsed = Parsed::parse(s)?;
+    let ((w, h), _) = parsed.placement();
+
+    let mut black = Vec::new();
+    let mut white = Vec::new();
+    let (mut circles, mut crosses, mut squares, mut triangles) =
+        (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    let mut labels = Vec::new();
+    let mut moves = Vec::new();
+
+    for (x, y, p) in parsed.points() {
+        let markup = match p {
+            Point::Empty => continue,
+            Point::Move(n) => {
+                moves.push((*n, x, y));
+                continue;
+            }
+            Point::Stone(stone, markup) => {
+                match stone {
+                    Stone::White => white.push((x, y)),
+                    _ => black.push((x, y)),
+                }
+                markup
+            }
+            Point::Markup(markup) => markup,
+        };
+
+        match markup {
+            Markup::Circle => circles.push((x, y)),
+            Markup::Cross => crosses.push((x, y)),
+            Markup::Square => squares.push((x, y)),
+            Markup::Triangle => triangles.push((x, y)),
+            Markup::Label(l) => labels.push((x, y, l.clone())),
+            _ => {}
+        }
+    }
+
+    let mut tree = SgfTree::default();
+    tree.set_root(String::from("FF[4]CA[UTF-8]GM[1]"))
+        .map_err(|e| format!("{:?}", e))?;
+    if !parsed.title.is_empty() {
+        tree.root_mut().set_prop("GN", parsed.title.as_str());
+    }
+
+    let root = [
+        Action::Size(w, h),
+        Action::AddBlack(black),
+        Action::AddWhite(white),
+        Action::Circle(circles),
+        Action::Cross(crosses),
+        Action::Square(squares),
+        Action::Triangle(triangles),
+    ];
+    for a in root {
+        let empty = match &a {
+            Action::AddBlack(v)
+            | Action::AddWhite(v)
+            | Action::Circle(v)
+            | Action::Cross(v)
+            | Action::Square(v)
+            | Action::Triangle(v) => v.is_empty(),
+            _ => false,
+        };
+        if !empty {
+            tree.handle_new_action(a, false);
+        }
+    }
+    if !labels.is_empty() {
+        tree.handle_new_action(Action::Label(labels), false);
+    }
+
+    moves.sort();
+    for (n, x, y) in moves {
+        let stone = if n % 2 == 1 {
+            parsed.first
+        } else {
+            !parsed.first
+        };
+        let a = match stone {
+            Stone::White => Action::PlayWhite(x, y),
+            _ => Action::PlayBlack(x, y),
+        };
+        tree.handle_new_action(a, true);
+    }
+
+    tree.select_root();
+    Ok(tree)
+}
+
+/// The stones of a diagram on a board, ignoring its markup and moves, for tests
+#[cfg(test)]
+pub fn board(s: &str) -> crate::board::Board {
+    let parsed = Parsed::parse(s).unwrap();
+    let ((w, h), _) = parsed.placement();
+
+    let mut board = crate::board::Board::new(w, h);
+    for (x, y, p) in parsed.points() {
+        if let Point::Stone(stone, _) = p {
+            board.set(x, y, *stone);
+        }
+    }
+
+    board
+}
+
+#[cfg(test)]
+mod ascii_tests {
+    use super::*;
+
+    #[test]
+    fn export() {
+        let tree = SgfTree::parse(String::from(
+            "(;GM[1]SZ[5]AB[aa][ba]AW[ab];W[cc];B[dc]CR[cc]TR[ee]LB[ae:A];W[ad])",
+        ))
+        .unwrap();
+        let mut game = Game::from_tree(tree, None);
+        while game.tree.select_child(0).is_ok() {}
+        game.do_to_now();
+
+        let expected = "\
+$$Bm2
+$$ +-----------+
+$$ | X X . . . |
+$$ | O . . . . |
+$$ | . . O 1 . |
+$$ | 2 . . . . |
+$$ | . . . . . |
+$$ +-----------+
+";
+        assert_eq!(to_ascii(&game, 2, None), expected);
+
+        // the markup of the node before, with white to play
+        game.tree.select_parent().unwrap();
+        game.do_to_now();
+        let region = Region::parse("ac:ee").unwrap();
+        let expected = "\
+$$W
+$$ | . . W X . |
+$$ | . . . . . |
+$$ | a . . . T |
+$$ +-----------+
+";
+        assert_eq!(to_ascii(&game, 0, Some(region)), expected);
+    }
+
+    #[test]
+    fn import() {
+        let tree = parse(
+            "some text around the diagram
+$$B A title
+$$  ---------------
+$$  . . . . . . . |
+$$  . . X O 1 , . |
+$$  . a Y 2 . . . |",
+        )
+        .unwrap();
+
+        assert_eq!(tree.root().get_prop("GN"), Some("A title"));
+        let text = tree.to_text();
+        assert!(text.contains("SZ[19]"), "{}", text);
+        assert!(text.contains("AB[ob][oc]"), "{}", text);
+        assert!(text.contains("AW[pb]"), "{}", text);
+        assert!(text.contains("TR[oc]"), "{}", text);
+        assert!(text.contains("LB[nc:A]"), "{}", text);
+        assert!(text.contains(";B[qb];W[pc]"), "{}", text);
+
+        assert!(parse("$$\n$$ | . . Ä").is_err());
+        assert!(parse("$$\n$$ | . .\n$$ | .").is_err());
+        assert!(parse("no diagram").is_err());
+        assert!(parse(&format!("$$\n$$ {}", ". ".repeat(60))).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let tree = SgfTree::parse(String::from(
+            "(;GM[1]SZ[9]AB[cc][dc]AW[cd];W[dd];B[ec];W[ed];B[bd];W[cb];B[be];W[bc];B[ce])",
+        ))
+        .unwrap();
+        let mut game = Game::from_tree(tree, None);
+        while game.tree.select_child(0).is_ok() {}
+        game.do_to_now();
+
+        let ascii = to_ascii(&game, 10, None);
+        let mut imported = Game::from_tree(parse(&ascii).unwrap(), None);
+        while imported.tree.select_child(0).is_ok() {}
+        imported.do_to_now();
+
+        assert_eq!(imported.board.stones(), game.board.stones());
+        assert_eq!(to_ascii(&imported, 10, None), ascii);
+    }
+}
@@ -0,0 +1,493 @@
+//! Printable game records: the main line split into figures of numbered moves,
+//! on A4 pages with the game's information as a header and comments as footnotes
+
+use std::path::{Path, PathBuf};
+
+use eframe::egui::{pos2, vec2, Color32, FontFamily, Vec2};
+
+use super::{pdf, svg, wrap, Align, Diagram, Options, Shape};
+use crate::board::Board;
+use crate::sgf::Action;
+use crate::ui::{BoardStyle, Overlays};
+use crate::{Game, SgfTree, Stone};
+
+/// A4, in points
+const PAGE: Vec2 = vec2(595.0, 842.0);
+const MARGIN: f32 = 48.0;
+/// The largest distance between lines, so small boards aren't drawn huge
+const MAX_SPACING: f32 = 32.0;
+
+/// The root properties in the header, with their names
+const INFO: [(&str, &str); 8] = [
+    ("EV", "Event"),
+    ("RO", "Round"),
+    ("DT", "Date"),
+    ("PC", "Place"),
+    ("RU", "Rules"),
+    ("KM", "Komi"),
+    ("HA", "Handicap"),
+    ("TM", "Time"),
+];
+
+/// A part of the main line, drawn as one board
+pub struct Figure {
+    /// The name from the `FG` property of its first node
+    pub name: Option<String>,
+    /// The position before the figure's first move, with its moves added
+    board: Board,
+    /// The stones numbered on the board, as `(x, y, number)`
+    numbers: Vec<(usize, usize, usize)>,
+    /// The numbers of its first and last moves
+    pub moves: (usize, usize),
+    /// Moves played where there was already a stone, such as `12 at 5`, and passes
+    pub notes: Vec<String>,
+    /// The comments of its nodes, after the move they are on
+    pub comments: Vec<String>,
+}
+
+/// The main line of `tree` split into figures, at the nodes with an `FG` property if it
+/// has any, and otherwise every `per_figure` moves. There are none if it is only the root.
+pub fn figures(tree: &SgfTree, per_figure: usize) -> Vec<Figure> {
+    let line = tree.main_line_from(0);
+    if line.len() < 2 {
+        return Vec::new();
+    }
+    let node = |p: usize| line.get(p).and_then(|&i| tree.node(i));
+    let moves_in = |p: usize| {
+        node(p).map_or(0, |n| {
+            n.actions
+                .iter()
+                .filter(|a| crate::util::mover(a).is_some())
+                .count()
+        })
+    };
+    let figure_name = |p: usize| {
+        let fg = node(p)?.get_prop("FG")?;
+        let name = fg.split_once(':').map_or("", |(_, n)| n).trim();
+        Some(name.to_string()).filter(|n| !n.is_empty())
+    };
+
+    // each figure starts at one of these places on the line, and goes on to the next
+    let marked = (1..line.len()).any(|p| node(p).is_some_and(|n| n.get_prop("FG").is_some()));
+    let mut starts = vec![1];
+    let mut count = 0;
+    for p in 1..line.len() {
+        let moves = moves_in(p);
+        let start = if marked {
+            node(p).is_some_and(|n| n.get_prop("FG").is_some())
+        } else {
+            count >= per_figure.max(1) && moves > 0
+        };
+        if start && p > 1 {
+            starts.push(p);
+            count = 0;
+        }
+        count += moves;
+    }
+
+    let mut game = Game::from_tree(tree.clone(), None);
+    let mut number = 0;
+    let mut figures = Vec::new();
+
+    for (k, &start) in starts.iter().enumerate() {
+        let end = starts.get(k + 1).copied().unwrap_or(line.len());
+
+        let _ = game.tree.select_node(line[start - 1]);
+        game.do_to_now();
+        let mut board = game.board.clone();
+        board.clear_markup();
+        let (w, h) = board.size();
+
+        let mut figure = Figure {
+            // a figure on the root names the first one
+            name: figure_name(start).or_else(|| figure_name(0).filter(|_| k == 0)),
+            board,
+            numbers: Vec::new(),
+            moves: (number + 1, number),
+            notes: Vec::new(),
+            comments: Vec::new(),
+        };
+
+        for p in start..end {
+            let Some(n) = node(p) else { continue };
+
+            let mut comment = None;
+            for a in &n.actions {
+                match a {
+                    // `tt` is a pass in older sgf
+                    Action::PlayBlack(x, y) | Action::PlayWhite(x, y) if *x >= w || *y >= h => {
+                        number += 1;
+                        figure.notes.push(format!("{} pass", number));
+                    }
+                    Action::PlayBlack(x, y) | Action::PlayWhite(x, y) => {
+                        number += 1;
+                        let (x, y) = (*x, *y);
+
+                        let here = figure
+                            .numbers
+                            .iter()
+                            .find(|&&(nx, ny, _)| (nx, ny) == (x, y));
+                        if let Some(&(_, _, m)) = here {
+                            figure.notes.push(format!("{} at {}", number, m));
+                        } else if figure.board.get(x, y) != Stone::Empty {
+                            let point = format!("{}{}", crate::gtp::column_letter(x), h - y);
+                            figure.notes.push(format!("{} at {}", number, point));
+                        } else {
+                            let stone = crate::util::mover(a).unwrap_or(Stone::Black);
+                            figure.board.set(x, y, stone);
+                            figure.numbers.push((x, y, number));
+                        }
+                    }
+                    Action::PassBlack | Action::PassWhite => {
+                        number += 1;
+                        figure.notes.push(format!("{} pass", number));
+                    }
+                    Action::Comment(c) if !c.trim().is_empty() => comment = Some(c.trim()),
+                    _ => {}
+                }
+            }
+
+            if let Some(c) = comment {
+                figure.comments.push(match number {
+                    0 => c.to_string(),
+                    n => format!("{}: {}", n, c),
+                });
+            }
+        }
+
+        figure.moves.1 = number;
+        figures.push(figure);
+    }
+
+    figures
+}
+
+/// Black and white, for printing
+fn print_style() -> BoardStyle {
+    BoardStyle {
+        name: String::from("Print"),
+        background_color: Color32::WHITE,
+        line_thickness: 1.5,
+        star_point_radius: 4.0,
+        stone_radius: 0.48,
+        outline: 2.0,
+        markup_color: Color32::BLACK,
+        font: FontFamily::Proportional,
+        ..Default::default()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Kifu {
+    /// How many moves are in each figure, unless the game's figures are marked with `FG`
+    pub per_figure: usize,
+}
+impl Default for Kifu {
+    fn default() -> Self {
+        Self { per_figure: 50 }
+    }
+}
+impl Kifu {
+    /// The pages of the game record, each the size of a sheet of A4 in points
+    pub fn pages(&self, game: &Game) -> Vec<Diagram> {
+        let tree = &game.tree;
+        let root = tree.root();
+        let mut pages = Pages::new();
+
+        // the header
+        let player = |name: &str, rank: &str| {
+            let name = root.get_prop(name)?.trim();
+            Some(match root.get_prop(rank).map(str::trim) {
+                Some(r) if !r.is_empty() => format!("{} ({})", name, r),
+                _ => name.to_string(),
+            })
+        };
+        let (black, white) = (player("PB", "BR"), player("PW", "WR"));
+        let title = match (root.get_prop("GN"), &black, &white) {
+            (Some(name), _, _) => name.to_string(),
+            (None, Some(b), Some(w)) => format!("{} vs {}", b, w),
+            _ => String::from("Game record"),
+        };
+        pages.text(&title, 18.0, Align::Center);
+        pages.gap(6.0);
+
+        let mut info = Vec::new();
+        if let Some(b) = black {
+            info.push(format!("Black: {}", b));
+        }
+        if let Some(w) = white {
+            info.push(format!("White: {}", w));
+        }
+        for (prop, name) in INFO {
+            if let Some(v) = root.get_prop(prop).filter(|v| !v.trim().is_empty()) {
+                info.push(format!("{}: {}", name, v.trim()));
+            }
+        }
+        if let Some(re) = root.get_prop("RE") {
+            info.push(format!("Result: {}", re));
+        }
+        for line in info {
+            pages.text(&line, 10.0, Align::Left);
+        }
+        for a in &root.actions {
+            if let Action::Comment(c) = a {
+                pages.gap(6.0);
+                pages.text(c, 10.0, Align::Left);
+            }
+        }
+
+        let style = print_style();
+        let (w, h) = game.board.size();
+        let width = PAGE.x - 2.0 * MARGIN;
+        let options = Options {
+            overlays: Overlays {
+                numbering: crate::game::Numbering::Off,
+                last_move: false,
+                coordinates: crate::ui::Coordinates::Off,
+            },
+            region: None,
+            // the margins are 0.7 lines on each side
+            spacing: (width / (w.max(h) as f32 + 0.4)).min(MAX_SPACING),
+        };
+
+        let mut figure_game = Game::from_tree(SgfTree::default(), None);
+        for (i, figure) in figures(tree, self.per_figure).into_iter().enumerate() {
+            let mut heading = format!("Figure {}", i + 1);
+            if let Some(name) = &figure.name {
+                heading.push_str(&format!(": {}", name));
+            }
+            match figure.moves {
+                (a, b) if a < b => heading.push_str(&format!(" ({}-{})", a, b)),
+                (a, b) if a == b => heading.push_str(&format!(" ({})", a)),
+                _ => {}
+            }
+
+            figure_game.board = figure.board;
+            let mut d = Diagram::new(&figure_game, &style, &options);
+            d.add_numbers(&figure_game.board, &figure.numbers, &style, &options);
+
+            // the heading stays with its board
+            pages.gap(14.0);
+            pages.space(d.height + 20.0);
+            pages.text(&heading, 12.0, Align::Center);
+            pages.place(d);
+
+            if !figure.notes.is_empty() {
+                pages.text(&figure.notes.join(", "), 10.0, Align::Center);
+            }
+            for c in &figure.comments {
+                pages.text(c, 9.0, Align::Left);
+            }
+        }
+
+        pages.pages
+    }
+
+    /// Writes the pages as pdf if `path` ends with `.pdf`, and otherwise as svg files,
+    /// numbered after the first page. Returns the files written.
+    pub fn write(&self, game: &Game, path: &Path) -> Result<Vec<PathBuf>, String> {
+        let pages = self.pages(game);
+        let is_pdf = path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("pdf"));
+
+        if is_pdf {
+            std::fs::write(path, pdf::to_pdf(&pages)?).map_err(|e| e.to_string())?;
+            return Ok(vec![path.to_path_buf()]);
+        }
+
+        let mut written = Vec::new();
+        for (i, page) in pages.iter().enumerate() {
+            let path = match i {
+                0 => path.to_path_buf(),
+                _ => numbered(path, i + 1),
+            };
+            std::fs::write(&path, svg::to_svg(page)).map_err(|e| e.to_string())?;
+            written.push(path);
+        }
+
+        Ok(written)
+    }
+}
+
+/// `game.svg` as `game-2.svg`
+fn numbered(path: &Path, n: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(e) => format!("{}-{}.{}", stem, n, e.to_string_lossy()),
+        None => format!("{}-{}", stem, n),
+    };
+
+    path.with_file_name(name)
+}
+
+/// Text and boards laid out down the pages
+struct Pages {
+    pages: Vec<Diagram>,
+    /// Where the next line goes on the last page
+    y: f32,
+}
+impl Pages {
+    fn new() -> Self {
+        let mut pages = Self {
+            pages: Vec::new(),
+            y: 0.0,
+        };
+        pages.new_page();
+
+        pages
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(Diagram {
+            width: PAGE.x,
+            height: PAGE.y,
+            font: FontFamily::Proportional,
+            shapes: vec![Shape::Rect {
+                min: pos2(0.0, 0.0),
+                size: PAGE,
+                fill: Color32::WHITE,
+            }],
+        });
+        self.y = MARGIN;
+    }
+
+    /// Starts a new page unless there is `height` left on this one
+    fn space(&mut self, height: f32) {
+        if self.y + height > PAGE.y - MARGIN && self.y > MARGIN {
+            self.new_page();
+        }
+    }
+
+    fn gap(&mut self, height: f32) {
+        self.y += height;
+    }
+
+    /// Adds `text`, wrapped to the width of the page
+    fn text(&mut self, text: &str, size: f32, align: Align) {
+        let line_height = 1.4 * size;
+        // roughly the width of a character
+        let per_line = ((PAGE.x - 2.0 * MARGIN) / (0.5 * size)) as usize;
+
+        for line in wrap(text, per_line) {
+            self.space(line_height);
+            let x = match align {
+                Align::Left => MARGIN,
+                Align::Center => 0.5 * PAGE.x,
+            };
+
+            let page = self.pages.last_mut().expect("there is always a page");
+            page.shapes.push(Shape::Text {
+                center: pos2(x, self.y + 0.5 * line_height),
+                size,
+                text: line,
+                color: Color32::BLACK,
+                align,
+            });
+            self.y += line_height;
+        }
+    }
+
+    /// Adds a diagram in the middle of the page
+    fn place(&mut self, d: Diagram) {
+        self.space(d.height);
+        let offset = vec2(0.5 * (PAGE.x - d.width), self.y);
+
+        let page = self.pages.last_mut().expect("there is always a page");
+        page.shapes
+            .extend(d.shapes.into_iter().map(|s| s.translated(offset)));
+        self.y += d.height;
+    }
+}
+
+#[cfg(test)]
+mod kifu_tests {
+    use super::*;
+
+    fn tree(moves: &str) -> SgfTree {
+        SgfTree::parse(format!("(;GM[1]SZ[9]{})", moves)).unwrap()
+    }
+
+    #[test]
+    fn split() {
+        let tree = tree(";B[aa];W[ba];B[ca]C[a comment];W[];B[ab];W[ac]");
+        let figures = figures(&tree, 4);
+
+        assert_eq!(figures.len(), 2);
+        assert_eq!(figures[0].moves, (1, 4));
+        assert_eq!(figures[0].numbers.len(), 3);
+        assert_eq!(figures[0].notes, vec!["4 pass"]);
+        assert_eq!(figures[0].comments, vec!["3: a comment"]);
+
+        // the stones of the first figure are on the board of the next
+        assert_eq!(figures[1].moves, (5, 6));
+        assert_eq!(figures[1].board.get(0, 0), Stone::Black);
+        assert_eq!(figures[1].numbers, vec![(0, 1, 5), (0, 2, 6)]);
+    }
+
+    #[test]
+    fn no_moves() {
+        let tree = tree("AB[cc]C[a problem]");
+        assert!(figures(&tree, 4).is_empty());
+
+        // only the header
+        let game = Game::from_tree(tree, None);
+        assert_eq!(Kifu::default().pages(&game).len(), 1);
+    }
+
+    #[test]
+    fn overlapping() {
+        // a ko: white takes at d9, black takes back at e9, and white retakes at d9
+        let tree = tree("AB[ca][db][ea]AW[fa][eb];W[da];B[ii];W[hh];B[ea];W[gg];B[gh];W[da]");
+        let figures = figures(&tree, 50);
+
+        assert_eq!(figures.len(), 1);
+        assert_eq!(figures[0].notes, vec!["4 at E9", "7 at 1"]);
+    }
+
+    #[test]
+    fn marked_figures() {
+        let tree = tree("FG[0:Opening];B[aa];W[ba];B[ca]FG[];W[da];B[ea]FG[0:End]");
+        let figures = figures(&tree, 1);
+
+        let names: Vec<_> = figures.iter().map(|f| f.name.as_deref()).collect();
+        assert_eq!(names, vec![Some("Opening"), None, Some("End")]);
+        assert_eq!(figures[1].moves, (3, 4));
+        assert_eq!(figures[2].moves, (5, 5));
+    }
+
+    #[test]
+    fn pages() {
+        let moves: String = (0..9)
+            .flat_map(|y| (0..9).map(move |x| (x, y)))
+            .take(60)
+            .enumerate()
+            .map(|(i, (x, y))| {
+                let color = if i % 2 == 0 { 'B' } else { 'W' };
+                format!(";{}[{}{}]", color, (b'a' + x) as char, (b'a' + y) as char)
+            })
+            .collect();
+        let tree = tree(&format!("GN[A game]PB[Black]BR[3d]KM[6.5]{}", moves));
+        let game = Game::from_tree(tree, None);
+
+        let pages = Kifu { per_figure: 20 }.pages(&game);
+        assert!(pages.len() >= 2);
+        assert!(pages
+            .iter()
+            .all(|p| p.width == PAGE.x && p.height == PAGE.y));
+
+        let texts: Vec<&str> = pages
+            .iter()
+            .flat_map(|p| &p.shapes)
+            .filter_map(|s| match s {
+                Shape::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts[0], "A game");
+        assert!(texts.contains(&"Black: Black (3d)"));
+        assert!(texts.contains(&"Komi: 6.5"));
+        assert!(texts.contains(&"Figure 3 (41-60)"));
+        assert!(texts.contains(&"60"));
+    }
+}
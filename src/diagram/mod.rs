@@ -0,0 +1,725 @@
+//! Diagrams of a position for articles and slides.
+//!
+//! A `Diagram` is a list of shapes drawn in the `BoardStyle` of the board,
+//! which is written out as svg, or rasterized to png without the gpu.
+//! The style's texture isn't drawn, only its background colour.
+
+mod animation;
+pub mod ascii;
+mod kifu;
+mod pdf;
+mod raster;
+mod svg;
+
+pub use animation::Animation;
+pub use kifu::Kifu;
+use raster::to_png;
+use svg::to_svg;
+
+use std::path::Path;
+
+use eframe::egui::{pos2, vec2, Color32, FontFamily, Pos2, Vec2};
+
+use crate::board::{Board, Markup};
+use crate::ui::{contrast, stone_circles, BoardStyle, Coordinates, Overlays};
+use crate::{Game, Stone};
+
+/// The spacing between lines that the pixel sizes of a `BoardStyle` are meant for
+const STYLE_SPACING: f32 = 40.0;
+
+/// The points to show, from the top left
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Region {
+    pub min: (usize, usize),
+    /// Inclusive
+    pub max: (usize, usize),
+}
+impl Region {
+    pub fn whole(w: usize, h: usize) -> Self {
+        Self {
+            min: (0, 0),
+            max: (w - 1, h - 1),
+        }
+    }
+
+    /// Parses two opposite corners as sgf points, such as `aa:jj`
+    pub fn parse(s: &str) -> Option<Self> {
+        let (a, b) = s.split_once(':')?;
+        let a = crate::sgf::string_coords(a).ok()?;
+        let b = crate::sgf::string_coords(b).ok()?;
+
+        Some(Self {
+            min: (a.0.min(b.0), a.1.min(b.1)),
+            max: (a.0.max(b.0), a.1.max(b.1)),
+        })
+    }
+
+    fn contains(&self, x: usize, y: usize) -> bool {
+        (self.min.0..=self.max.0).contains(&x) && (self.min.1..=self.max.1).contains(&y)
+    }
+
+    /// The part of the region that is on a board of `w` by `h`
+    fn clamp(self, w: usize, h: usize) -> Self {
+        let max = (self.max.0.min(w - 1), self.max.1.min(h - 1));
+
+        Self {
+            min: (self.min.0.min(max.0), self.min.1.min(max.1)),
+            max,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Options {
+    pub overlays: Overlays,
+    /// The whole board if None
+    pub region: Option<Region>,
+    /// The distance between lines, in pixels
+    pub spacing: f32,
+}
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            overlays: Overlays::default(),
+            region: None,
+            spacing: STYLE_SPACING,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Shape {
+    Rect {
+        min: Pos2,
+        size: Vec2,
+        fill: Color32,
+    },
+    Line {
+        from: Pos2,
+        to: Pos2,
+        width: f32,
+        color: Color32,
+    },
+    Circle {
+        center: Pos2,
+        radius: f32,
+        fill: Option<Color32>,
+        /// `(width, colour)`
+        stroke: Option<(f32, Color32)>,
+    },
+    Polygon {
+        points: Vec<Pos2>,
+        fill: Color32,
+    },
+    /// In the diagram's font, vertically centered on `center`
+    Text {
+        center: Pos2,
+        /// The height of an em, in pixels
+        size: f32,
+        text: String,
+        color: Color32,
+        align: Align,
+    },
+}
+
+impl Shape {
+    /// The shape moved by `offset`
+    fn translated(self, offset: Vec2) -> Self {
+        match self {
+            Self::Rect { min, size, fill } => Self::Rect {
+                min: min + offset,
+                size,
+                fill,
+            },
+            Self::Line {
+                from,
+                to,
+                width,
+                color,
+            } => Self::Line {
+                from: from + offset,
+                to: to + offset,
+                width,
+                color,
+            },
+            Self::Circle {
+                center,
+                radius,
+                fill,
+                stroke,
+            } => Self::Circle {
+                center: center + offset,
+                radius,
+                fill,
+                stroke,
+            },
+            Self::Polygon { points, fill } => Self::Polygon {
+                points: points.into_iter().map(|p| p + offset).collect(),
+                fill,
+            },
+            Self::Text {
+                center,
+                size,
+                text,
+                color,
+                align,
+            } => Self::Text {
+                center: center + offset,
+                size,
+                text,
+                color,
+                align,
+            },
+        }
+    }
+}
+
+/// Where text is horizontally, from its `center`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Align {
+    /// Starting at the center
+    Left,
+    Center,
+}
+
+#[derive(Clone)]
+pub struct Diagram {
+    pub width: f32,
+    pub height: f32,
+    pub font: FontFamily,
+    /// From the bottom up
+    pub shapes: Vec<Shape>,
+}
+impl Diagram {
+    /// The current position of `game`
+    pub fn new(game: &Game, style: &BoardStyle, options: &Options) -> Self {
+        let board = &game.board;
+        let (w, h) = board.size();
+        let region = options
+            .region
+            .map_or(Region::whole(w, h), |r| r.clamp(w, h));
+
+        let s = options.spacing;
+        // the sizes in the style are scaled with the spacing
+        let k = s / STYLE_SPACING;
+        let layout = Layout::new(options, &region);
+        let margin = layout.margin;
+        let point = |x: usize, y: usize| layout.point(x, y);
+        let r = style.stone_radius * s;
+
+        let mut d = Self {
+            width: 2.0 * margin + (region.max.0 - region.min.0) as f32 * s,
+            height: 2.0 * margin + (region.max.1 - region.min.1) as f32 * s,
+            font: style.font.clone(),
+            shapes: Vec::new(),
+        };
+        let points: Vec<(usize, usize)> = (region.min.1..=region.max.1)
+            .flat_map(|y| (region.min.0..=region.max.0).map(move |x| (x, y)))
+            .collect();
+
+        d.shapes.push(Shape::Rect {
+            min: Pos2::ZERO,
+            size: vec2(d.width, d.height),
+            fill: style.background_color,
+        });
+
+        // lines that go on past the edge of the region stick out a little
+        let line = |from: Pos2, to: Pos2| Shape::Line {
+            from,
+            to,
+            width: style.line_thickness * k,
+            color: style.line_color,
+        };
+        let top = if region.min.1 > 0 { 0.5 * s } else { 0.0 };
+        let bottom = if region.max.1 < h - 1 { 0.5 * s } else { 0.0 };
+        for x in region.min.0..=region.max.0 {
+            let from = point(x, region.min.1) - vec2(0.0, top);
+            let to = point(x, region.max.1) + vec2(0.0, bottom);
+            d.shapes.push(line(from, to));
+        }
+        let left = if region.min.0 > 0 { 0.5 * s } else { 0.0 };
+        let right = if region.max.0 < w - 1 { 0.5 * s } else { 0.0 };
+        for y in region.min.1..=region.max.1 {
+            let from = point(region.min.0, y) - vec2(left, 0.0);
+            let to = point(region.max.0, y) + vec2(right, 0.0);
+            d.shapes.push(line(from, to));
+        }
+
+        for (x, y) in crate::util::star_points(w, h) {
+            if region.contains(x, y) {
+                d.shapes.push(Shape::Circle {
+                    center: point(x, y),
+                    radius: style.star_point_radius * k,
+                    fill: Some(style.line_color),
+                    stroke: None,
+                });
+            }
+        }
+
+        for &(x, y) in &points {
+            let stone = board.get(x, y);
+            if stone == Stone::Empty {
+                continue;
+            }
+
+            for (center, radius, color) in stone_circles(style, stone, point(x, y), r) {
+                d.shapes.push(Shape::Circle {
+                    center,
+                    radius,
+                    fill: Some(color),
+                    stroke: None,
+                });
+            }
+            if style.outline > 0.0 {
+                d.shapes.push(Shape::Circle {
+                    center: point(x, y),
+                    radius: r,
+                    fill: None,
+                    stroke: Some((style.outline * k, style.line_color)),
+                });
+            }
+        }
+
+        for &(x, y) in &points {
+            d.markup(
+                board.get_markup(x, y),
+                board.get(x, y),
+                point(x, y),
+                style,
+                s,
+            );
+        }
+
+        d.overlays(game, style, options, &region, point);
+        d.coordinates(
+            options.overlays.coordinates,
+            (w, h),
+            &region,
+            margin,
+            style,
+            point,
+        );
+
+        d
+    }
+
+    /// Writes a png if the path ends with `.png`, or svg otherwise
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let png = path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("png"));
+
+        let bytes = if png {
+            to_png(self)?
+        } else {
+            to_svg(self).into_bytes()
+        };
+
+        std::fs::write(path, bytes).map_err(|e| e.to_string())
+    }
+
+    /// Draws `numbers`, as `(x, y, number)`, on the stones of a diagram of `board`
+    /// that was drawn with `options` and without move numbers
+    pub fn add_numbers(
+        &mut self,
+        board: &Board,
+        numbers: &[(usize, usize, usize)],
+        style: &BoardStyle,
+        options: &Options,
+    ) {
+        let (w, h) = board.size();
+        let region = options
+            .region
+            .map_or(Region::whole(w, h), |r| r.clamp(w, h));
+        let layout = Layout::new(options, &region);
+        let r = style.stone_radius * options.spacing;
+
+        for &(x, y, n) in numbers {
+            if region.contains(x, y) {
+                let color = contrast(style.stone_color(board.get(x, y)));
+                self.shapes
+                    .push(move_number(layout.point(x, y), n, r, color));
+            }
+        }
+    }
+
+    /// Adds `lines` lines of text below the board, with as much of `text` as fits
+    pub fn add_caption(&mut self, text: &str, lines: usize, style: &BoardStyle, spacing: f32) {
+        let size = 0.5 * spacing;
+        let line_height = 1.4 * size;
+        let top = self.height;
+        self.height += lines as f32 * line_height + 0.5 * spacing;
+
+        self.shapes.push(Shape::Rect {
+            min: pos2(0.0, top),
+            size: vec2(self.width, self.height - top),
+            fill: style.background_color,
+        });
+
+        // roughly the width of a character
+        let per_line = ((self.width - spacing) / (0.6 * size)).max(1.0) as usize;
+        let mut wrapped = wrap(text, per_line);
+        if wrapped.len() > lines && lines > 0 {
+            wrapped.truncate(lines);
+            wrapped[lines - 1].push_str("...");
+        }
+
+        for (i, line) in wrapped.into_iter().enumerate() {
+            self.shapes.push(Shape::Text {
+                center: pos2(0.5 * self.width, top + (i as f32 + 0.5) * line_height),
+                size,
+                text: line,
+                color: style.line_color,
+                align: Align::Center,
+            });
+        }
+    }
+
+    fn markup(&mut self, markup: Markup, stone: Stone, center: Pos2, style: &BoardStyle, s: f32) {
+        let r = 0.75 * style.stone_radius * s;
+        let width = 0.05 * s;
+        let color = style.markup_color;
+        let stroke = |from: Pos2, to: Pos2| Shape::Line {
+            from,
+            to,
+            width,
+            color,
+        };
+
+        match markup {
+            Markup::Empty => {}
+            Markup::Circle => self.shapes.push(Shape::Circle {
+                center,
+                radius: r,
+                fill: None,
+                stroke: Some((width, color)),
+            }),
+            Markup::Cross => {
+                self.shapes
+                    .push(stroke(center + vec2(-r, -r), center + vec2(r, r)));
+                self.shapes
+                    .push(stroke(center + vec2(r, -r), center + vec2(-r, r)));
+            }
+            Markup::Square => {
+                let corners = [vec2(-r, -r), vec2(r, -r), vec2(r, r), vec2(-r, r)];
+                for i in 0..4 {
+                    self.shapes
+                        .push(stroke(center + corners[i], center + corners[(i + 1) % 4]));
+                }
+            }
+            Markup::Triangle => {
+                let corners = [vec2(0.0, -r), vec2(r, r), vec2(-r, r)];
+                for i in 0..3 {
+                    self.shapes
+                        .push(stroke(center + corners[i], center + corners[(i + 1) % 3]));
+                }
+            }
+            Markup::Dim => self.shapes.push(Shape::Rect {
+                min: center - vec2(0.5 * s, 0.5 * s),
+                size: vec2(s, s),
+                fill: Color32::from_rgba_unmultiplied(100, 100, 100, 100),
+            }),
+            // drawn with the overlays, since they cross other points
+            Markup::Arrow(_, _) | Markup::Line(_, _) => {}
+            Markup::Label(text) => {
+                // labels on empty points hide the lines under them
+                if stone == Stone::Empty {
+                    self.shapes.push(Shape::Circle {
+                        center,
+                        radius: r,
+                        fill: Some(style.background_color),
+                        stroke: None,
+                    });
+                }
+                self.shapes.push(Shape::Text {
+                    center,
+                    size: 1.2 * style.stone_radius * s,
+                    text,
+                    color,
+                    align: Align::Center,
+                });
+            }
+        }
+    }
+
+    fn overlays(
+        &mut self,
+        game: &Game,
+        style: &BoardStyle,
+        options: &Options,
+        region: &Region,
+        point: impl Fn(usize, usize) -> Pos2,
+    ) {
+        let board = &game.board;
+        let s = options.spacing;
+        let r = style.stone_radius * s;
+
+        // arrows and lines go over everything else on the board, and may leave the region
+        for y in region.min.1..=region.max.1 {
+            for x in region.min.0..=region.max.0 {
+                let (ex, ey, arrow) = match board.get_markup(x, y) {
+                    Markup::Arrow(ex, ey) => (ex, ey, true),
+                    Markup::Line(ex, ey) => (ex, ey, false),
+                    _ => continue,
+                };
+                let from = point(x, y);
+                let to = pos2(
+                    from.x + (ex as f32 - x as f32) * s,
+                    from.y + (ey as f32 - y as f32) * s,
+                );
+                self.shapes.push(Shape::Line {
+                    from,
+                    to,
+                    width: 0.1 * s,
+                    color: style.markup_color,
+                });
+
+                if arrow && from != to {
+                    let back = (from - to).normalized() * 0.375 * s;
+                    let side = vec2(-back.y, back.x) * 0.5;
+                    self.shapes.push(Shape::Polygon {
+                        points: vec![to, to + back + side, to + back - side],
+                        fill: style.markup_color,
+                    });
+                }
+            }
+        }
+
+        let last = game.last_move().filter(|_| options.overlays.last_move);
+        let mut last_numbered = false;
+
+        for (x, y, n) in game.move_numbers(options.overlays.numbering) {
+            // markup is more important than the number
+            if !region.contains(x, y) || board.get_markup(x, y) != Markup::Empty {
+                continue;
+            }
+
+            let color = if last == Some((x, y)) {
+                last_numbered = true;
+                style.markup_color
+            } else {
+                contrast(style.stone_color(board.get(x, y)))
+            };
+            self.shapes.push(move_number(point(x, y), n, r, color));
+        }
+
+        if let Some((x, y)) = last.filter(|&(x, y)| !last_numbered && region.contains(x, y)) {
+            self.shapes.push(Shape::Circle {
+                center: point(x, y),
+                radius: 0.5 * r,
+                fill: None,
+                stroke: Some((0.05 * s, contrast(style.stone_color(board.get(x, y))))),
+            });
+        }
+    }
+
+    fn coordinates(
+        &mut self,
+        coordinates: Coordinates,
+        (w, h): (usize, usize),
+        region: &Region,
+        margin: f32,
+        style: &BoardStyle,
+        point: impl Fn(usize, usize) -> Pos2,
+    ) {
+        let column: &dyn Fn(usize) -> String = match coordinates {
+            Coordinates::Off => return,
+            Coordinates::Gtp if w <= 25 => &|x| crate::gtp::column_letter(x).to_string(),
+            Coordinates::Gtp => &|x| (x + 1).to_string(),
+            Coordinates::Sgf => &|x| crate::sgf::to_sgf_coord(x).unwrap_or('?').to_string(),
+        };
+        let row: &dyn Fn(usize) -> String = match coordinates {
+            Coordinates::Sgf => &|y| crate::sgf::to_sgf_coord(y).unwrap_or('?').to_string(),
+            _ => &|y| (h - y).to_string(),
+        };
+
+        let size = 0.4 * margin;
+        let mut text = |center: Pos2, text: String| {
+            self.shapes.push(Shape::Text {
+                center,
+                size,
+                text,
+                color: style.line_color,
+                align: Align::Center,
+            })
+        };
+
+        // the labels go in the middle of the margins
+        let (first, last) = (
+            point(region.min.0, region.min.1),
+            point(region.max.0, region.max.1),
+        );
+        for x in region.min.0..=region.max.0 {
+            let px = point(x, region.min.1).x;
+            text(pos2(px, first.y - 0.5 * margin), column(x));
+            text(pos2(px, last.y + 0.5 * margin), column(x));
+        }
+        for y in region.min.1..=region.max.1 {
+            let py = point(region.min.0, y).y;
+            text(pos2(first.x - 0.5 * margin, py), row(y));
+            text(pos2(last.x + 0.5 * margin, py), row(y));
+        }
+    }
+}
+
+/// Where the points of a region are drawn
+struct Layout {
+    /// Around the board, for the coordinates
+    margin: f32,
+    spacing: f32,
+    min: (usize, usize),
+}
+impl Layout {
+    fn new(options: &Options, region: &Region) -> Self {
+        let s = options.spacing;
+
+        Self {
+            margin: match options.overlays.coordinates {
+                Coordinates::Off => 0.7 * s,
+                _ => 1.4 * s,
+            },
+            spacing: s,
+            min: region.min,
+        }
+    }
+
+    fn point(&self, x: usize, y: usize) -> Pos2 {
+        pos2(
+            self.margin + (x - self.min.0) as f32 * self.spacing,
+            self.margin + (y - self.min.1) as f32 * self.spacing,
+        )
+    }
+}
+
+/// The number of a move, on a stone of radius `r`
+fn move_number(center: Pos2, n: usize, r: f32, color: Color32) -> Shape {
+    Shape::Text {
+        center,
+        size: if n < 100 { 1.0 } else { 0.75 } * r,
+        text: n.to_string(),
+        color,
+        align: Align::Center,
+    }
+}
+
+/// A number for svg and pdf, without needless digits
+fn num(x: f32) -> String {
+    let s = format!("{:.2}", x);
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Splits `text` into lines of at most `width` characters, between words where it can
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+
+            // words longer than a line are broken up
+            while line.chars().count() > width {
+                let rest: String = line.chars().skip(width).collect();
+                line = line.chars().take(width).collect();
+                lines.push(std::mem::replace(&mut line, rest));
+            }
+        }
+
+        if !line.is_empty() {
+            lines.push(line);
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod diagram_tests {
+    use super::*;
+    use crate::game::Numbering;
+    use crate::SgfTree;
+
+    fn game(moves: &str) -> Game {
+        let tree = SgfTree::parse(format!("(;GM[1]SZ[9]{})", moves)).unwrap();
+        let mut game = Game::from_tree(tree, None);
+        while game.tree.select_child(0).is_ok() {}
+        game.do_to_now();
+
+        game
+    }
+
+    #[test]
+    fn region() {
+        assert_eq!(
+            Region::parse("ec:ba"),
+            Some(Region {
+                min: (1, 0),
+                max: (4, 2)
+            })
+        );
+        assert_eq!(Region::parse("aa"), None);
+
+        let r = Region::parse("aa:zz").unwrap().clamp(9, 9);
+        assert_eq!(r, Region::whole(9, 9));
+    }
+
+    #[test]
+    fn wrapping() {
+        assert_eq!(
+            wrap("a good move\nblack resigns", 6),
+            vec!["a good", "move", "black", "resign", "s"]
+        );
+        assert_eq!(wrap("", 6), Vec::<String>::new());
+    }
+
+    #[test]
+    fn shapes() {
+        let game = game(";B[cc];W[dd]CR[cc]");
+        let style = BoardStyle::default();
+
+        let whole = Diagram::new(&game, &style, &Options::default());
+        assert_eq!(whole.width, 2.0 * 0.7 * 40.0 + 8.0 * 40.0);
+        let lines = |d: &Diagram| {
+            d.shapes
+                .iter()
+                .filter(|s| matches!(s, Shape::Line { .. }))
+                .count()
+        };
+        assert_eq!(lines(&whole), 18);
+
+        // the circle on c3, and the last move marker on d4
+        let circles = whole
+            .shapes
+            .iter()
+            .filter(|s| matches!(s, Shape::Circle { fill: None, .. }))
+            .count();
+        assert_eq!(circles, 2);
+
+        let options = Options {
+            region: Some(Region::parse("aa:cc").unwrap()),
+            overlays: Overlays {
+                numbering: Numbering::All,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let corner = Diagram::new(&game, &style, &options);
+        assert_eq!(corner.height, 2.0 * 0.7 * 40.0 + 2.0 * 40.0);
+        assert_eq!(lines(&corner), 6);
+
+        // the only number in the corner is hidden by the circle
+        let texts = corner
+            .shapes
+            .iter()
+            .filter(|s| matches!(s, Shape::Text { .. }))
+            .count();
+        assert_eq!(texts, 0);
+    }
+}
@@ -0,0 +1,342 @@
+//! A small pdf writer for diagrams, one page each, with the standard
+//! Helvetica and Courier fonts so that nothing needs to be embedded.
+//! Those only have Latin-1, so text in other scripts, such as names in Chinese,
+//! Japanese or Korean, has to be written as svg instead.
+
+use std::fmt::Write;
+
+use eframe::egui::{Color32, FontFamily, Pos2};
+
+use super::{num, Align, Diagram, Shape};
+
+/// How far the control points of a quarter circle bezier are from its ends, per radius
+const KAPPA: f32 = 0.552_284_8;
+
+/// The pdf, or an error if some text has a character the fonts don't have
+pub fn to_pdf(pages: &[Diagram]) -> Result<Vec<u8>, String> {
+    let missing = pages
+        .iter()
+        .flat_map(|p| &p.shapes)
+        .filter_map(|shape| match shape {
+            Shape::Text { text, .. } => text.chars().find(|&c| !c.is_control() && !encodable(c)),
+            _ => None,
+        })
+        .next();
+    if let Some(c) = missing {
+        return Err(format!(
+            "the pdf fonts can't show `{}`, so save it as svg instead",
+            c
+        ));
+    }
+
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+
+    // 1 is the catalog, 2 the page tree, 3 and 4 the fonts, and then each page and its contents
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    let kids: Vec<String> = (0..pages.len())
+        .map(|i| format!("{} 0 R", 5 + 2 * i))
+        .collect();
+    objects.push(
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+    );
+    for font in ["Helvetica", "Courier"] {
+        objects.push(
+            format!(
+                "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+                font
+            )
+            .into_bytes(),
+        );
+    }
+
+    for (i, page) in pages.iter().enumerate() {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                num(page.width),
+                num(page.height),
+                6 + 2 * i
+            )
+            .into_bytes(),
+        );
+
+        let content = content(page);
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend(content);
+        stream.extend(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    for (i, o) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", i + 1).into_bytes());
+        pdf.extend(o);
+        pdf.extend(b"\nendobj\n");
+    }
+
+    let xref = pdf.len();
+    let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for o in offsets {
+        let _ = writeln!(trailer, "{:010} 00000 n ", o);
+    }
+    let _ = write!(
+        trailer,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    );
+    pdf.extend(trailer.into_bytes());
+
+    Ok(pdf)
+}
+
+/// The drawing operators for a page
+fn content(d: &Diagram) -> Vec<u8> {
+    let (font, widths): (&str, fn(char) -> f32) = match d.font {
+        FontFamily::Monospace => ("F2", |_| 0.6),
+        _ => ("F1", helvetica_width),
+    };
+
+    // pdf's y axis goes up from the bottom of the page
+    let mut s = format!("1 0 0 -1 0 {} cm\n1 J 1 j\n", num(d.height));
+
+    for shape in &d.shapes {
+        let _ = match shape {
+            Shape::Rect { min, size, fill } => writeln!(
+                s,
+                "{} rg {} {} {} {} re f",
+                color(*fill),
+                num(min.x),
+                num(min.y),
+                num(size.x),
+                num(size.y)
+            ),
+            Shape::Line {
+                from,
+                to,
+                width,
+                color: c,
+            } => writeln!(
+                s,
+                "{} w {} RG {} {} m {} {} l S",
+                num(*width),
+                color(*c),
+                num(from.x),
+                num(from.y),
+                num(to.x),
+                num(to.y)
+            ),
+            Shape::Circle {
+                center,
+                radius,
+                fill,
+                stroke,
+            } => {
+                let op = match (fill, stroke) {
+                    (Some(_), Some(_)) => "B",
+                    (Some(_), None) => "f",
+                    (None, Some(_)) => "S",
+                    (None, None) => continue,
+                };
+                if let Some(c) = fill {
+                    let _ = write!(s, "{} rg ", color(*c));
+                }
+                if let Some((w, c)) = stroke {
+                    let _ = write!(s, "{} w {} RG ", num(*w), color(*c));
+                }
+                writeln!(s, "{} {}", circle(*center, *radius), op)
+            }
+            Shape::Polygon { points, fill } => {
+                let _ = write!(s, "{} rg", color(*fill));
+                for (i, p) in points.iter().enumerate() {
+                    let op = if i == 0 { "m" } else { "l" };
+                    let _ = write!(s, " {} {} {}", num(p.x), num(p.y), op);
+                }
+                writeln!(s, " h f")
+            }
+            Shape::Text {
+                center,
+                size,
+                text,
+                color: c,
+                align,
+            } => {
+                let width: f32 = text.chars().map(widths).sum::<f32>() * size;
+                let x = match align {
+                    Align::Left => center.x,
+                    Align::Center => center.x - 0.5 * width,
+                };
+                // roughly half the height of a capital letter below the middle
+                let baseline = center.y + 0.35 * size;
+
+                writeln!(
+                    s,
+                    "BT /{} {} Tf {} rg 1 0 0 -1 {} {} Tm ({}) Tj ET",
+                    font,
+                    num(*size),
+                    color(*c),
+                    num(x),
+                    num(baseline),
+                    escape(text)
+                )
+            }
+        };
+    }
+
+    s.into_bytes()
+}
+
+/// Four beziers around `center`
+fn circle(center: Pos2, r: f32) -> String {
+    let (x, y, k) = (center.x, center.y, KAPPA * r);
+
+    format!(
+        "{} {} m {} {} {} {} {} {} c {} {} {} {} {} {} c {} {} {} {} {} {} c {} {} {} {} {} {} c",
+        num(x + r),
+        num(y),
+        num(x + r),
+        num(y + k),
+        num(x + k),
+        num(y + r),
+        num(x),
+        num(y + r),
+        num(x - k),
+        num(y + r),
+        num(x - r),
+        num(y + k),
+        num(x - r),
+        num(y),
+        num(x - r),
+        num(y - k),
+        num(x - k),
+        num(y - r),
+        num(x),
+        num(y - r),
+        num(x + k),
+        num(y - r),
+        num(x + r),
+        num(y - k),
+        num(x + r),
+        num(y),
+    )
+}
+
+/// The colour as pdf rgb, on white paper if it is see-through
+fn color(c: Color32) -> String {
+    let [r, g, b, a] = c.to_srgba_unmultiplied();
+    let a = a as f32 / 255.0;
+    let channel = |v: u8| num((v as f32 * a + 255.0 * (1.0 - a)) / 255.0);
+
+    format!("{} {} {}", channel(r), channel(g), channel(b))
+}
+
+/// If the fonts' encoding has `c`
+fn encodable(c: char) -> bool {
+    matches!(c, ' '..='~' | '\u{a0}'..='\u{ff}')
+}
+
+/// A pdf string in the fonts' encoding, with control characters as `?`
+fn escape(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '(' | ')' | '\\' => format!("\\{}", c),
+            ' '..='~' => c.to_string(),
+            // latin-1 is the same in the encoding, and is written as octal escapes
+            '\u{a0}'..='\u{ff}' => format!("\\{:03o}", c as u32),
+            _ => String::from("?"),
+        })
+        .collect()
+}
+
+/// The advance of a character in Helvetica, per em
+fn helvetica_width(c: char) -> f32 {
+    let w = match c {
+        ' ' | '!' | ',' | '.' | '/' | ':' | ';' | '[' | '\\' | ']' | 'f' | 't' => 278,
+        'i' | 'j' | 'l' | '\'' => 222,
+        '"' => 355,
+        '%' => 889,
+        '&' | 'A' | 'B' | 'E' | 'K' | 'P' | 'S' | 'V' | 'X' | 'Y' => 667,
+        '(' | ')' | '-' | '`' | 'r' => 333,
+        '*' => 389,
+        '+' | '<' | '=' | '>' | '~' => 584,
+        '@' => 1015,
+        'C' | 'D' | 'H' | 'N' | 'R' | 'U' | 'w' => 722,
+        'F' | 'T' | 'Z' => 611,
+        'G' | 'O' | 'Q' => 778,
+        'I' => 278,
+        'J' | 'c' | 'k' | 's' | 'v' | 'x' | 'y' | 'z' => 500,
+        'L' => 556,
+        'M' | 'm' => 833,
+        'W' => 944,
+        '^' => 469,
+        '{' | '}' => 334,
+        '|' => 260,
+        _ => 556,
+    };
+
+    w as f32 / 1000.0
+}
+
+#[cfg(test)]
+mod pdf_tests {
+    use super::*;
+    use eframe::egui::{pos2, vec2};
+
+    #[test]
+    fn pdf() {
+        let page = Diagram {
+            width: 100.0,
+            height: 50.0,
+            font: FontFamily::Proportional,
+            shapes: vec![
+                Shape::Rect {
+                    min: pos2(0.0, 0.0),
+                    size: vec2(100.0, 50.0),
+                    fill: Color32::WHITE,
+                },
+                Shape::Text {
+                    center: pos2(10.0, 20.0),
+                    size: 10.0,
+                    text: String::from("1 (at) é"),
+                    color: Color32::BLACK,
+                    align: Align::Left,
+                },
+            ],
+        };
+        let pdf = to_pdf(&[page.clone(), page.clone()]).unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.contains("/Count 2"));
+        assert!(text.contains("/MediaBox [0 0 100 50]"));
+        assert!(text.contains("(1 \\(at\\) \\351) Tj"));
+        assert!(text.ends_with("%%EOF\n"));
+
+        // the cross reference table points at each object
+        let xref = text.find("xref\n").unwrap();
+        let offsets: Vec<usize> = text[xref..]
+            .lines()
+            .skip(3)
+            .take_while(|l| !l.starts_with("trailer"))
+            .map(|l| l[..10].parse().unwrap())
+            .collect();
+        assert_eq!(offsets.len(), 8);
+        for (i, o) in offsets.into_iter().enumerate() {
+            assert!(text[o..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+
+        let mut korean = page;
+        if let Shape::Text { text, .. } = &mut korean.shapes[1] {
+            *text = String::from("이세돌");
+        }
+        assert!(to_pdf(&[korean]).unwrap_err().contains("svg"));
+    }
+}
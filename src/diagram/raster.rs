@@ -0,0 +1,239 @@
+use ab_glyph::{Font, FontVec, ScaleFont};
+use eframe::egui::{self, Color32, FontFamily};
+use tiny_skia::{
+    FillRule, LineCap, Paint, PathBuilder, Pixmap, PremultipliedColorU8, Rect, Stroke, Transform,
+};
+
+use super::{Align, Diagram, Shape};
+
+/// Draws the diagram on the cpu, and encodes it as png
+pub fn to_png(d: &Diagram) -> Result<Vec<u8>, String> {
+    render(d)?.encode_png().map_err(|e| e.to_string())
+}
+
+/// Draws the diagram on the cpu, as `(width, height, rgba pixels)`
+pub fn to_rgba(d: &Diagram) -> Result<(u32, u32, Vec<u8>), String> {
+    let pixmap = render(d)?;
+    let rgba = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|p| {
+            let c = p.demultiply();
+            [c.red(), c.green(), c.blue(), c.alpha()]
+        })
+        .collect();
+
+    Ok((pixmap.width(), pixmap.height(), rgba))
+}
+
+fn render(d: &Diagram) -> Result<Pixmap, String> {
+    let mut pixmap = Pixmap::new(d.width.ceil() as u32, d.height.ceil() as u32)
+        .ok_or_else(|| String::from("the diagram is empty"))?;
+    let font = font(&d.font)?;
+
+    for shape in &d.shapes {
+        // a shape with no area, such as an empty polygon, is skipped
+        let _ = match shape {
+            Shape::Rect { min, size, fill } => Rect::from_xywh(min.x, min.y, size.x, size.y)
+                .and_then(|r| pixmap.fill_rect(r, &paint(*fill), Transform::identity(), None)),
+            Shape::Line {
+                from,
+                to,
+                width,
+                color,
+            } => {
+                let mut pb = PathBuilder::new();
+                pb.move_to(from.x, from.y);
+                pb.line_to(to.x, to.y);
+
+                pb.finish().and_then(|p| {
+                    pixmap.stroke_path(
+                        &p,
+                        &paint(*color),
+                        &stroke(*width),
+                        Transform::identity(),
+                        None,
+                    )
+                })
+            }
+            Shape::Circle {
+                center,
+                radius,
+                fill,
+                stroke: outline,
+            } => PathBuilder::from_circle(center.x, center.y, *radius).map(|p| {
+                if let Some(c) = fill {
+                    pixmap.fill_path(
+                        &p,
+                        &paint(*c),
+                        FillRule::Winding,
+                        Transform::identity(),
+                        None,
+                    );
+                }
+                if let Some((w, c)) = outline {
+                    pixmap.stroke_path(&p, &paint(*c), &stroke(*w), Transform::identity(), None);
+                }
+            }),
+            Shape::Polygon { points, fill } => {
+                let mut pb = PathBuilder::new();
+                for (i, p) in points.iter().enumerate() {
+                    if i == 0 {
+                        pb.move_to(p.x, p.y);
+                    } else {
+                        pb.line_to(p.x, p.y);
+                    }
+                }
+                pb.close();
+
+                pb.finish().and_then(|p| {
+                    pixmap.fill_path(
+                        &p,
+                        &paint(*fill),
+                        FillRule::Winding,
+                        Transform::identity(),
+                        None,
+                    )
+                })
+            }
+            Shape::Text {
+                center,
+                size,
+                text,
+                color,
+                align,
+            } => {
+                draw_text(&mut pixmap, &font, *center, *size, text, *color, *align);
+                Some(())
+            }
+        };
+    }
+
+    Ok(pixmap)
+}
+
+fn paint(c: Color32) -> Paint<'static> {
+    let [r, g, b, a] = c.to_srgba_unmultiplied();
+    let mut p = Paint::default();
+    p.set_color_rgba8(r, g, b, a);
+    p.anti_alias = true;
+
+    p
+}
+
+fn stroke(width: f32) -> Stroke {
+    Stroke {
+        width,
+        line_cap: LineCap::Round,
+        ..Default::default()
+    }
+}
+
+/// The font egui uses for `family`, so diagrams look like the board
+fn font(family: &FontFamily) -> Result<FontVec, String> {
+    let fonts = egui::FontDefinitions::default();
+    let data = fonts
+        .families
+        .get(family)
+        .and_then(|names| names.first())
+        .and_then(|name| fonts.font_data.get(name))
+        .ok_or_else(|| format!("no font for {:?}", family))?;
+
+    FontVec::try_from_vec_and_index(data.font.to_vec(), data.index).map_err(|e| e.to_string())
+}
+
+/// Draws `text` on `center`, `size` pixels to the em
+fn draw_text(
+    pixmap: &mut Pixmap,
+    font: &FontVec,
+    center: egui::Pos2,
+    size: f32,
+    text: &str,
+    color: Color32,
+    align: Align,
+) {
+    let scaled = font.as_scaled(font.pt_to_px_scale(size).unwrap_or(size.into()));
+    let glyphs: Vec<_> = text.chars().map(|c| font.glyph_id(c)).collect();
+    let width: f32 = glyphs.iter().map(|&g| scaled.h_advance(g)).sum();
+
+    let mut x = match align {
+        Align::Left => center.x,
+        Align::Center => center.x - 0.5 * width,
+    };
+    let baseline = center.y + 0.5 * (scaled.ascent() + scaled.descent());
+
+    for id in glyphs {
+        let glyph = id.with_scale_and_position(scaled.scale(), ab_glyph::point(x, baseline));
+        x += scaled.h_advance(id);
+
+        if let Some(outline) = font.outline_glyph(glyph) {
+            let bounds = outline.px_bounds();
+            outline.draw(|gx, gy, coverage| {
+                let px = bounds.min.x as i32 + gx as i32;
+                let py = bounds.min.y as i32 + gy as i32;
+                blend(pixmap, px, py, color, coverage);
+            });
+        }
+    }
+}
+
+/// Draws `color` over the pixel at `(x, y)`, `coverage` of the way
+fn blend(pixmap: &mut Pixmap, x: i32, y: i32, color: Color32, coverage: f32) {
+    let (w, h) = (pixmap.width() as i32, pixmap.height() as i32);
+    if x < 0 || y < 0 || x >= w || y >= h {
+        return;
+    }
+
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    let alpha = a as f32 / 255.0 * coverage.clamp(0.0, 1.0);
+    let pixel = &mut pixmap.pixels_mut()[(y * w + x) as usize];
+
+    // the pixels are premultiplied
+    let over = |src: u8, dst: u8| (src as f32 * alpha + dst as f32 * (1.0 - alpha)).round() as u8;
+    let blended = PremultipliedColorU8::from_rgba(
+        over(r, pixel.red()),
+        over(g, pixel.green()),
+        over(b, pixel.blue()),
+        (alpha * 255.0 + pixel.alpha() as f32 * (1.0 - alpha)).round() as u8,
+    );
+
+    if let Some(p) = blended {
+        *pixel = p;
+    }
+}
+
+#[cfg(test)]
+mod raster_tests {
+    use super::*;
+    use crate::diagram::{Diagram, Options};
+    use crate::ui::BoardStyle;
+    use crate::{Game, SgfTree};
+
+    #[test]
+    fn png() {
+        let tree = SgfTree::parse(String::from("(;GM[1]SZ[9];B[ee]LB[cc:A])")).unwrap();
+        let mut game = Game::from_tree(tree, None);
+        game.tree.select_child(0).unwrap();
+        game.do_to_now();
+
+        let d = Diagram::new(&game, &BoardStyle::default(), &Options::default());
+        let png = to_png(&d).unwrap();
+
+        let image = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!(image.width(), d.width.ceil() as u32);
+
+        // the black stone in the middle
+        let center = image.get_pixel(image.width() / 2, image.height() / 2);
+        assert_eq!(center.0, [0, 0, 0, 255]);
+
+        // the label is drawn in the markup colour somewhere around c7
+        let (cx, cy) = (28 + 2 * 40, 28 + 2 * 40);
+        let labelled = (cx - 10..cx + 10)
+            .flat_map(|x| (cy - 10..cy + 10).map(move |y| (x, y)))
+            .any(|(x, y)| {
+                let p = image.get_pixel(x, y).0;
+                p[0] > 200 && p[1] < 80 && p[2] < 80
+            });
+        assert!(labelled);
+    }
+}
@@ -0,0 +1,166 @@
+use std::fmt::Write;
+
+use eframe::egui::{Color32, FontFamily};
+
+use super::{num, Align, Diagram, Shape};
+
+pub fn to_svg(d: &Diagram) -> String {
+    let mut s = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = num(d.width),
+        h = num(d.height),
+    );
+    s.push('\n');
+
+    let family = match d.font {
+        FontFamily::Monospace => "monospace",
+        _ => "sans-serif",
+    };
+
+    for shape in &d.shapes {
+        // writing to a String can't fail
+        let _ = match shape {
+            Shape::Rect { min, size, fill } => writeln!(
+                s,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" {}/>"#,
+                num(min.x),
+                num(min.y),
+                num(size.x),
+                num(size.y),
+                paint("fill", *fill),
+            ),
+            Shape::Line {
+                from,
+                to,
+                width,
+                color,
+            } => writeln!(
+                s,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke-width="{}" stroke-linecap="round" {}/>"#,
+                num(from.x),
+                num(from.y),
+                num(to.x),
+                num(to.y),
+                num(*width),
+                paint("stroke", *color),
+            ),
+            Shape::Circle {
+                center,
+                radius,
+                fill,
+                stroke,
+            } => {
+                let fill = match fill {
+                    Some(c) => paint("fill", *c),
+                    None => String::from(r#"fill="none""#),
+                };
+                let stroke = match stroke {
+                    Some((w, c)) => {
+                        format!(r#" stroke-width="{}" {}"#, num(*w), paint("stroke", *c))
+                    }
+                    None => String::new(),
+                };
+
+                writeln!(
+                    s,
+                    r#"<circle cx="{}" cy="{}" r="{}" {}{}/>"#,
+                    num(center.x),
+                    num(center.y),
+                    num(*radius),
+                    fill,
+                    stroke,
+                )
+            }
+            Shape::Polygon { points, fill } => {
+                let points: Vec<String> = points
+                    .iter()
+                    .map(|p| format!("{},{}", num(p.x), num(p.y)))
+                    .collect();
+
+                writeln!(
+                    s,
+                    r#"<polygon points="{}" {}/>"#,
+                    points.join(" "),
+                    paint("fill", *fill),
+                )
+            }
+            Shape::Text {
+                center,
+                size,
+                text,
+                color,
+                align,
+            } => writeln!(
+                s,
+                r#"<text x="{}" y="{}" font-family="{}" font-size="{}" text-anchor="{}" dominant-baseline="central" {}>{}</text>"#,
+                num(center.x),
+                num(center.y),
+                family,
+                num(*size),
+                match align {
+                    Align::Left => "start",
+                    Align::Center => "middle",
+                },
+                paint("fill", *color),
+                escape(text),
+            ),
+        };
+    }
+
+    s.push_str("</svg>\n");
+    s
+}
+
+/// The `attribute` (fill or stroke) set to `c`, with its opacity if it is see-through
+fn paint(attribute: &str, c: Color32) -> String {
+    let [r, g, b, a] = c.to_srgba_unmultiplied();
+    let mut s = format!(r##"{}="#{:02x}{:02x}{:02x}""##, attribute, r, g, b);
+    if a < 255 {
+        let _ = write!(s, r#" {}-opacity="{}""#, attribute, num(a as f32 / 255.0));
+    }
+
+    s
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod svg_tests {
+    use super::*;
+    use eframe::egui::pos2;
+
+    #[test]
+    fn svg() {
+        let d = Diagram {
+            width: 100.0,
+            height: 50.5,
+            font: FontFamily::Monospace,
+            shapes: vec![
+                Shape::Circle {
+                    center: pos2(10.0, 10.0),
+                    radius: 4.0,
+                    fill: None,
+                    stroke: Some((1.5, Color32::from_black_alpha(128))),
+                },
+                Shape::Text {
+                    center: pos2(20.0, 20.0),
+                    size: 12.0,
+                    text: String::from("<A>"),
+                    color: Color32::RED,
+                    align: Align::Center,
+                },
+            ],
+        };
+
+        let expected = r##"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="50.5" viewBox="0 0 100 50.5">
+<circle cx="10" cy="10" r="4" fill="none" stroke-width="1.5" stroke="#000000" stroke-opacity="0.5"/>
+<text x="20" y="20" font-family="monospace" font-size="12" text-anchor="middle" dominant-baseline="central" fill="#ff0000">&lt;A&gt;</text>
+</svg>
+"##;
+        assert_eq!(to_svg(&d), expected);
+    }
+}
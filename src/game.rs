@@ -1,9 +1,47 @@
 use crate::board::Markup;
+use crate::clock::{GameClock, TimeControl};
+use crate::flood_fill::find_group;
+use crate::scoring::{area_score_with_dead, GameResult, Score};
 use crate::sgf::{Action, SgfResult, SgfTree};
 use crate::Board;
 use crate::Stone;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
+use std::time::Instant;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameState {
+    InProgress,
+    /// Both players passed, and dead stones are being marked
+    Counting,
+    /// The result has been written to `RE`
+    Finished,
+}
+
+/// Which stones show the number of the move that played them
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Numbering {
+    Off,
+    All,
+    /// The last n moves
+    Last(usize),
+    /// The moves since the last node with more than one variation
+    SinceVariation,
+}
+
+/// A move in another variation, to show on the board
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Variation {
+    /// Index of the node on the tree
+    pub node: usize,
+    pub x: usize,
+    pub y: usize,
+    pub stone: Stone,
+    /// A for the first variation, B for the second...
+    pub label: char,
+}
 
 /// Contains the Board and additional info about the game
 /// that can be manipulated by actions in the ui
@@ -13,6 +51,17 @@ pub struct Game {
 
     pub tree: crate::SgfTree,
     pub path: Option<PathBuf>,
+
+    /// None if the game isn't timed
+    pub clock: Option<GameClock>,
+
+    pub state: GameState,
+    /// Allows editing a finished game
+    pub analysis: bool,
+    /// Stones marked as dead while counting
+    pub dead: HashSet<(usize, usize)>,
+    /// The tree as it was when the game was last saved or opened
+    saved: SgfTree,
 }
 impl Game {
     pub fn do_action(&mut self, a: &Action) {
@@ -43,8 +92,8 @@ impl Game {
                 }
             }
 
-            Action::PassBlack => {}
-            Action::PassWhite => {}
+            Action::PassBlack => self.turn = Stone::White,
+            Action::PassWhite => self.turn = Stone::Black,
             Action::Size(_, _) => {}
 
             Action::Circle(v) => {
@@ -97,13 +146,328 @@ impl Game {
         }
     }
 
+    /// The komi from the root node's `KM` property, or 0 if there isn't one
+    pub fn komi(&self) -> f32 {
+        self.tree
+            .root()
+            .get_prop("KM")
+            .and_then(|k| k.trim().parse().ok())
+            .unwrap_or(0.0)
+    }
+
+    /// Called after `color` has moved: switches the clocks and records
+    /// `color`'s remaining time (`BL`/`WL`, `OB`/`OW`) in the current node
+    pub fn press_clock(&mut self, color: Stone) {
+        let clock = match &mut self.clock {
+            Some(c) => c,
+            None => return,
+        };
+
+        let c = clock.press(color, Instant::now());
+        let (time_prop, overtime_prop) = match color {
+            Stone::White => ("WL", "OW"),
+            _ => ("BL", "OB"),
+        };
+
+        let node = self.tree.current_node_mut();
+        node.set_prop(time_prop, format!("{:.1}", c.time_left().as_secs_f32()));
+        if let Some(o) = c.overtime_left() {
+            node.set_prop(overtime_prop, o.to_string());
+        }
+    }
+
+    /// Stops the clocks if someone has run out of time and ends the game.
+    /// Returns the player who lost on time.
+    pub fn check_timeout(&mut self) -> Option<Stone> {
+        let clock = self.clock.as_ref()?;
+        clock.running()?;
+
+        let loser = clock.flagged(Instant::now())?;
+        self.finish(GameResult::Time(!loser));
+
+        Some(loser)
+    }
+
+    /// If moves and markup can be added to the tree
+    pub fn is_editable(&self) -> bool {
+        match self.state {
+            GameState::InProgress => true,
+            GameState::Counting => false,
+            GameState::Finished => self.analysis,
+        }
+    }
+
+    /// Ends the game, stopping the clocks and writing `result` to `RE`
+    pub fn finish(&mut self, result: GameResult) {
+        if let Some(c) = &mut self.clock {
+            c.stop(Instant::now());
+        }
+
+        self.tree.root_mut().set_prop("RE", result.to_string());
+        self.state = GameState::Finished;
+        self.dead.clear();
+    }
+
+    pub fn resign(&mut self, color: Stone) {
+        self.finish(GameResult::Resign(!color));
+    }
+
+    /// Starts counting if the current node and its parent are both passes
+    pub fn check_passes(&mut self) {
+        if self.state != GameState::InProgress {
+            return;
+        }
+
+        let is_pass = |v: &Vec<Action>| {
+            v.iter()
+                .any(|a| matches!(a, Action::PassBlack | Action::PassWhite))
+        };
+
+        let all = self.tree.get_all_parent_action();
+        if all.len() >= 2 && is_pass(&all[0]) && is_pass(&all[1]) {
+            if let Some(c) = &mut self.clock {
+                c.stop(Instant::now());
+            }
+
+            self.state = GameState::Counting;
+            self.dead.clear();
+        }
+    }
+
+    /// Marks or unmarks the group at (`x`, `y`) as dead while counting
+    pub fn toggle_dead(&mut self, x: usize, y: usize) {
+        let s = self.board.get(x, y);
+        if self.state != GameState::Counting || s == Stone::Empty {
+            return;
+        }
+
+        let group = find_group(&self.board, x, y, s).inside;
+        if self.dead.contains(&(x, y)) {
+            for p in group {
+                self.dead.remove(&p);
+            }
+        } else {
+            self.dead.extend(group);
+        }
+    }
+
+    /// The area score, not counting the dead stones
+    pub fn count(&self) -> Score {
+        area_score_with_dead(&self.board, &self.dead)
+    }
+
+    /// The result the count would give
+    pub fn count_result(&self) -> GameResult {
+        GameResult::from_score(self.count(), self.komi())
+    }
+
+    /// Accepts the count and ends the game
+    pub fn accept_count(&mut self) {
+        self.finish(self.count_result());
+    }
+
+    /// Goes back to playing after counting was started
+    pub fn resume(&mut self) {
+        self.state = GameState::InProgress;
+        self.dead.clear();
+
+        if let Some(c) = &mut self.clock {
+            c.start(self.turn, Instant::now());
+        }
+    }
+
+    /// A game showing the root of an existing tree
+    pub fn from_tree(tree: SgfTree, path: Option<PathBuf>) -> Self {
+        let mut size = (19, 19);
+
+        for a in &tree.root().actions {
+            if let Action::Size(w, h) = a {
+                size = (*w, *h);
+            }
+        }
+
+        // a game that already has a result is opened read-only
+        let state = match tree.root().get_prop("RE").and_then(GameResult::parse) {
+            Some(_) => GameState::Finished,
+            None => GameState::InProgress,
+        };
+
+        let mut game = Self {
+            board: Board::new(size.0, size.1),
+            saved: tree.clone(),
+            tree,
+            turn: Stone::Black,
+            path,
+            clock: None,
+            state,
+            analysis: false,
+            dead: HashSet::new(),
+        };
+        game.tree.select_root();
+        game.do_to_now();
+
+        game
+    }
+
+    /// Takes back the last move, which stays in the tree as a variation
+    pub fn undo(&mut self) -> bool {
+        if self.tree.select_parent().is_err() {
+            return false;
+        }
+
+        self.do_to_now();
+        true
+    }
+
+    /// The points of the moves from the root to the current node, with how many
+    /// of them were played since the last node with more than one variation.
+    /// Passes are counted, but have no point.
+    fn moves_to_now(&self) -> (Vec<Option<(usize, usize)>>, usize) {
+        let mut path = vec![self.tree.current_index()];
+        while let Some(p) = self
+            .tree
+            .node(path[path.len() - 1])
+            .and_then(|n| n.parent())
+        {
+            path.push(p);
+        }
+        path.reverse();
+
+        let mut moves = Vec::new();
+        let mut since_variation = 0;
+        for (k, &i) in path.iter().enumerate() {
+            let node = match self.tree.node(i) {
+                Some(n) => n,
+                None => break,
+            };
+
+            for a in &node.actions {
+                match a {
+                    Action::PlayBlack(x, y) | Action::PlayWhite(x, y) => {
+                        moves.push(Some((*x, *y)));
+                        since_variation += 1;
+                    }
+                    Action::PassBlack | Action::PassWhite => {
+                        moves.push(None);
+                        since_variation += 1;
+                    }
+                    _ => {}
+                }
+            }
+
+            if k + 1 < path.len() && node.children().len() > 1 {
+                since_variation = 0;
+            }
+        }
+
+        (moves, since_variation)
+    }
+
+    /// The number to show on each stone that is still on the board, as `(x, y, number)`
+    pub fn move_numbers(&self, numbering: Numbering) -> Vec<(usize, usize, usize)> {
+        let (moves, since_variation) = self.moves_to_now();
+        let shown = match numbering {
+            Numbering::Off => return Vec::new(),
+            Numbering::All => moves.len(),
+            Numbering::Last(n) => n,
+            Numbering::SinceVariation => since_variation,
+        };
+        let first = moves.len().saturating_sub(shown) + 1;
+
+        // only the latest move at a point is shown, if its stone hasn't been captured
+        let mut latest = HashMap::new();
+        for (i, p) in moves.into_iter().enumerate() {
+            if let Some(p) = p {
+                latest.insert(p, i + 1);
+            }
+        }
+
+        let mut numbers: Vec<_> = latest
+            .into_iter()
+            .filter(|&((x, y), n)| n >= first && self.board.get(x, y) != Stone::Empty)
+            .map(|((x, y), n)| (x, y, n))
+            .collect();
+        numbers.sort_by_key(|&(_, _, n)| n);
+
+        numbers
+    }
+
+    /// The moves of the variations to show on the board, following the root's `ST`:
+    /// the current node's children, or its siblings with `ST[1]`, and none with `ST[2]`.
+    /// Nothing is shown where there is only one line of play.
+    pub fn variations(&self) -> Vec<Variation> {
+        let st: u8 = self
+            .tree
+            .root()
+            .get_prop("ST")
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(0);
+        if st & 2 != 0 {
+            return Vec::new();
+        }
+
+        let current = self.tree.current_index();
+        let nodes = if st & 1 != 0 {
+            match self.tree.current_node().parent() {
+                Some(p) => self.tree.node(p).map(|n| n.children()).unwrap_or(&[]),
+                None => &[],
+            }
+        } else {
+            self.tree.current_node().children()
+        };
+        if nodes.len() < 2 {
+            return Vec::new();
+        }
+
+        let mut variations = Vec::new();
+        for (i, &node) in nodes.iter().enumerate() {
+            if node == current {
+                continue;
+            }
+
+            let point = self.tree.node(node).and_then(|n| {
+                n.actions.iter().find_map(|a| match a {
+                    Action::PlayBlack(x, y) => Some((*x, *y, Stone::Black)),
+                    Action::PlayWhite(x, y) => Some((*x, *y, Stone::White)),
+                    _ => None,
+                })
+            });
+
+            if let Some((x, y, stone)) = point {
+                variations.push(Variation {
+                    node,
+                    x,
+                    y,
+                    stone,
+                    label: (b'A' + (i % 26) as u8) as char,
+                });
+            }
+        }
+
+        variations
+    }
+
+    /// The point played in the current node, if any
+    pub fn last_move(&self) -> Option<(usize, usize)> {
+        self.tree
+            .current_node()
+            .actions
+            .iter()
+            .find_map(|a| match a {
+                Action::PlayBlack(x, y) | Action::PlayWhite(x, y) => Some((*x, *y)),
+                _ => None,
+            })
+    }
+
     /// Starting from a blank board, performs all the actions up to this point in `self.tree`
     pub fn do_to_now(&mut self) {
         let (w, h) = self.board.size();
         self.board = Board::new(w, h);
         self.turn = Stone::Black;
 
-        let all = self.tree.get_all_parent_action();
+        let mut all = self.tree.get_all_parent_action();
+        // the root isn't included, but it can have setup stones
+        all.push(self.tree.root().actions.clone());
 
         for s in all.iter().rev() {
             self.board.clear_markup();
@@ -113,24 +477,51 @@ impl Game {
         }
     }
 
-    pub fn write_to_file(&mut self) -> SgfResult<()> {
-        if let Some(p) = &self.path {
-            fs::write(p, self.tree.to_text().as_bytes())?;
-        } else {
-            // TODO: you can't cancel saving the file
-            self.path = rfd::FileDialog::new()
-                .add_filter("sgf", &["sgf"])
-                .save_file();
-            self.write_to_file()?;
+    /// If the tree has changed since it was last saved or opened
+    pub fn is_modified(&self) -> bool {
+        !self.tree.same_nodes(&self.saved)
+    }
+
+    /// Makes `is_modified` compare against `saved` rather than the tree the game started with,
+    /// or against nothing if there is no saved copy
+    pub fn set_saved(&mut self, saved: Option<SgfTree>) {
+        self.saved = saved.unwrap_or_default();
+    }
+
+    /// Saves the game to `path`, asking for one if there is none yet.
+    /// Returns false if the user cancelled choosing the path.
+    pub fn write_to_file(&mut self) -> SgfResult<bool> {
+        match self.path.clone() {
+            Some(p) => self.write_to(p),
+            None => self.save_as(),
         }
+    }
 
-        Ok(())
+    /// Asks for a new path and saves the game there.
+    /// Returns false if the user cancelled, in which case the path isn't changed.
+    pub fn save_as(&mut self) -> SgfResult<bool> {
+        match rfd::FileDialog::new()
+            .add_filter("sgf", &["sgf"])
+            .save_file()
+        {
+            Some(p) => self.write_to(p),
+            None => Ok(false),
+        }
+    }
+
+    fn write_to(&mut self, path: PathBuf) -> SgfResult<bool> {
+        fs::write(&path, self.tree.to_text().as_bytes())?;
+        self.path = Some(path);
+        self.saved = self.tree.clone();
+
+        Ok(true)
     }
 }
 
 pub struct GameBuilder {
     pub size: (usize, usize),
     pub path: Option<PathBuf>,
+    pub time_control: Option<TimeControl>,
 }
 impl GameBuilder {
     pub fn build(&self) -> Game {
@@ -150,11 +541,28 @@ impl GameBuilder {
             crate::log(format!("FAILED TO SET ROOT WITH {:?}", e));
         }
 
+        let clock = self.time_control.map(|t| {
+            tree.root_mut()
+                .set_prop("TM", t.main().as_secs().to_string());
+            if let Some(ot) = t.overtime_text() {
+                tree.root_mut().set_prop("OT", ot);
+            }
+
+            let mut c = GameClock::new(t);
+            c.start(Stone::Black, Instant::now());
+            c
+        });
+
         Game {
             board: Board::new(self.size.0, self.size.1),
             turn: Stone::Black,
+            saved: tree.clone(),
             tree,
             path: self.path.clone(),
+            clock,
+            state: GameState::InProgress,
+            analysis: false,
+            dead: HashSet::new(),
         }
     }
 }
@@ -164,26 +572,181 @@ impl Default for GameBuilder {
         Self {
             size: (19, 19),
             path: None,
+            time_control: None,
         }
     }
 }
 
-fn build_game_from_path(p: PathBuf) -> SgfResult<Game> {
+pub fn build_game_from_path(p: PathBuf) -> SgfResult<Game> {
+    // imported games have no path, so that saving them asks where to put the sgf
+    if let Some(format) = crate::import::Format::from_path(&p) {
+        return Ok(Game::from_tree(crate::import::read(&p, format)?, None));
+    }
+
     let s = fs::read_to_string(&p)?;
     let tree = SgfTree::parse(s)?;
 
-    let mut size = (19, 19);
+    Ok(Game::from_tree(tree, Some(p)))
+}
+
+#[cfg(test)]
+mod game_tests {
+    use super::*;
+
+    #[test]
+    fn two_passes_then_count() {
+        let mut game = GameBuilder {
+            size: (5, 5),
+            ..Default::default()
+        }
+        .build();
 
-    for a in &tree.root().actions {
-        if let Action::Size(w, h) = a {
-            size = (*w, *h);
+        // + + b w +
+        // + + b w +
+        // + + b w +
+        // + + b w w
+        // + + b w +
+        for y in 0..5 {
+            game.board.set(2, y, Stone::Black);
+            game.board.set(3, y, Stone::White);
         }
+        game.board.set(4, 3, Stone::White);
+
+        game.tree.handle_new_action(Action::PassBlack, true);
+        game.check_passes();
+        assert_eq!(game.state, GameState::InProgress);
+
+        game.tree.handle_new_action(Action::PassWhite, true);
+        game.check_passes();
+        assert_eq!(game.state, GameState::Counting);
+        assert!(!game.is_editable());
+
+        game.toggle_dead(3, 0);
+        assert_eq!(game.dead.len(), 6);
+        game.toggle_dead(3, 3);
+        assert!(game.dead.is_empty());
+
+        game.accept_count();
+        assert_eq!(game.state, GameState::Finished);
+        assert_eq!(game.tree.root().get_prop("RE"), Some("B+5"));
+
+        game.analysis = true;
+        assert!(game.is_editable());
+    }
+
+    #[test]
+    fn undo() {
+        let mut game = GameBuilder::default().build();
+
+        game.board.attempt_set(3, 3, Stone::Black);
+        game.tree.handle_new_action(Action::PlayBlack(3, 3), true);
+        game.tree.handle_new_action(Action::PassWhite, true);
+        game.do_to_now();
+        assert_eq!(game.turn, Stone::Black);
+
+        assert!(game.undo());
+        assert_eq!(game.turn, Stone::White);
+        assert_eq!(game.board.get(3, 3), Stone::Black);
+
+        assert!(game.undo());
+        assert_eq!(game.board.get(3, 3), Stone::Empty);
+        assert!(!game.undo());
+    }
+
+    #[test]
+    fn resign() {
+        let mut game = GameBuilder::default().build();
+
+        game.resign(Stone::White);
+
+        assert_eq!(game.state, GameState::Finished);
+        assert_eq!(game.tree.root().get_prop("RE"), Some("B+R"));
     }
 
-    Ok(Game {
-        board: Board::new(size.0, size.1),
-        tree,
-        turn: Stone::Black,
-        path: Some(p),
-    })
+    #[test]
+    fn numbering() {
+        // b[aa] is captured by w[ab] and w[ba], then w plays a variation
+        let tree = SgfTree::parse(String::from(
+            "(;SZ[5];B[aa];W[ab];B[cc];W[ba];B[];W[dd](;B[ee];W[ed])(;B[de]))",
+        ))
+        .unwrap();
+        let mut game = Game::from_tree(tree, None);
+        while game.tree.select_child(0).is_ok() {}
+        game.do_to_now();
+
+        let all = game.move_numbers(Numbering::All);
+        assert_eq!(
+            all,
+            vec![
+                (0, 1, 2),
+                (2, 2, 3),
+                (1, 0, 4),
+                (3, 3, 6),
+                (4, 4, 7),
+                (4, 3, 8)
+            ]
+        );
+        assert_eq!(
+            game.move_numbers(Numbering::Last(3)),
+            vec![(3, 3, 6), (4, 4, 7), (4, 3, 8)]
+        );
+        assert_eq!(
+            game.move_numbers(Numbering::SinceVariation),
+            vec![(4, 4, 7), (4, 3, 8)]
+        );
+        assert_eq!(game.move_numbers(Numbering::Off), vec![]);
+        assert_eq!(game.last_move(), Some((4, 3)));
+    }
+
+    #[test]
+    fn variation_display() {
+        let text = |st: &str| format!("(;SZ[9]{}(;B[aa](;W[bb])(;W[cc]))(;B[dd])(;B[]))", st);
+
+        let mut game = Game::from_tree(SgfTree::parse(text("")).unwrap(), None);
+        let v = game.variations();
+        assert_eq!(v.len(), 2);
+        assert_eq!(
+            (v[1].x, v[1].y, v[1].stone, v[1].label),
+            (3, 3, Stone::Black, 'B')
+        );
+
+        // the first move has two children, but no siblings are shown for it
+        game.tree.select_child(0).unwrap();
+        let labels: Vec<char> = game.variations().iter().map(|v| v.label).collect();
+        assert_eq!(labels, vec!['A', 'B']);
+
+        let mut game = Game::from_tree(SgfTree::parse(text("ST[1]")).unwrap(), None);
+        assert_eq!(game.variations(), vec![]);
+        game.tree.select_child(0).unwrap();
+        let v = game.variations();
+        assert_eq!(v.len(), 1);
+        assert_eq!((v[0].x, v[0].y, v[0].label), (3, 3, 'B'));
+
+        let game = Game::from_tree(SgfTree::parse(text("ST[2]")).unwrap(), None);
+        assert_eq!(game.variations(), vec![]);
+    }
+
+    #[test]
+    fn modified() {
+        let dir = std::env::temp_dir().join(format!("go-modified-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("game.sgf");
+        fs::write(&path, "(;GM[1]SZ[9];B[aa])").unwrap();
+
+        let mut game = build_game_from_path(path.clone()).unwrap();
+        assert!(!game.is_modified());
+
+        // moving around the tree isn't a change
+        game.tree.select_child(0).unwrap();
+        assert!(!game.is_modified());
+
+        game.tree.handle_new_action(Action::PlayWhite(1, 1), true);
+        assert!(game.is_modified());
+
+        assert!(game.write_to_file().unwrap());
+        assert!(!game.is_modified());
+        assert!(fs::read_to_string(&path).unwrap().contains("W[bb]"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
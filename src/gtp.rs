@@ -0,0 +1,337 @@
+//! A minimal client for engines speaking the
+//! [Go Text Protocol](https://www.lysator.liu.se/~gunnar/gtp/gtp2-spec-draft2/gtp2-spec.html)
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use crate::Stone;
+
+#[derive(Debug)]
+pub enum GtpError {
+    /// The command line for the engine was empty
+    EmptyCommand,
+    /// The engine closed its output
+    Eof,
+    /// The engine answered with `?`
+    Failure(String),
+    /// The engine didn't answer within `GtpEngine::timeout`
+    Timeout,
+
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for GtpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::EmptyCommand => write!(f, "empty engine command"),
+            Self::Eof => write!(f, "the engine closed its output"),
+            Self::Failure(e) => write!(f, "the engine failed: {}", e),
+            Self::Timeout => write!(f, "the engine stopped responding"),
+            Self::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for GtpError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+pub type GtpResult<T> = Result<T, GtpError>;
+
+/// A move as sent or received over gtp
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Vertex {
+    Point(usize, usize),
+    Pass,
+    Resign,
+}
+impl Vertex {
+    /// Parses a gtp vertex like `D4` on a board of height `h`.
+    /// Columns skip the letter I, and rows count up from the bottom.
+    pub fn parse(s: &str, h: usize) -> Option<Self> {
+        let s = s.trim().to_uppercase();
+
+        match s.as_str() {
+            "PASS" => return Some(Self::Pass),
+            "RESIGN" => return Some(Self::Resign),
+            _ => {}
+        }
+
+        let mut chars = s.chars();
+        let col = chars.next()?;
+        if !col.is_ascii_uppercase() || col == 'I' {
+            return None;
+        }
+
+        let mut x = (col as u8 - b'A') as usize;
+        if col > 'I' {
+            x -= 1;
+        }
+
+        let row: usize = chars.as_str().parse().ok()?;
+        if row == 0 || row > h {
+            return None;
+        }
+
+        Some(Self::Point(x, h - row))
+    }
+
+    /// Formats as a gtp vertex on a board of height `h`
+    pub fn to_gtp(self, h: usize) -> String {
+        match self {
+            Self::Pass => String::from("pass"),
+            Self::Resign => String::from("resign"),
+            Self::Point(x, y) => format!("{}{}", column_letter(x), h - y),
+        }
+    }
+}
+
+/// The letter of column `x` in a gtp vertex, which skips I.
+/// Only boards up to 25 wide have letters for every column.
+pub fn column_letter(x: usize) -> char {
+    let mut col = b'A' + x as u8;
+    if col >= b'I' {
+        col += 1;
+    }
+
+    col as char
+}
+
+/// The gtp name of a colour
+pub fn color_name(s: Stone) -> &'static str {
+    match s {
+        Stone::White => "white",
+        _ => "black",
+    }
+}
+
+/// How long an engine gets to answer `quit` before it is killed
+const QUIT_GRACE: Duration = Duration::from_secs(2);
+
+/// An engine running as a child process
+pub struct GtpEngine {
+    child: Child,
+    stdin: ChildStdin,
+    /// The engine's output a line at a time, read on another thread so that waiting can time out
+    lines: Receiver<std::io::Result<String>>,
+    /// How long to wait for each response
+    pub timeout: Duration,
+}
+impl GtpEngine {
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
+
+    /// Starts the engine from a command line such as `gnugo --mode gtp`
+    pub fn spawn(command: &str) -> GtpResult<Self> {
+        let words = split_command(command);
+        let (program, args) = words.split_first().ok_or(GtpError::EmptyCommand)?;
+
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        // both were requested as piped above
+        let stdin = child.stdin.take().unwrap();
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+        let (send, lines) = mpsc::channel();
+        std::thread::spawn(move || loop {
+            let mut line = String::new();
+            let read = stdout.read_line(&mut line).map(|_| line);
+            let done = !matches!(&read, Ok(l) if !l.is_empty());
+            // stops when the engine closes its output, or nobody is listening
+            if send.send(read).is_err() || done {
+                break;
+            }
+        });
+
+        Ok(Self {
+            child,
+            stdin,
+            lines,
+            timeout: Self::DEFAULT_TIMEOUT,
+        })
+    }
+
+    /// Sends a command and returns the text of the response
+    pub fn send(&mut self, command: &str) -> GtpResult<String> {
+        writeln!(self.stdin, "{}", command)?;
+        self.stdin.flush()?;
+
+        let deadline = Instant::now() + self.timeout;
+        let mut response = String::new();
+        loop {
+            let wait = deadline.saturating_duration_since(Instant::now());
+            let line = match self.lines.recv_timeout(wait) {
+                Ok(line) => line?,
+                Err(RecvTimeoutError::Timeout) => return Err(GtpError::Timeout),
+                Err(RecvTimeoutError::Disconnected) => return Err(GtpError::Eof),
+            };
+            if line.is_empty() {
+                return Err(GtpError::Eof);
+            }
+
+            let line = line.trim_end();
+            if line.is_empty() {
+                if response.is_empty() {
+                    // stray blank line before the response
+                    continue;
+                }
+                break;
+            }
+
+            response.push_str(line);
+            response.push('\n');
+        }
+
+        parse_response(&response)
+    }
+
+    /// The engine's name and version, or `fallback` if it doesn't say
+    pub fn name(&mut self, fallback: &str) -> String {
+        let name = self.send("name").unwrap_or_else(|_| fallback.to_string());
+
+        match self.send("version") {
+            Ok(v) if !v.is_empty() => format!("{} {}", name, v),
+            _ => name,
+        }
+    }
+
+    pub fn genmove(&mut self, color: Stone, h: usize) -> GtpResult<Option<Vertex>> {
+        let r = self.send(&format!("genmove {}", color_name(color)))?;
+
+        Ok(Vertex::parse(&r, h))
+    }
+
+    pub fn play(&mut self, color: Stone, v: Vertex, h: usize) -> GtpResult<()> {
+        self.send(&format!("play {} {}", color_name(color), v.to_gtp(h)))?;
+
+        Ok(())
+    }
+}
+impl Drop for GtpEngine {
+    /// Asks the engine to quit, and kills it if it hasn't after `QUIT_GRACE`
+    fn drop(&mut self) {
+        let deadline = Instant::now() + QUIT_GRACE;
+        self.timeout = QUIT_GRACE;
+        let _ = self.send("quit");
+
+        while Instant::now() < deadline {
+            match self.child.try_wait() {
+                Ok(Some(_)) | Err(_) => return,
+                Ok(None) => std::thread::sleep(Duration::from_millis(20)),
+            }
+        }
+
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// The text of a response, which starts with `=` on success and `?` on failure.
+/// Anything else, such as stray output from a broken engine, is a failure too.
+fn parse_response(response: &str) -> GtpResult<String> {
+    match response.strip_prefix('=') {
+        Some(text) => Ok(text.trim().to_string()),
+        None => {
+            let text = response.strip_prefix('?').unwrap_or(response);
+            Err(GtpError::Failure(text.trim().to_string()))
+        }
+    }
+}
+
+/// Splits a command line on whitespace, keeping quoted sections together
+fn split_command(s: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote = None;
+
+    for c in s.chars() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, c) if c.is_whitespace() => {
+                if !word.is_empty() {
+                    words.push(word.clone());
+                    word.clear();
+                }
+            }
+            _ => word.push(c),
+        }
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+#[cfg(test)]
+mod gtp_tests {
+    use super::*;
+
+    #[test]
+    fn vertex_round_trip() {
+        assert_eq!(Vertex::parse("A19", 19), Some(Vertex::Point(0, 0)));
+        assert_eq!(Vertex::parse("t1", 19), Some(Vertex::Point(18, 18)));
+        assert_eq!(Vertex::parse("J9", 9), Some(Vertex::Point(8, 0)));
+        assert_eq!(Vertex::parse("PASS", 9), Some(Vertex::Pass));
+        assert_eq!(Vertex::parse("resign", 9), Some(Vertex::Resign));
+        assert_eq!(Vertex::parse("I5", 9), None);
+        assert_eq!(Vertex::parse("A10", 9), None);
+
+        assert_eq!(Vertex::Point(8, 0).to_gtp(9), "J9");
+        assert_eq!(Vertex::Point(3, 15).to_gtp(19), "D4");
+    }
+
+    #[test]
+    fn responses() {
+        assert_eq!(parse_response("= D4\n").unwrap(), "D4");
+        assert_eq!(parse_response("=\n").unwrap(), "");
+        assert!(matches!(
+            parse_response("? unknown command\n"),
+            Err(GtpError::Failure(e)) if e == "unknown command"
+        ));
+        // a multibyte first character is a failure, not a panic
+        assert!(matches!(
+            parse_response("é\n"),
+            Err(GtpError::Failure(e)) if e == "é"
+        ));
+    }
+
+    #[test]
+    fn hung_engine() {
+        let mut engine = GtpEngine::spawn("sh -c 'read c; echo \"= ok\"; echo; sleep 30'").unwrap();
+        engine.timeout = Duration::from_millis(200);
+
+        assert_eq!(engine.send("name").unwrap(), "ok");
+        assert!(matches!(
+            engine.send("genmove black"),
+            Err(GtpError::Timeout)
+        ));
+
+        // dropping doesn't wait for the sleep
+        let start = Instant::now();
+        drop(engine);
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn split_command_test() {
+        assert_eq!(
+            split_command("gnugo --mode gtp  --komi 7.5"),
+            vec!["gnugo", "--mode", "gtp", "--komi", "7.5"]
+        );
+        assert_eq!(
+            split_command("'my engine' -c \"a b\""),
+            vec!["my engine", "-c", "a b"]
+        );
+    }
+}
@@ -0,0 +1,301 @@
+//! Guessing the next move of a game record, to study professional games.
+//!
+//! The main line (the first child of each node) is hidden, and each guess is
+//! scored by its distance from the real move, which is then played.
+//! The score for each game is appended to a file as
+//! `<unix time>\t<exact>\t<near>\t<miss>\t<game>`.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::sgf::Action;
+use crate::{Game, Stone};
+
+/// Guesses at most this far from the real move are near
+pub const NEAR: usize = 2;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Grade {
+    Exact,
+    Near,
+    Miss,
+}
+
+/// Grades a guess by its distance from the real move,
+/// counting diagonal steps as one
+pub fn grade(guess: (usize, usize), actual: (usize, usize)) -> (Grade, usize) {
+    let d = guess.0.abs_diff(actual.0).max(guess.1.abs_diff(actual.1));
+
+    let g = match d {
+        0 => Grade::Exact,
+        d if d <= NEAR => Grade::Near,
+        _ => Grade::Miss,
+    };
+
+    (g, d)
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Record {
+    /// Seconds since the unix epoch
+    pub time: u64,
+    /// Usually the file name of the game
+    pub game: String,
+    pub exact: u32,
+    pub near: u32,
+    pub miss: u32,
+}
+impl Record {
+    pub fn total(&self) -> u32 {
+        self.exact + self.near + self.miss
+    }
+
+    /// The share of exact guesses, from 0 to 1
+    pub fn accuracy(&self) -> f32 {
+        if self.total() == 0 {
+            return 0.0;
+        }
+
+        self.exact as f32 / self.total() as f32
+    }
+
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(5, '\t');
+
+        Some(Self {
+            time: fields.next()?.parse().ok()?,
+            exact: fields.next()?.parse().ok()?,
+            near: fields.next()?.parse().ok()?,
+            miss: fields.next()?.parse().ok()?,
+            game: fields.next()?.to_string(),
+        })
+    }
+
+    /// The results kept in the app's data directory
+    pub fn default_path() -> Option<PathBuf> {
+        crate::util::data_dir().map(|d| d.join("guesses.tsv"))
+    }
+
+    /// Every record in the file at `path`, or none if it doesn't exist yet
+    pub fn load_all(path: &Path) -> io::Result<Vec<Self>> {
+        match fs::read_to_string(path) {
+            Ok(t) => Ok(t.lines().filter_map(Self::parse).collect()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn append(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut f = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(f, "{}", self)
+    }
+}
+impl std::fmt::Display for Record {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}\t{}\t{}",
+            self.time, self.exact, self.near, self.miss, self.game
+        )
+    }
+}
+
+/// The result of the last guess
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Feedback {
+    pub grade: Grade,
+    pub distance: usize,
+    pub actual: (usize, usize),
+}
+
+pub struct Guessing {
+    /// The colour whose moves are guessed, or `None` for both
+    pub side: Option<Stone>,
+    pub record: Record,
+    pub last: Option<Feedback>,
+}
+impl Guessing {
+    /// Starts from the root of `game`'s tree
+    pub fn start(game: &mut Game, side: Option<Stone>) -> Self {
+        game.tree.select_root();
+        game.do_to_now();
+
+        let name = game
+            .path
+            .as_ref()
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| String::from("untitled"));
+
+        let g = Self {
+            side,
+            record: Record {
+                game: name,
+                ..Default::default()
+            },
+            last: None,
+        };
+        g.skip(game);
+
+        g
+    }
+
+    /// If there are no more moves to guess
+    pub fn is_over(&self, game: &Game) -> bool {
+        game.tree.num_children() == 0
+    }
+
+    /// Grades a guess at the next move and plays the real one.
+    /// Returns `None` if the game is over.
+    pub fn guess(&mut self, game: &mut Game, x: usize, y: usize) -> Option<Feedback> {
+        let actual = match next_move(game)? {
+            Action::PlayBlack(x, y) | Action::PlayWhite(x, y) => (x, y),
+            _ => return None,
+        };
+
+        let (grade, distance) = grade((x, y), actual);
+        match grade {
+            Grade::Exact => self.record.exact += 1,
+            Grade::Near => self.record.near += 1,
+            Grade::Miss => self.record.miss += 1,
+        }
+
+        advance(game);
+        self.skip(game);
+
+        let f = Feedback {
+            grade,
+            distance,
+            actual,
+        };
+        self.last = Some(f);
+
+        Some(f)
+    }
+
+    /// Plays the moves that aren't guessed: passes, nodes without moves,
+    /// and the other side's moves
+    fn skip(&self, game: &mut Game) {
+        while let Some(a) = next_move(game) {
+            let guessed = match (&a, self.side) {
+                (Action::PlayBlack(_, _) | Action::PlayWhite(_, _), None) => true,
+                (Action::PlayBlack(_, _) | Action::PlayWhite(_, _), Some(s)) => {
+                    crate::util::mover(&a) == Some(s)
+                }
+                _ => false,
+            };
+            if guessed {
+                return;
+            }
+
+            advance(game);
+        }
+    }
+
+    /// Ends the session, with the time of the record set to now
+    pub fn finish(mut self) -> Record {
+        self.record.time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        self.record
+    }
+}
+
+/// The move in the next main line node (`NoOp` if it doesn't have one),
+/// or `None` at the end of the main line
+fn next_move(game: &mut Game) -> Option<Action> {
+    let node = game.tree.select_child(0).ok()?;
+    let a = node
+        .actions
+        .iter()
+        .find(|a| crate::util::mover(a).is_some())
+        .cloned()
+        .unwrap_or(Action::NoOp);
+    let _ = game.tree.select_parent();
+
+    Some(a)
+}
+
+/// Goes to the next main line node, playing it with `Game::do_action`
+fn advance(game: &mut Game) {
+    let actions = match game.tree.select_child(0) {
+        Ok(n) => n.actions.clone(),
+        Err(_) => return,
+    };
+
+    game.board.clear_markup();
+    for a in &actions {
+        game.do_action(a);
+    }
+}
+
+#[cfg(test)]
+mod guess_tests {
+    use super::*;
+    use crate::SgfTree;
+
+    fn game() -> Game {
+        let tree = SgfTree::parse(String::from(
+            "(;SZ[9];B[cc];W[gg]C[variation follows](;B[cg];W[];B[gc])(;B[ee]))",
+        ))
+        .unwrap();
+
+        Game::from_tree(tree, None)
+    }
+
+    #[test]
+    fn grades() {
+        assert_eq!(grade((3, 3), (3, 3)), (Grade::Exact, 0));
+        assert_eq!(grade((3, 3), (5, 4)), (Grade::Near, 2));
+        assert_eq!(grade((3, 3), (15, 15)), (Grade::Miss, 12));
+    }
+
+    #[test]
+    fn main_line() {
+        let mut game = game();
+        let mut g = Guessing::start(&mut game, None);
+
+        assert_eq!(g.guess(&mut game, 2, 2).unwrap().grade, Grade::Exact);
+        assert_eq!(game.board.get(2, 2), Stone::Black);
+
+        let f = g.guess(&mut game, 5, 5).unwrap();
+        assert_eq!((f.grade, f.actual), (Grade::Near, (6, 6)));
+
+        // the first variation is the main line, and the pass is skipped
+        assert_eq!(g.guess(&mut game, 18, 0).unwrap().grade, Grade::Miss);
+        assert_eq!(game.board.get(2, 6), Stone::Black);
+        assert_eq!(game.turn, Stone::Black);
+
+        g.guess(&mut game, 6, 2);
+        assert!(g.is_over(&game));
+        assert_eq!(g.guess(&mut game, 0, 0), None);
+
+        let r = g.finish();
+        assert_eq!((r.exact, r.near, r.miss), (2, 1, 1));
+        assert_eq!(r.accuracy(), 0.5);
+        assert_eq!(Record::parse(&r.to_string()), Some(r));
+    }
+
+    #[test]
+    fn one_side() {
+        let mut game = game();
+        let mut g = Guessing::start(&mut game, Some(Stone::White));
+
+        // black's first move was played for us
+        assert_eq!(game.board.get(2, 2), Stone::Black);
+        g.guess(&mut game, 6, 6);
+        assert_eq!(g.record.exact, 1);
+
+        // and so were the rest, since they are all black's or passes
+        assert!(g.is_over(&game));
+        assert_eq!(game.board.get(6, 2), Stone::Black);
+    }
+}
@@ -0,0 +1,297 @@
+//! A game as a single html page with a board viewer, for people without the app.
+//!
+//! The tree is embedded as json, in the same order as `SgfTree`'s nodes:
+//!
+//! ```text
+//! {
+//!   "size": [19, 19],
+//!   "stars": [[3, 3], ...],
+//!   "info": {"PB": "...", ...},
+//!   "nodes": [
+//!     {
+//!       "parent": null, "children": [1, 4],
+//!       "move": {"color": "B", "point": [3, 15]},   point is null for a pass
+//!       "black": [[x, y], ...], "white": [...], "empty": [...],
+//!       "circle": [...], "cross": [...], "square": [...], "triangle": [...], "dim": [...],
+//!       "label": [[x, y, "A"], ...], "arrow": [[[x, y], [x, y]], ...], "line": [...],
+//!       "comment": "..."
+//!     },
+//!     ...
+//!   ]
+//! }
+//! ```
+//!
+//! with points from the top left, and fields that would be empty left out.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::sgf::Action;
+use crate::SgfTree;
+
+const CSS: &str = include_str!("viewer.css");
+const JS: &str = include_str!("viewer.js");
+
+/// The root properties shown above the board
+const INFO: [&str; 12] = [
+    "GN", "PB", "BR", "PW", "WR", "EV", "RO", "DT", "PC", "KM", "HA", "RE",
+];
+
+pub fn to_html(tree: &SgfTree) -> String {
+    let title = tree
+        .root()
+        .get_prop("GN")
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            let player = |p| tree.root().get_prop(p).unwrap_or("?");
+            format!("{} vs {}", player("PB"), player("PW"))
+        });
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title}</title>
+<style>
+{css}</style>
+</head>
+<body>
+<main>
+<h1 id="title"></h1>
+<dl id="info"></dl>
+<div id="board"></div>
+<nav>
+<button id="first" title="Home">&#x23EE;</button>
+<button id="back" title="Left arrow">&#x25C0;</button>
+<span id="move"></span>
+<button id="forward" title="Right arrow">&#x25B6;</button>
+<button id="last" title="End">&#x23ED;</button>
+</nav>
+<div id="variations"></div>
+<div id="comment"></div>
+</main>
+<script type="application/json" id="game">{json}</script>
+<script>
+{js}</script>
+</body>
+</html>
+"#,
+        title = escape_html(&title),
+        css = CSS,
+        json = to_json(tree),
+        js = JS,
+    )
+}
+
+pub fn write(tree: &SgfTree, path: &Path) -> Result<(), String> {
+    std::fs::write(path, to_html(tree)).map_err(|e| e.to_string())
+}
+
+/// The tree as the viewer's json
+pub fn to_json(tree: &SgfTree) -> String {
+    let (w, h) = tree
+        .root()
+        .actions
+        .iter()
+        .find_map(|a| match a {
+            Action::Size(w, h) => Some((*w, *h)),
+            _ => None,
+        })
+        .unwrap_or((19, 19));
+
+    let mut s = format!(
+        r#"{{"size":[{},{}],"stars":{},"info":{{"#,
+        w,
+        h,
+        points(&crate::util::star_points(w, h), (w, h))
+    );
+    let info: Vec<String> = INFO
+        .iter()
+        .filter_map(|&p| {
+            Some(format!(
+                "{}:{}",
+                string(p),
+                string(tree.root().get_prop(p)?)
+            ))
+        })
+        .collect();
+    s.push_str(&info.join(","));
+    s.push_str(r#"},"nodes":["#);
+
+    for i in 0..tree.num_nodes() {
+        if i > 0 {
+            s.push(',');
+        }
+        if let Some(n) = tree.node(i) {
+            s.push_str(&node(n, (w, h)));
+        }
+    }
+    s.push_str("]}");
+
+    s
+}
+
+fn node(n: &crate::sgf::SgfNode, (w, h): (usize, usize)) -> String {
+    let parent = n.parent().map_or(String::from("null"), |p| p.to_string());
+    let children: Vec<String> = n.children().iter().map(|c| c.to_string()).collect();
+    let mut s = format!(
+        r#"{{"parent":{},"children":[{}]"#,
+        parent,
+        children.join(",")
+    );
+
+    let mut field = |name: &str, value: String| {
+        let _ = write!(s, r#","{}":{}"#, name, value);
+    };
+    let on_board = |x: usize, y: usize| x < w && y < h;
+    let size = (w, h);
+
+    for a in &n.actions {
+        match a {
+            Action::PlayBlack(x, y) | Action::PlayWhite(x, y) => {
+                let color = if matches!(a, Action::PlayBlack(..)) {
+                    "B"
+                } else {
+                    "W"
+                };
+                // `tt` is a pass in older sgf
+                let point = if on_board(*x, *y) {
+                    format!("[{},{}]", x, y)
+                } else {
+                    String::from("null")
+                };
+                field(
+                    "move",
+                    format!(r#"{{"color":"{}","point":{}}}"#, color, point),
+                );
+            }
+            Action::PassBlack => field("move", String::from(r#"{"color":"B","point":null}"#)),
+            Action::PassWhite => field("move", String::from(r#"{"color":"W","point":null}"#)),
+            Action::AddBlack(v) => field("black", points(v, size)),
+            Action::AddWhite(v) => field("white", points(v, size)),
+            Action::Circle(v) => field("circle", points(v, size)),
+            Action::Cross(v) => field("cross", points(v, size)),
+            Action::Square(v) => field("square", points(v, size)),
+            Action::Triangle(v) => field("triangle", points(v, size)),
+            Action::Dim(v) => field("dim", points(v, size)),
+            Action::Label(v) => {
+                let labels: Vec<String> = v
+                    .iter()
+                    .filter(|(x, y, _)| on_board(*x, *y))
+                    .map(|(x, y, l)| format!("[{},{},{}]", x, y, string(l)))
+                    .collect();
+                field("label", format!("[{}]", labels.join(",")));
+            }
+            Action::Arrow(v) => field("arrow", pairs(v, size)),
+            Action::Line(v) => field("line", pairs(v, size)),
+            Action::Comment(c) => field("comment", string(c)),
+            Action::Other(k, v) if k == "AE" => {
+                field("empty", empty_points(std::slice::from_ref(v), size))
+            }
+            Action::OtherMany(k, v) if k == "AE" => field("empty", empty_points(v, size)),
+            _ => {}
+        }
+    }
+
+    s.push('}');
+    s
+}
+
+/// The points on a board of `(w, h)`, leaving out any past its edges,
+/// which the viewer would otherwise wrap onto the next row
+fn points(v: &[(usize, usize)], (w, h): (usize, usize)) -> String {
+    let points: Vec<String> = v
+        .iter()
+        .filter(|(x, y)| *x < w && *y < h)
+        .map(|(x, y)| format!("[{},{}]", x, y))
+        .collect();
+    format!("[{}]", points.join(","))
+}
+
+fn pairs(v: &[[(usize, usize); 2]], (w, h): (usize, usize)) -> String {
+    let pairs: Vec<String> = v
+        .iter()
+        .filter(|p| p.iter().all(|(x, y)| *x < w && *y < h))
+        .map(|[(ax, ay), (bx, by)]| format!("[[{},{}],[{},{}]]", ax, ay, bx, by))
+        .collect();
+    format!("[{}]", pairs.join(","))
+}
+
+/// The points of an `AE` property, which isn't parsed into an `Action`
+fn empty_points(values: &[String], size: (usize, usize)) -> String {
+    let v: Vec<(usize, usize)> = values
+        .iter()
+        .filter_map(|p| crate::sgf::string_coords(p).ok())
+        .collect();
+    points(&v, size)
+}
+
+/// A json string, which is also safe inside a `<script>`
+fn string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            // so that `</script>` can't end the data
+            '<' | '>' | '&' => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+
+    out
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod html_tests {
+    use super::*;
+
+    #[test]
+    fn json() {
+        let tree = SgfTree::parse(String::from(
+            "(;GM[1]SZ[9]PB[Alice]AB[cc][jc]C[a \"quote\"</script>];B[dd]LB[ee:A][ej:B](;W[]AE[cc][cj])(;W[tt]))",
+        ))
+        .unwrap();
+        let json = to_json(&tree);
+
+        assert!(json.starts_with(r#"{"size":[9,9],"stars":[[4,4],[2,2],"#));
+        assert!(json.contains(r#""info":{"PB":"Alice"}"#));
+        assert!(json.contains(
+            r#"{"parent":null,"children":[1],"black":[[2,2]],"comment":"a \"quote\"\u003c/script\u003e"}"#
+        ));
+        assert!(json.contains(
+            r#"{"parent":0,"children":[2,3],"move":{"color":"B","point":[3,3]},"label":[[4,4,"A"]]}"#
+        ));
+        assert!(json.contains(r#""move":{"color":"W","point":null},"empty":[[2,2]]"#));
+        // `tt` on a small board
+        assert!(json.ends_with(r#"{"parent":1,"children":[],"move":{"color":"W","point":null}}]}"#));
+    }
+
+    #[test]
+    fn page() {
+        let tree = SgfTree::parse(String::from("(;GM[1]SZ[9]GN[<b>Final</b>];B[ee])")).unwrap();
+        let html = to_html(&tree);
+
+        assert!(html.contains("<title>&lt;b&gt;Final&lt;/b&gt;</title>"));
+        assert!(html.contains(r#"<script type="application/json" id="game">{"size":[9,9]"#));
+        assert_eq!(html.matches("</script>").count(), 2);
+    }
+}
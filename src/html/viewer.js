@@ -0,0 +1,303 @@
+// A board viewer for the game in the `game` script tag, written by the go app.
+// The position of a node is found by playing every node from the root to it.
+(function () {
+  "use strict";
+
+  const game = JSON.parse(document.getElementById("game").textContent);
+  const [W, H] = game.size;
+  const nodes = game.nodes;
+  const EMPTY = 0, BLACK = 1, WHITE = 2;
+  const SPACING = 30, MARGIN = 22;
+  const NAMES = {
+    GN: "Game", PB: "Black", BR: "Black rank", PW: "White", WR: "White rank",
+    EV: "Event", RO: "Round", DT: "Date", PC: "Place", KM: "Komi", HA: "Handicap", RE: "Result",
+  };
+
+  let current = 0;
+  // the child last visited from each node, so that going forward follows it
+  const chosen = {};
+
+  function pathTo(i) {
+    const path = [];
+    for (let n = i; n !== null; n = nodes[n].parent) {
+      path.push(n);
+    }
+    return path.reverse();
+  }
+
+  function neighbours(p) {
+    const x = p % W, y = Math.floor(p / W);
+    const out = [];
+    if (x > 0) out.push(p - 1);
+    if (x < W - 1) out.push(p + 1);
+    if (y > 0) out.push(p - W);
+    if (y < H - 1) out.push(p + W);
+    return out;
+  }
+
+  // the stones connected to `p`, or null if they have a liberty
+  function deadGroup(board, p) {
+    const color = board[p];
+    const group = [p];
+    const seen = new Set(group);
+    for (let i = 0; i < group.length; i++) {
+      for (const n of neighbours(group[i])) {
+        if (board[n] === EMPTY) return null;
+        if (board[n] === color && !seen.has(n)) {
+          seen.add(n);
+          group.push(n);
+        }
+      }
+    }
+    return group;
+  }
+
+  function play(board, [x, y], color) {
+    const p = y * W + x;
+    board[p] = color;
+    let captures = 0;
+    for (const n of neighbours(p)) {
+      if (board[n] !== EMPTY && board[n] !== color) {
+        const dead = deadGroup(board, n);
+        if (dead) {
+          dead.forEach((d) => (board[d] = EMPTY));
+          captures += dead.length;
+        }
+      }
+    }
+    // suicide, which some rules allow
+    const own = deadGroup(board, p);
+    if (own) own.forEach((d) => (board[d] = EMPTY));
+    return captures;
+  }
+
+  function position(i) {
+    const board = new Array(W * H).fill(EMPTY);
+    const captures = { [BLACK]: 0, [WHITE]: 0 };
+    let moveNumber = 0;
+    let last = null;
+
+    for (const n of pathTo(i)) {
+      const node = nodes[n];
+      for (const [x, y] of node.black || []) board[y * W + x] = BLACK;
+      for (const [x, y] of node.white || []) board[y * W + x] = WHITE;
+      for (const [x, y] of node.empty || []) board[y * W + x] = EMPTY;
+      last = null;
+      if (node.move) {
+        moveNumber++;
+        if (node.move.point) {
+          const color = node.move.color === "B" ? BLACK : WHITE;
+          captures[color] += play(board, node.move.point, color);
+          last = node.move.point;
+        }
+      }
+    }
+
+    return { board, captures, moveNumber, last };
+  }
+
+  function svg(tag, attributes, text) {
+    const e = document.createElementNS("http://www.w3.org/2000/svg", tag);
+    for (const [k, v] of Object.entries(attributes)) e.setAttribute(k, v);
+    if (text !== undefined) e.textContent = text;
+    return e;
+  }
+
+  const cx = (x) => MARGIN + x * SPACING;
+  const cy = (y) => MARGIN + y * SPACING;
+
+  function drawBoard(state) {
+    const node = nodes[current];
+    const root = svg("svg", {
+      viewBox: `0 0 ${2 * MARGIN + (W - 1) * SPACING} ${2 * MARGIN + (H - 1) * SPACING}`,
+      class: "board",
+    });
+
+    for (let x = 0; x < W; x++) {
+      root.append(svg("line", { x1: cx(x), y1: cy(0), x2: cx(x), y2: cy(H - 1), class: "grid" }));
+    }
+    for (let y = 0; y < H; y++) {
+      root.append(svg("line", { x1: cx(0), y1: cy(y), x2: cx(W - 1), y2: cy(y), class: "grid" }));
+    }
+    for (const [x, y] of game.stars) {
+      root.append(svg("circle", { cx: cx(x), cy: cy(y), r: 3, class: "star" }));
+    }
+
+    const stoneAt = (x, y) => state.board[y * W + x];
+    for (let y = 0; y < H; y++) {
+      for (let x = 0; x < W; x++) {
+        const s = stoneAt(x, y);
+        if (s !== EMPTY) {
+          const cls = s === BLACK ? "black" : "white";
+          root.append(svg("circle", { cx: cx(x), cy: cy(y), r: 0.47 * SPACING, class: cls }));
+        }
+      }
+    }
+
+    // markup is drawn in the colour that stands out on what is under it
+    const ink = (x, y) => (stoneAt(x, y) === BLACK ? "mark on-black" : "mark");
+    const r = 0.3 * SPACING;
+    for (const [x, y] of node.circle || []) {
+      root.append(svg("circle", { cx: cx(x), cy: cy(y), r, class: ink(x, y) }));
+    }
+    for (const [x, y] of node.square || []) {
+      root.append(svg("rect", { x: cx(x) - r, y: cy(y) - r, width: 2 * r, height: 2 * r, class: ink(x, y) }));
+    }
+    for (const [x, y] of node.triangle || []) {
+      const points = `${cx(x)},${cy(y) - r} ${cx(x) + r},${cy(y) + 0.7 * r} ${cx(x) - r},${cy(y) + 0.7 * r}`;
+      root.append(svg("polygon", { points, class: ink(x, y) }));
+    }
+    for (const [x, y] of node.cross || []) {
+      const d = `M${cx(x) - r} ${cy(y) - r}L${cx(x) + r} ${cy(y) + r}M${cx(x) + r} ${cy(y) - r}L${cx(x) - r} ${cy(y) + r}`;
+      root.append(svg("path", { d, class: ink(x, y) }));
+    }
+    for (const [x, y] of node.dim || []) {
+      root.append(svg("rect", { x: cx(x) - SPACING / 2, y: cy(y) - SPACING / 2, width: SPACING, height: SPACING, class: "dim" }));
+    }
+    for (const [x, y, text] of node.label || []) {
+      if (stoneAt(x, y) === EMPTY) {
+        root.append(svg("circle", { cx: cx(x), cy: cy(y), r: 0.4 * SPACING, class: "label-background" }));
+      }
+      root.append(svg("text", { x: cx(x), y: cy(y), class: ink(x, y) + " label" }, text));
+    }
+    for (const [kind, lines] of [["arrow", node.arrow], ["line", node.line]]) {
+      for (const [[ax, ay], [bx, by]] of lines || []) {
+        const line = svg("line", { x1: cx(ax), y1: cy(ay), x2: cx(bx), y2: cy(by), class: "overlay" });
+        if (kind === "arrow") line.setAttribute("marker-end", "url(#head)");
+        root.append(line);
+      }
+    }
+
+    if (state.last) {
+      const [x, y] = state.last;
+      root.append(svg("circle", { cx: cx(x), cy: cy(y), r: 0.2 * SPACING, class: ink(x, y) + " last" }));
+    }
+
+    // the moves of the other variations, as letters to click on
+    const children = node.children;
+    if (children.length > 1) {
+      children.forEach((c, i) => {
+        const move = nodes[c].move;
+        if (!move || !move.point) return;
+        const [x, y] = move.point;
+        const letter = svg("text", { x: cx(x), y: cy(y), class: "variation label" }, String.fromCharCode(65 + i));
+        letter.addEventListener("click", () => go(c));
+        root.append(letter);
+      });
+    }
+
+    const defs = svg("defs", {});
+    const marker = svg("marker", { id: "head", viewBox: "0 0 10 10", refX: 8, refY: 5, markerWidth: 4, markerHeight: 4, orient: "auto" });
+    marker.append(svg("path", { d: "M0 0L10 5L0 10z", class: "head" }));
+    defs.append(marker);
+    root.prepend(defs);
+
+    return root;
+  }
+
+  function describe(i) {
+    const move = nodes[i].move;
+    if (!move) return "setup";
+    const color = move.color === "B" ? "Black" : "White";
+    if (!move.point) return color + " passes";
+    const [x, y] = move.point;
+    // gtp coordinates, without I
+    const column = String.fromCharCode(65 + x + (x >= 8 ? 1 : 0));
+    return `${color} ${column}${H - y}`;
+  }
+
+  function render() {
+    const state = position(current);
+    const node = nodes[current];
+
+    document.getElementById("board").replaceChildren(drawBoard(state));
+
+    const moveText = state.moveNumber === 0 ? "Start" : `Move ${state.moveNumber}: ${describe(current)}`;
+    document.getElementById("move").textContent =
+      `${moveText} — captures B ${state.captures[BLACK]}, W ${state.captures[WHITE]}`;
+
+    const variations = document.getElementById("variations");
+    variations.replaceChildren();
+    if (node.children.length > 1) {
+      node.children.forEach((c, i) => {
+        const button = document.createElement("button");
+        button.textContent = `${String.fromCharCode(65 + i)}: ${describe(c)}`;
+        button.addEventListener("click", () => go(c));
+        variations.append(button);
+      });
+    }
+
+    const comment = document.getElementById("comment");
+    comment.textContent = node.comment || "";
+    comment.hidden = !node.comment;
+
+    document.getElementById("first").disabled = current === 0;
+    document.getElementById("back").disabled = current === 0;
+    document.getElementById("forward").disabled = node.children.length === 0;
+    document.getElementById("last").disabled = node.children.length === 0;
+  }
+
+  function go(i) {
+    const parent = nodes[i].parent;
+    if (parent !== null) chosen[parent] = i;
+    current = i;
+    render();
+  }
+
+  function forward() {
+    const children = nodes[current].children;
+    if (children.length === 0) return false;
+    go(children.includes(chosen[current]) ? chosen[current] : children[0]);
+    return true;
+  }
+
+  function back() {
+    if (nodes[current].parent !== null) go(nodes[current].parent);
+  }
+
+  // the siblings of the current node, for switching variations
+  function sibling(offset) {
+    const parent = nodes[current].parent;
+    if (parent === null) return;
+    const siblings = nodes[parent].children;
+    const i = siblings.indexOf(current) + offset;
+    if (i >= 0 && i < siblings.length) go(siblings[i]);
+  }
+
+  document.getElementById("first").addEventListener("click", () => go(0));
+  document.getElementById("back").addEventListener("click", back);
+  document.getElementById("forward").addEventListener("click", forward);
+  document.getElementById("last").addEventListener("click", () => {
+    while (forward());
+  });
+  document.addEventListener("keydown", (e) => {
+    const actions = {
+      ArrowLeft: back,
+      ArrowRight: forward,
+      ArrowUp: () => sibling(-1),
+      ArrowDown: () => sibling(1),
+      Home: () => go(0),
+      End: () => {
+        while (forward());
+      },
+    };
+    if (actions[e.key]) {
+      actions[e.key]();
+      e.preventDefault();
+    }
+  });
+
+  const title = game.info.GN || `${game.info.PB || "?"} vs ${game.info.PW || "?"}`;
+  document.getElementById("title").textContent = title;
+  const info = document.getElementById("info");
+  for (const [key, name] of Object.entries(NAMES)) {
+    if (key === "GN" || !game.info[key]) continue;
+    const dt = document.createElement("dt");
+    const dd = document.createElement("dd");
+    dt.textContent = name;
+    dd.textContent = game.info[key];
+    info.append(dt, dd);
+  }
+
+  render();
+})();
@@ -0,0 +1,183 @@
+//! Tygem's `.gib` files: a header of `\[KEY=value\]` lines, then a handicap line
+//! `INI 0 1 <handicap> ...` and a move per line, `STO 0 <number> <1 black, 2 white> <x> <y>`
+//! from the top left. `SKI 0 <number>` is a pass.
+
+use super::{invalid, result, warn, Record};
+use crate::board::Stone;
+use crate::sgf::SgfResult;
+use crate::SgfTree;
+
+pub fn parse(text: &str) -> SgfResult<SgfTree> {
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+    if !lines
+        .iter()
+        .any(|l| l.starts_with("\\[") || l.starts_with("STO "))
+    {
+        return Err(invalid("no gib header or moves"));
+    }
+
+    // tygem only has 19x19 games
+    let mut record = Record::new((19, 19))?;
+    let header = |key: &str| {
+        lines.iter().find_map(|l| {
+            l.strip_prefix("\\[")?
+                .strip_suffix("\\]")?
+                .strip_prefix(key)?
+                .strip_prefix('=')
+        })
+    };
+
+    for (color, name, rank) in [("B", "PB", "BR"), ("W", "PW", "WR")] {
+        let long = if color == "B" { "BLACK" } else { "WHITE" };
+        let player = header(&format!("GAME{}NAME", long))
+            .or_else(|| header(&format!("GAME{}NICK", long)))
+            .unwrap_or_default();
+        // "name (9D)"
+        match player.rsplit_once(" (") {
+            Some((n, r)) if r.ends_with(')') => {
+                record.info(name, n);
+                record.info(rank, r.trim_end_matches(')'));
+            }
+            _ => record.info(name, player),
+        }
+    }
+
+    // GAMEINFOMAIN has fields like `GRLT:0,ZIPSU:35,GONGJE:65`,
+    // and GAMETAG the same ones as `W0,Z35,G65`
+    let info = header("GAMEINFOMAIN").unwrap_or_default();
+    let tag = header("GAMETAG").unwrap_or_default();
+    let field = |main: &str, short: &str| {
+        fields(info, ':')
+            .find(|(k, _)| *k == main)
+            .or_else(|| fields(tag, '\0').find(|(k, _)| *k == short))
+            .map(|(_, v)| v)
+    };
+
+    if let Some(komi) = field("GONGJE", "G").and_then(tenths) {
+        record.info("KM", &komi);
+    }
+    if let Some(code) = field("GRLT", "W") {
+        let margin = field("ZIPSU", "Z").and_then(tenths).unwrap_or_default();
+        match code {
+            "0" => record.info("RE", &result(Stone::Black, &margin)),
+            "1" => record.info("RE", &result(Stone::White, &margin)),
+            "3" => record.info("RE", &result(Stone::Black, "R")),
+            "4" => record.info("RE", &result(Stone::White, "R")),
+            "7" => record.info("RE", &result(Stone::Black, "T")),
+            "8" => record.info("RE", &result(Stone::White, "T")),
+            c => warn(format!("unknown gib result {}", c)),
+        }
+    }
+    // C2014:06:06:20:17
+    let date = fields(tag, '\0')
+        .find(|(k, v)| *k == "C" && v.contains(':'))
+        .map(|(_, v)| v.split(':').take(3).collect::<Vec<_>>().join("-"));
+    if let Some(d) = date {
+        record.info("DT", &d);
+    }
+
+    let mut last = Stone::White;
+    for l in &lines {
+        let parts: Vec<&str> = l.split_whitespace().collect();
+        match parts.as_slice() {
+            ["INI", _, _, handicap, ..] => match handicap.parse() {
+                Ok(n) => record.handicap(n),
+                Err(_) => warn(format!("can't read the handicap of `{}`", l)),
+            },
+            ["STO", _, _, color, x, y, ..] => {
+                let stone = match *color {
+                    "1" => Stone::Black,
+                    "2" => Stone::White,
+                    _ => {
+                        warn(format!("unknown color in `{}`", l));
+                        continue;
+                    }
+                };
+                match (x.parse(), y.parse()) {
+                    (Ok(x), Ok(y)) => {
+                        record.play(stone, Some((x, y)));
+                        last = stone;
+                    }
+                    _ => warn(format!("can't read the move `{}`", l)),
+                }
+            }
+            ["SKI", ..] => {
+                last = !last;
+                record.play(last, None);
+            }
+            ["STO", ..] | ["INI", ..] => warn(format!("can't read `{}`", l)),
+            _ => {}
+        }
+    }
+
+    Ok(record.finish())
+}
+
+/// The comma separated `key<separator>value` fields of a header value.
+/// With `'\0'`, the key is the first letter, as in `GAMETAG`.
+fn fields(value: &str, separator: char) -> impl Iterator<Item = (&str, &str)> {
+    value.split(',').filter_map(move |f| {
+        if separator == '\0' {
+            let i = f.char_indices().nth(1)?.0;
+            Some(f.split_at(i))
+        } else {
+            f.split_once(separator)
+        }
+    })
+}
+
+/// A number stored in tenths, such as komi, as a decimal
+fn tenths(v: &str) -> Option<String> {
+    let n: i32 = v.trim().parse().ok()?;
+    let s = format!("{}.{}", n / 10, (n % 10).abs());
+
+    Some(s.strip_suffix(".0").map(str::to_string).unwrap_or(s))
+}
+
+#[cfg(test)]
+mod gib_tests {
+    use super::*;
+
+    const GAME: &str = "\\HS\r
+\\[GAMEINFOMAIN=GBKIND:3,GTYPE:0,GCDT:1,GTIME:1200-30-3,GRLT:1,ZIPSU:35,DUM:0,GONGJE:65,TCNT:4\\]\r
+\\[GAMETAG=S1,R1,D0,G65,W1,Z35,T30-3-1200,C2014:06:06:20:17,I:white,L:black\\]\r
+\\[GAMEWHITENAME=Lee Sedol (9D)\\]\r
+\\[GAMEBLACKNAME=black]one\\]\r
+\\HE\r
+\\GS\r
+2 1 0\r
+INI 0 1 0 &4\r
+STO 0 2 1 15 3\r
+STO 0 3 2 3 15\r
+SKI 0 4\r
+STO 0 5 2 19 19\r
+\\GE\r
+";
+
+    #[test]
+    fn game() {
+        let tree = parse(GAME).unwrap();
+
+        assert_eq!(
+            tree.to_text(),
+            "(;FF[4]CA[UTF-8]GM[1]SZ[19]PB[black\\]one]PW[Lee Sedol]WR[9D]KM[6.5]RE[W+3.5]DT[2014-06-06]\
+             ;B[pd];W[dp];B[];W[])"
+        );
+    }
+
+    #[test]
+    fn handicap() {
+        let tree =
+            parse("\\[GAMEINFOMAIN=GRLT:3,GONGJE:5\\]\nINI 0 1 2 &4\nSTO 0 2 2 16 16\n").unwrap();
+
+        assert_eq!(
+            tree.to_text(),
+            "(;FF[4]CA[UTF-8]GM[1]SZ[19]KM[0.5]RE[B+R]HA[2]AB[pd][dp];W[qq])"
+        );
+    }
+
+    #[test]
+    fn not_gib() {
+        assert!(parse("(;GM[1])").is_err());
+    }
+}
@@ -0,0 +1,311 @@
+//! Games from servers that don't write sgf, read into `SgfTree`s:
+//! Tygem's `.gib`, WBaduk's `.ngf` and PandaNet's `.ugf` and `.ugi`.
+//!
+//! Game info goes in the root as the sgf property it matches, and each move is a node
+//! on the main line. Anything that can't be converted is skipped with a warning in the
+//! debug log, like the sgf parser's.
+//!
+//! The servers write their files in their country's encoding, EUC-KR (or its superset
+//! CP949) for Tygem and WBaduk and Shift_JIS for PandaNet, unless they are already utf-8.
+
+mod gib;
+mod ngf;
+mod ugf;
+
+use std::path::Path;
+
+use encoding_rs::{Encoding, EUC_KR, SHIFT_JIS, UTF_8};
+
+use crate::board::Stone;
+use crate::sgf::{Action, SgfError, SgfResult};
+use crate::SgfTree;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    Gib,
+    Ngf,
+    Ugf,
+}
+impl Format {
+    /// The extensions of the files that can be imported
+    pub const EXTENSIONS: [&'static str; 4] = ["gib", "ngf", "ugf", "ugi"];
+
+    /// The format of a file from its extension, or `None` for sgf and anything else
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let e = path.extension()?.to_str()?.to_ascii_lowercase();
+
+        match e.as_str() {
+            "gib" => Some(Self::Gib),
+            "ngf" => Some(Self::Ngf),
+            "ugf" | "ugi" => Some(Self::Ugf),
+            _ => None,
+        }
+    }
+
+    /// The encoding the format's server writes
+    fn encoding(self) -> &'static Encoding {
+        match self {
+            // which encoding_rs decodes as CP949
+            Self::Gib | Self::Ngf => EUC_KR,
+            Self::Ugf => SHIFT_JIS,
+        }
+    }
+}
+
+pub fn read(path: &Path, format: Format) -> SgfResult<SgfTree> {
+    let bytes = std::fs::read(path)?;
+
+    import(&decode(&bytes, format), format)
+}
+
+/// The text of a file, as utf-8 if it is valid, or otherwise in the format's encoding
+fn decode(bytes: &[u8], format: Format) -> String {
+    let encoding = if std::str::from_utf8(bytes).is_ok() {
+        UTF_8
+    } else {
+        format.encoding()
+    };
+
+    let (text, encoding, had_errors) = encoding.decode(bytes);
+    if had_errors {
+        warn(format!(
+            "the file isn't valid {}, so names and comments may be garbled",
+            encoding.name()
+        ));
+    }
+
+    text.into_owned()
+}
+
+pub fn import(text: &str, format: Format) -> SgfResult<SgfTree> {
+    match format {
+        Format::Gib => gib::parse(text),
+        Format::Ngf => ngf::parse(text),
+        Format::Ugf => ugf::parse(text),
+    }
+}
+
+fn warn(s: impl Into<String>) {
+    let s = s.into();
+
+    #[cfg(debug_assertions)]
+    crate::log(format!("[WARNING] {}", s));
+    #[cfg(not(debug_assertions))]
+    let _ = s;
+}
+
+fn invalid(s: impl Into<String>) -> SgfError {
+    SgfError::Import(s.into())
+}
+
+/// A game being read, from the root down its only line
+struct Record {
+    tree: SgfTree,
+    size: (usize, usize),
+    moves: usize,
+}
+impl Record {
+    fn new(size: (usize, usize)) -> SgfResult<Self> {
+        // the most sgf allows
+        if !(1..=52).contains(&size.0) || !(1..=52).contains(&size.1) {
+            return Err(invalid(format!("{}x{} isn't a board size", size.0, size.1)));
+        }
+
+        let mut tree = SgfTree::default();
+        tree.set_root(format!(
+            "FF[4]CA[UTF-8]GM[1]{}",
+            Action::Size(size.0, size.1)
+        ))?;
+
+        Ok(Self {
+            tree,
+            size,
+            moves: 0,
+        })
+    }
+
+    /// Sets a root property, unless `value` is blank
+    fn info(&mut self, prop: &str, value: &str) {
+        let value = value.trim();
+        if !value.is_empty() {
+            self.tree.root_mut().set_prop(prop, escape(value));
+        }
+    }
+
+    fn on_board(&self, (x, y): (usize, usize)) -> bool {
+        x < self.size.0 && y < self.size.1
+    }
+
+    /// Adds stones to the root, such as handicap stones
+    fn setup(&mut self, stone: Stone, points: Vec<(usize, usize)>) {
+        let points: Vec<_> = points.into_iter().filter(|&p| self.on_board(p)).collect();
+        if points.is_empty() {
+            return;
+        }
+
+        let root = self.tree.root_mut();
+        let existing = root.actions.iter_mut().find_map(|a| match (a, stone) {
+            (Action::AddBlack(v), Stone::Black) | (Action::AddWhite(v), Stone::White) => Some(v),
+            _ => None,
+        });
+        match existing {
+            Some(v) => v.extend(points),
+            None if stone == Stone::White => root.actions.push(Action::AddWhite(points)),
+            None => root.actions.push(Action::AddBlack(points)),
+        }
+    }
+
+    /// Places the usual handicap stones for black
+    fn handicap(&mut self, n: usize) {
+        if n < 2 {
+            return;
+        }
+
+        self.info("HA", &n.to_string());
+        let points = handicap_points(n, self.size);
+        if points.len() < n {
+            warn(format!(
+                "only {} of {} handicap stones fit on a {}x{} board",
+                points.len(),
+                n,
+                self.size.0,
+                self.size.1
+            ));
+        }
+        self.setup(Stone::Black, points);
+    }
+
+    /// Adds a move as a new node, with `None` or a point off the board as a pass
+    fn play(&mut self, stone: Stone, point: Option<(usize, usize)>) {
+        self.moves += 1;
+
+        let point = point.filter(|&p| {
+            let on_board = self.on_board(p);
+            if !on_board {
+                warn(format!(
+                    "move {} at {:?} is off the board, so it is read as a pass",
+                    self.moves, p
+                ));
+            }
+            on_board
+        });
+
+        let a = match (stone, point) {
+            (Stone::White, Some((x, y))) => Action::PlayWhite(x, y),
+            (Stone::White, None) => Action::PassWhite,
+            (_, Some((x, y))) => Action::PlayBlack(x, y),
+            (_, None) => Action::PassBlack,
+        };
+        self.tree.handle_new_action(a, true);
+    }
+
+    fn finish(mut self) -> SgfTree {
+        self.tree.select_root();
+        self.tree
+    }
+}
+
+/// A property value as the sgf parser keeps it, with `]` and `\` escaped
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace(']', "\\]")
+}
+
+/// A result as `RE` writes it, from the winner and how they won:
+/// a number of points, or `R`, `T` or `F` for resignation, time or forfeit
+fn result(winner: Stone, by: &str) -> String {
+    let color = if winner == Stone::White { "W" } else { "B" };
+    let by = by.trim();
+
+    match by {
+        "R" | "T" | "F" => format!("{}+{}", color, by),
+        _ if by.parse::<f32>().is_ok_and(|p| p > 0.0) => format!("{}+{}", color, by),
+        _ => format!("{}+", color),
+    }
+}
+
+/// Where handicap stones go, in the order they are usually placed
+fn handicap_points(n: usize, (w, h): (usize, usize)) -> Vec<(usize, usize)> {
+    if w < 7 || h < 7 {
+        return Vec::new();
+    }
+
+    let edge = if w < 13 || h < 13 { 2 } else { 3 };
+    let (left, top, right, bottom) = (edge, edge, w - 1 - edge, h - 1 - edge);
+    let center = (w / 2, h / 2);
+    let has_center = w % 2 == 1 && h % 2 == 1;
+
+    let mut points = vec![(right, top), (left, bottom), (right, bottom), (left, top)];
+    points.truncate(n);
+    if n > 5 && has_center {
+        points.extend([(left, center.1), (right, center.1)]);
+        if n > 7 {
+            points.extend([(center.0, top), (center.0, bottom)]);
+        }
+    }
+    if n % 2 == 1 && n > 4 && has_center {
+        points.push(center);
+    }
+    points.truncate(n);
+
+    points
+}
+
+#[cfg(test)]
+mod import_tests {
+    use super::*;
+
+    #[test]
+    fn handicaps() {
+        let sgf = |n| {
+            let mut points = handicap_points(n, (19, 19));
+            points.sort();
+            let points: Vec<String> = points
+                .into_iter()
+                .map(|(x, y)| format!("{}{}", (b'a' + x as u8) as char, (b'a' + y as u8) as char))
+                .collect();
+            points.join(" ")
+        };
+
+        assert_eq!(sgf(2), "dp pd");
+        assert_eq!(sgf(3), "dp pd pp");
+        assert_eq!(sgf(5), "dd dp jj pd pp");
+        assert_eq!(sgf(6), "dd dj dp pd pj pp");
+        assert_eq!(sgf(7), "dd dj dp jj pd pj pp");
+        assert_eq!(sgf(8), "dd dj dp jd jp pd pj pp");
+        assert_eq!(sgf(9), "dd dj dp jd jj jp pd pj pp");
+        assert_eq!(
+            handicap_points(4, (9, 9)),
+            vec![(6, 2), (2, 6), (6, 6), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn results() {
+        assert_eq!(result(Stone::Black, "R"), "B+R");
+        assert_eq!(result(Stone::White, "6.5"), "W+6.5");
+        assert_eq!(result(Stone::White, "0"), "W+");
+        assert_eq!(result(Stone::Black, ""), "B+");
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(Format::from_path(Path::new("a.GIB")), Some(Format::Gib));
+        assert_eq!(Format::from_path(Path::new("a.ugi")), Some(Format::Ugf));
+        assert_eq!(Format::from_path(Path::new("a.sgf")), None);
+        assert_eq!(Format::from_path(Path::new("gib")), None);
+    }
+
+    #[test]
+    fn encodings() {
+        let (korean, _, _) = EUC_KR.encode("이세돌");
+        assert_eq!(decode(&korean, Format::Gib), "이세돌");
+        assert_eq!(decode(&korean, Format::Ngf), "이세돌");
+
+        let (japanese, _, _) = SHIFT_JIS.encode("井山裕太");
+        assert_eq!(decode(&japanese, Format::Ugf), "井山裕太");
+
+        // files that were converted already
+        assert_eq!(decode("이세돌".as_bytes(), Format::Ugf), "이세돌");
+        assert_eq!(decode(b"\xef\xbb\xbfHoninbo", Format::Ugf), "Honinbo");
+    }
+}
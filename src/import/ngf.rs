@@ -0,0 +1,188 @@
+//! WBaduk's `.ngf` files: a line each for the game name, board size, white and black
+//! as `name rank`, the server, handicap, (unused), komi, date, (unused), result and
+//! number of moves, then a move per line as `PM<number><B or W><x><y>...`,
+//! with coordinates as letters from `B` at the top left.
+
+use super::{invalid, result, warn, Record};
+use crate::board::Stone;
+use crate::sgf::SgfResult;
+use crate::SgfTree;
+
+/// The line the moves start on
+const MOVES: usize = 12;
+
+pub fn parse(text: &str) -> SgfResult<SgfTree> {
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+    if lines.len() < MOVES {
+        return Err(invalid("an ngf header has 12 lines"));
+    }
+
+    let size = lines[1]
+        .parse()
+        .map_err(|_| invalid(format!("`{}` isn't a board size", lines[1])))?;
+    let mut record = Record::new((size, size))?;
+
+    record.info("GN", lines[0]);
+    for (line, name, rank) in [(lines[3], "PB", "BR"), (lines[2], "PW", "WR")] {
+        // "name      3D*", where * marks a rank the server is sure of
+        let mut parts = line.split_whitespace();
+        record.info(name, parts.next().unwrap_or_default());
+        if let Some(r) = parts.last() {
+            record.info(rank, r.trim_end_matches('*'));
+        }
+    }
+    record.info("PC", lines[4]);
+
+    let handicap = lines[5].parse().unwrap_or_else(|_| {
+        warn(format!("can't read the handicap `{}`", lines[5]));
+        0
+    });
+    match lines[7].parse::<f32>() {
+        // komi is stored without its half point in even games
+        Ok(k) if handicap == 0 && k.fract() == 0.0 => record.info("KM", &(k + 0.5).to_string()),
+        Ok(k) => record.info("KM", &k.to_string()),
+        Err(_) => warn(format!("can't read the komi `{}`", lines[7])),
+    }
+    // 20080201 [16:09]
+    let date = lines[8]
+        .get(..8)
+        .filter(|d| d.bytes().all(|b| b.is_ascii_digit()));
+    match date {
+        Some(d) => record.info("DT", &format!("{}-{}-{}", &d[..4], &d[4..6], &d[6..])),
+        None => warn(format!("can't read the date `{}`", lines[8])),
+    }
+    if let Some(re) = game_result(lines[10]) {
+        record.info("RE", &re);
+    }
+
+    record.handicap(handicap);
+
+    for l in &lines[MOVES..] {
+        let Some(m) = l.strip_prefix("PM") else {
+            continue;
+        };
+        let chars: Vec<char> = m.chars().collect();
+        let (color, x, y) = match chars.as_slice() {
+            [_, _, color, x, y, ..] => (*color, *x, *y),
+            _ => {
+                warn(format!("can't read the move `{}`", l));
+                continue;
+            }
+        };
+        let stone = match color {
+            'B' => Stone::Black,
+            'W' => Stone::White,
+            _ => {
+                warn(format!("unknown color in `{}`", l));
+                continue;
+            }
+        };
+
+        // `A` and anything else off the board is a pass
+        let coord = |c: char| (c as usize).checked_sub('B' as usize);
+        let point = coord(x).zip(coord(y));
+        if point.is_some_and(|(x, y)| x < size && y < size) {
+            record.play(stone, point);
+        } else {
+            record.play(stone, None);
+        }
+    }
+
+    match lines[11].parse::<usize>() {
+        Ok(n) if n != record.moves => warn(format!(
+            "the header says {} moves, but there are {}",
+            n, record.moves
+        )),
+        _ => {}
+    }
+
+    Ok(record.finish())
+}
+
+/// `RE` from a line like "White wins by resignation" or "Black wins by 3.5 points"
+fn game_result(line: &str) -> Option<String> {
+    let lower = line.to_lowercase();
+    let winner = if lower.contains("white win") {
+        Stone::White
+    } else if lower.contains("black win") {
+        Stone::Black
+    } else {
+        if !line.is_empty() {
+            warn(format!("unknown ngf result `{}`", line));
+        }
+        return None;
+    };
+
+    let by = if lower.contains("resign") {
+        String::from("R")
+    } else if lower.contains("time") {
+        String::from("T")
+    } else {
+        lower
+            .split_whitespace()
+            .find(|w| w.parse::<f32>().is_ok())
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    Some(result(winner, &by))
+}
+
+#[cfg(test)]
+mod ngf_tests {
+    use super::*;
+
+    const GAME: &str = "Rated game\r
+19\r
+whiteplayer      3D*\r
+blackplayer      2D\r
+www.wbaduk.com\r
+0\r
+0\r
+6\r
+20080201 [16:09]\r
+5\r
+White wins by resignation\r
+4\r
+PMABBQEEQ\r
+PMACWEQQE\r
+PMADBAAAA\r
+PMAEWUUUU\r
+";
+
+    #[test]
+    fn game() {
+        let tree = parse(GAME).unwrap();
+
+        assert_eq!(
+            tree.to_text(),
+            "(;FF[4]CA[UTF-8]GM[1]SZ[19]GN[Rated game]PB[blackplayer]BR[2D]PW[whiteplayer]WR[3D]\
+             PC[www.wbaduk.com]KM[6.5]DT[2008-02-01]RE[W+R];B[pd];W[dp];B[];W[])"
+        );
+    }
+
+    #[test]
+    fn results() {
+        assert_eq!(
+            game_result("Black wins by 3.5 points"),
+            Some(String::from("B+3.5"))
+        );
+        assert_eq!(game_result("White wins on time"), Some(String::from("W+T")));
+        assert_eq!(game_result("Black wins"), Some(String::from("B+")));
+        assert_eq!(game_result(""), None);
+    }
+
+    #[test]
+    fn handicap() {
+        let game = GAME.replacen("\r\n0\r\n0\r\n6\r\n", "\r\n2\r\n0\r\n0\r\n", 1);
+        let tree = parse(&game).unwrap();
+
+        assert!(tree.to_text().contains("KM[0]"));
+        assert!(tree.to_text().contains("HA[2]AB[pd][dp];"));
+    }
+
+    #[test]
+    fn too_short() {
+        assert!(parse("19\n").is_err());
+    }
+}
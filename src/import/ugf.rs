@@ -0,0 +1,209 @@
+//! PandaNet's `.ugf` and `.ugi` files, which are ini files with the game info under
+//! `[Header]` and a move per line under `[Data]` as `<x><y>,<B or W><number>,<time>`.
+//! Coordinates are letters from `A` at the bottom left, and stones numbered 0 are
+//! placed before the game, such as handicap stones.
+
+use super::{invalid, result, warn, Record};
+use crate::board::Stone;
+use crate::sgf::SgfResult;
+use crate::SgfTree;
+
+/// The sgf property of each header key that is copied as it is
+const INFO: [(&str, &str); 4] = [
+    ("Title", "GN"),
+    ("Place", "PC"),
+    ("Writer", "US"),
+    ("Copyright", "CP"),
+];
+
+pub fn parse(text: &str) -> SgfResult<SgfTree> {
+    let mut header: Vec<(&str, &str)> = Vec::new();
+    let mut data: Vec<&str> = Vec::new();
+    let mut skipped = Vec::new();
+
+    let mut section = "";
+    for l in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if l.starts_with('[') && l.ends_with(']') {
+            section = l;
+            continue;
+        }
+        match section {
+            "[Header]" => header.extend(l.split_once('=')),
+            "[Data]" => data.push(l),
+            // figures and comments are about how the game was shown on the server
+            s if !skipped.contains(&s) => skipped.push(s),
+            _ => {}
+        }
+    }
+    if header.is_empty() {
+        return Err(invalid("no ugf [Header]"));
+    }
+    for s in skipped {
+        warn(format!("the ugf section {} isn't imported", s));
+    }
+
+    let value = |key: &str| {
+        header
+            .iter()
+            .find(|(k, _)| k.trim() == key)
+            .map(|(_, v)| v.trim())
+    };
+    // many values have more fields after a comma
+    let first = |key: &str| value(key).map(|v| v.split(',').next().unwrap_or_default());
+
+    let size = match first("Size") {
+        Some(s) => s
+            .parse()
+            .map_err(|_| invalid(format!("`{}` isn't a board size", s)))?,
+        None => 19,
+    };
+    let mut record = Record::new((size, size))?;
+
+    for (key, prop) in INFO {
+        if let Some(v) = first(key) {
+            record.info(prop, v);
+        }
+    }
+    for (key, name, rank) in [("PlayerB", "PB", "BR"), ("PlayerW", "PW", "WR")] {
+        let mut fields = value(key).unwrap_or_default().split(',');
+        record.info(name, fields.next().unwrap_or_default());
+        record.info(rank, fields.next().unwrap_or_default());
+    }
+    // 2004/04/10
+    if let Some(d) = first("Date") {
+        record.info("DT", &d.replace('/', "-"));
+    }
+    if let Some(r) = first("Rule") {
+        let rules = match r {
+            "JPN" => "Japanese",
+            "CHN" => "Chinese",
+            "KOR" => "Korean",
+            r => r,
+        };
+        record.info("RU", rules);
+    }
+
+    // handicap, komi
+    let mut handicap = 0;
+    if let Some(v) = value("Hdcp") {
+        let mut fields = v.split(',').map(str::trim);
+        handicap = fields.next().and_then(|h| h.parse().ok()).unwrap_or(0);
+        if let Some(k) = fields.next() {
+            record.info("KM", k);
+        }
+    }
+    // winner, and a number of points, R for resignation or T for time
+    if let Some(v) = value("Winner") {
+        let mut fields = v.split(',').map(str::trim);
+        match fields.next() {
+            Some("B") => record.info("RE", &result(Stone::Black, fields.next().unwrap_or(""))),
+            Some("W") => record.info("RE", &result(Stone::White, fields.next().unwrap_or(""))),
+            Some("D") => record.info("RE", "0"),
+            _ => warn(format!("unknown ugf winner `{}`", v)),
+        }
+    }
+
+    let mut placed = false;
+    for l in data {
+        let fields: Vec<&str> = l.split(',').map(str::trim).collect();
+        let (point, turn) = match fields.as_slice() {
+            [point, turn, ..] => (point.as_bytes(), *turn),
+            _ => {
+                warn(format!("can't read the move `{}`", l));
+                continue;
+            }
+        };
+
+        let stone = match turn.get(..1) {
+            Some("B") => Stone::Black,
+            Some("W") => Stone::White,
+            _ => {
+                warn(format!("unknown color in `{}`", l));
+                continue;
+            }
+        };
+        // from the bottom, with anything off the board, such as `YA`, as a pass
+        let point = match point {
+            [x @ b'A'..=b'Z', y @ b'A'..=b'Z'] => {
+                let (x, y) = ((x - b'A') as usize, (y - b'A') as usize);
+                (x < size && y < size).then(|| (x, size - 1 - y))
+            }
+            _ => None,
+        };
+
+        if turn[1..] == *"0" {
+            record.setup(stone, point.into_iter().collect());
+            placed = true;
+        } else {
+            record.play(stone, point);
+        }
+    }
+
+    if handicap >= 2 {
+        if placed {
+            record.info("HA", &handicap.to_string());
+        } else {
+            record.handicap(handicap);
+        }
+    }
+
+    Ok(record.finish())
+}
+
+#[cfg(test)]
+mod ugf_tests {
+    use super::*;
+
+    const GAME: &str = "[Header]\r
+Lang=JPN\r
+Title=Honinbo,The Honinbo\r
+Place=Tokyo\r
+Date=2004/04/10,2004/04/11\r
+Rule=JPN,DefaultTime,\r
+Size=19\r
+Hdcp=0,6.5\r
+Winner=W,2.5\r
+Writer=somebody\r
+PlayerB=Black player,9p,\r
+PlayerW=White player,9p,\r
+[Data]\r
+QP,B1,0\r
+DD,W2,0\r
+YA,B3,0\r
+[Figure]\r
+.Fig,1,1,1,3\r
+";
+
+    #[test]
+    fn game() {
+        let tree = parse(GAME).unwrap();
+
+        assert_eq!(
+            tree.to_text(),
+            "(;FF[4]CA[UTF-8]GM[1]SZ[19]GN[Honinbo]PC[Tokyo]US[somebody]\
+             PB[Black player]BR[9p]PW[White player]WR[9p]DT[2004-04-10]RU[Japanese]KM[6.5]RE[W+2.5]\
+             ;B[qd];W[dp];B[])"
+        );
+    }
+
+    #[test]
+    fn handicap() {
+        let placed = GAME
+            .replace("Hdcp=0,6.5", "Hdcp=2,0.5")
+            .replace("QP,B1,0", "DD,B0,0\r\nPP,B0,0\r\nQP,W1,0");
+        let tree = parse(&placed).unwrap();
+        assert!(tree
+            .to_text()
+            .contains("KM[0.5]RE[W+2.5]AB[dp][pd]HA[2];W[qd]"));
+
+        // without the stones, they go in the usual places
+        let unplaced = GAME.replace("Hdcp=0,6.5", "Hdcp=2,0.5");
+        let tree = parse(&unplaced).unwrap();
+        assert!(tree.to_text().contains("HA[2]AB[pd][dp];B[qd]"));
+    }
+
+    #[test]
+    fn not_ugf() {
+        assert!(parse("(;GM[1])").is_err());
+    }
+}
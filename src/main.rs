@@ -6,8 +6,21 @@ use std::sync::Mutex;
 use eframe::egui;
 
 mod board;
+mod bot;
+mod cli;
+mod clock;
+mod diagram;
 mod flood_fill;
 mod game;
+mod gtp;
+mod guess;
+mod html;
+mod import;
+mod net;
+mod problem;
+mod recovery;
+mod scoring;
+mod session;
 mod sgf;
 mod ui;
 mod util;
@@ -29,6 +42,11 @@ fn log(s: impl Into<String>) {
 }
 
 fn main() -> eframe::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args));
+    }
+
     let native_options = eframe::NativeOptions::default();
 
     eframe::run_native(
@@ -39,34 +57,85 @@ fn main() -> eframe::Result<()> {
 }
 
 pub struct State {
-    /// Some => show board editor
-    /// None => show game builder ui
-    game: Option<Game>,
+    tabs: Vec<ui::Tab>,
+    /// Index on `tabs` of the game being shown,
+    /// or None to show the game builder ui
+    current: Option<usize>,
     builder: GameBuilder,
     ui_state: ui::UiState,
+    /// The window's title, to only set it when it changes
+    title: String,
+    /// None if there is nowhere to put the snapshots
+    recovery: Option<recovery::Recovery>,
 }
 
 impl State {
-    fn new(_cc: &eframe::CreationContext<'_>) -> Self {
+    fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let mut ui_state = ui::UiState::default();
+        if let Some(storage) = cc.storage {
+            ui_state.load(storage);
+        }
+
+        let recovery = recovery::Recovery::default_dir().map(recovery::Recovery::new);
+        if let Some(r) = &recovery {
+            ui_state.lost = r.lost();
+        }
+
         Self {
-            game: None,
+            tabs: Vec::new(),
+            current: None,
             builder: GameBuilder::default(),
-            ui_state: ui::UiState::default(),
+            ui_state,
+            title: String::from("Go"),
+            recovery,
         }
     }
 }
 
 impl eframe::App for State {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.ui_state.save(&self.tabs, self.current, storage);
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if let Some(r) = &mut self.recovery {
+            r.finish();
+        }
+    }
+
+    fn on_close_event(&mut self) -> bool {
+        self.ui_state.allow_close(&self.tabs)
+    }
+
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let title = ui::window_title(self);
+        if title != self.title {
+            frame.set_window_title(&title);
+            self.title = title;
+        }
+
         egui::CentralPanel::default()
             .frame(
                 // remove the margins so the `ui::render` function has access to the full window
                 egui::Frame::default().inner_margin(0.0).outer_margin(0.0),
             )
             .show(ctx, |ui| {
+                ui::tab_bar(self, ui);
+                ui::recovery_prompt(ui, self);
+                if ui::quit_prompt(ui, self) {
+                    frame.close();
+                }
+
+                let size = ui.available_size();
                 ui.horizontal(|ui| {
-                    ui::render(self, ui, frame.info().window_info.size);
+                    ui::render(self, ui, size);
                 });
             });
+
+        if let Some(r) = &mut self.recovery {
+            r.autosave(self.tabs.iter().map(|t| (t.id, &t.game)));
+            // so that the last changes are written even if nothing else happens
+            ctx.request_repaint_after(recovery::INTERVAL);
+        }
     }
 }
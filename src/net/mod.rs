@@ -0,0 +1,280 @@
+//! Two-player games between two instances of the app over TCP.
+//!
+//! Connecting happens on background threads, so [listen] and [connect] return a
+//! [Pending] connection, and incoming messages are read by [Connection::poll].
+
+mod protocol;
+
+pub use protocol::{Message, Setup, SIZES, VERSION};
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+
+/// A connection that is still being made
+pub type Pending = Receiver<io::Result<Connection>>;
+
+pub enum Event {
+    Message(Message),
+    /// A line that isn't part of the protocol
+    Invalid(String),
+    Closed,
+}
+
+pub struct Connection {
+    writer: TcpStream,
+    events: Receiver<Event>,
+    closed: bool,
+}
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        let reader = BufReader::new(stream.try_clone()?);
+        let (sender, events) = channel();
+
+        std::thread::spawn(move || {
+            for line in reader.lines() {
+                let line = match line {
+                    Ok(l) => l,
+                    Err(_) => break,
+                };
+
+                let e = match Message::parse(&line) {
+                    Some(m) => Event::Message(m),
+                    None => Event::Invalid(line),
+                };
+                if sender.send(e).is_err() {
+                    return;
+                }
+            }
+
+            let _ = sender.send(Event::Closed);
+        });
+
+        Ok(Self {
+            writer: stream,
+            events,
+            closed: false,
+        })
+    }
+
+    pub fn send(&mut self, m: &Message) -> io::Result<()> {
+        let r = writeln!(self.writer, "{}", m).and_then(|_| self.writer.flush());
+        if r.is_err() {
+            self.closed = true;
+        }
+
+        r
+    }
+
+    /// The next message from the other side, if one has arrived
+    pub fn poll(&mut self) -> Option<Event> {
+        match self.events.try_recv() {
+            Ok(Event::Closed) | Err(TryRecvError::Disconnected) if !self.closed => {
+                self.closed = true;
+                Some(Event::Closed)
+            }
+            Ok(e) => Some(e),
+            Err(_) => None,
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+}
+impl Drop for Connection {
+    fn drop(&mut self) {
+        let _ = self.writer.shutdown(std::net::Shutdown::Both);
+    }
+}
+
+/// Waits for one guest to connect to `addr`.
+/// Returns the address actually bound, which matters when the port is 0.
+pub fn listen(addr: &str) -> io::Result<(SocketAddr, Pending)> {
+    let listener = TcpListener::bind(addr)?;
+    let local = listener.local_addr()?;
+    let (sender, receiver) = channel();
+
+    std::thread::spawn(move || {
+        let c = listener
+            .accept()
+            .and_then(|(stream, _)| Connection::new(stream));
+        let _ = sender.send(c);
+    });
+
+    Ok((local, receiver))
+}
+
+/// Connects to a host at `addr`, such as `192.168.1.5:4650`
+pub fn connect(addr: &str) -> Pending {
+    let addr = addr.to_string();
+    let (sender, receiver) = channel();
+
+    std::thread::spawn(move || {
+        let c = TcpStream::connect(addr).and_then(Connection::new);
+        let _ = sender.send(c);
+    });
+
+    receiver
+}
+
+/// Agreeing on the protocol version and the game before playing
+pub struct Handshake {
+    conn: Connection,
+    /// `Some` on the host's side
+    setup: Option<Setup>,
+}
+impl Handshake {
+    pub fn host(mut conn: Connection, setup: Setup) -> io::Result<Self> {
+        conn.send(&Message::Hello {
+            version: VERSION,
+            setup,
+        })?;
+
+        Ok(Self {
+            conn,
+            setup: Some(setup),
+        })
+    }
+
+    pub fn guest(conn: Connection) -> Self {
+        Self { conn, setup: None }
+    }
+
+    /// Returns the agreed setup once the handshake has finished
+    pub fn poll(&mut self) -> Result<Option<Setup>, String> {
+        let m = match self.conn.poll() {
+            None => return Ok(None),
+            Some(Event::Message(m)) => m,
+            Some(Event::Invalid(l)) => return Err(format!("unexpected message `{}`", l)),
+            Some(Event::Closed) => return Err(String::from("the connection was closed")),
+        };
+
+        match (self.setup, m) {
+            (Some(setup), Message::Welcome { version }) => {
+                check_version(version)?;
+                Ok(Some(setup))
+            }
+            (None, Message::Hello { version, setup }) => {
+                if let Err(e) = check_version(version).and_then(|_| check_size(setup.size)) {
+                    let _ = self.conn.send(&Message::Error(e.clone()));
+                    return Err(e);
+                }
+
+                self.conn
+                    .send(&Message::Welcome { version: VERSION })
+                    .map_err(|e| e.to_string())?;
+                Ok(Some(setup))
+            }
+            (_, Message::Error(e)) => Err(e),
+            (_, m) => Err(format!("unexpected message `{}`", m)),
+        }
+    }
+
+    pub fn is_host(&self) -> bool {
+        self.setup.is_some()
+    }
+
+    pub fn into_connection(self) -> Connection {
+        self.conn
+    }
+}
+
+fn check_version(v: u32) -> Result<(), String> {
+    if v == VERSION {
+        Ok(())
+    } else {
+        Err(format!(
+            "unsupported protocol version {} (expected {})",
+            v, VERSION
+        ))
+    }
+}
+
+fn check_size((w, h): (usize, usize)) -> Result<(), String> {
+    if SIZES.contains(&w) && SIZES.contains(&h) {
+        Ok(())
+    } else {
+        Err(format!(
+            "unsupported board size {}x{} (from {} to {})",
+            w,
+            h,
+            SIZES.start(),
+            SIZES.end()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod net_tests {
+    use super::*;
+    use crate::Stone;
+    use std::time::{Duration, Instant};
+
+    fn wait<T>(mut f: impl FnMut() -> Option<T>) -> T {
+        let start = Instant::now();
+        loop {
+            if let Some(t) = f() {
+                return t;
+            }
+            assert!(start.elapsed() < Duration::from_secs(10), "timed out");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn localhost_game() {
+        let setup = Setup {
+            size: (9, 9),
+            komi: 7.5,
+            host_color: Stone::Black,
+        };
+
+        let (addr, host) = listen("127.0.0.1:0").unwrap();
+        let guest = connect(&addr.to_string());
+
+        let host = wait(|| host.try_recv().ok()).unwrap();
+        let guest = wait(|| guest.try_recv().ok()).unwrap();
+
+        let mut host = Handshake::host(host, setup).unwrap();
+        let mut guest = Handshake::guest(guest);
+
+        assert_eq!(wait(|| guest.poll().unwrap()), setup);
+        assert_eq!(wait(|| host.poll().unwrap()), setup);
+
+        let mut host = host.into_connection();
+        let mut guest = guest.into_connection();
+
+        host.send(&Message::Move(2, 6)).unwrap();
+        match wait(|| guest.poll()) {
+            Event::Message(m) => assert_eq!(m, Message::Move(2, 6)),
+            _ => panic!("expected a move"),
+        }
+
+        drop(host);
+        assert!(matches!(wait(|| guest.poll()), Event::Closed));
+        assert!(guest.is_closed());
+    }
+
+    #[test]
+    fn board_too_big() {
+        let setup = Setup {
+            size: (1000000, 1000000),
+            komi: 7.5,
+            host_color: Stone::Black,
+        };
+
+        let (addr, host) = listen("127.0.0.1:0").unwrap();
+        let guest = connect(&addr.to_string());
+
+        let host = wait(|| host.try_recv().ok()).unwrap();
+        let guest = wait(|| guest.try_recv().ok()).unwrap();
+
+        let mut host = Handshake::host(host, setup).unwrap();
+        let mut guest = Handshake::guest(guest);
+
+        let e = wait(|| guest.poll().err());
+        assert!(e.contains("1000000x1000000"));
+        assert_eq!(wait(|| host.poll().err()), e);
+    }
+}
@@ -0,0 +1,172 @@
+//! The text protocol spoken between two instances of the app.
+//!
+//! Every message is one line. The host starts with `HELLO`, the guest answers
+//! `WELCOME`, and after that either side may send moves and requests:
+//!
+//! ```text
+//! HELLO <version> <width> <height> <komi> <b|w>   host -> guest, the colour is the host's
+//! WELCOME <version>                               guest -> host
+//! MOVE <x> <y>                                    (0, 0) is the top left
+//! PASS
+//! RESIGN
+//! UNDO                                            asks to take back the sender's last move
+//! UNDO-ACCEPT
+//! UNDO-DECLINE
+//! DEAD <x> <y>                                    marks or unmarks a group while counting
+//! ACCEPT <result>                                 agrees to the count, as `RE` would have it
+//! RESUME                                          goes back to playing instead of counting
+//! ERROR <text>
+//! ```
+
+use crate::Stone;
+
+pub const VERSION: u32 = 1;
+
+/// The board sizes a game can have, as in the game creator
+pub const SIZES: std::ops::RangeInclusive<usize> = 5..=52;
+
+/// What the host decides about the game
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Setup {
+    pub size: (usize, usize),
+    pub komi: f32,
+    /// The colour the host plays
+    pub host_color: Stone,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Message {
+    Hello { version: u32, setup: Setup },
+    Welcome { version: u32 },
+    Move(usize, usize),
+    Pass,
+    Resign,
+    UndoRequest,
+    UndoAccept,
+    UndoDecline,
+    Dead(usize, usize),
+    Accept(String),
+    Resume,
+    Error(String),
+}
+impl Message {
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut words = line.split_whitespace();
+        let command = words.next()?;
+
+        let mut num = || words.next().and_then(|w| w.parse::<usize>().ok());
+
+        let m = match command {
+            "HELLO" => {
+                let version = num()? as u32;
+                let size = (num()?, num()?);
+                let komi = words.next()?.parse().ok()?;
+                let host_color = match words.next()? {
+                    "b" => Stone::Black,
+                    "w" => Stone::White,
+                    _ => return None,
+                };
+
+                Self::Hello {
+                    version,
+                    setup: Setup {
+                        size,
+                        komi,
+                        host_color,
+                    },
+                }
+            }
+            "WELCOME" => Self::Welcome {
+                version: num()? as u32,
+            },
+            "MOVE" => Self::Move(num()?, num()?),
+            "PASS" => Self::Pass,
+            "RESIGN" => Self::Resign,
+            "UNDO" => Self::UndoRequest,
+            "UNDO-ACCEPT" => Self::UndoAccept,
+            "UNDO-DECLINE" => Self::UndoDecline,
+            "DEAD" => Self::Dead(num()?, num()?),
+            "ACCEPT" => Self::Accept(words.next()?.to_string()),
+            "RESUME" => Self::Resume,
+            "ERROR" => Self::Error(line.trim()["ERROR".len()..].trim().to_string()),
+            _ => return None,
+        };
+
+        Some(m)
+    }
+}
+impl std::fmt::Display for Message {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Hello { version, setup } => write!(
+                f,
+                "HELLO {} {} {} {} {}",
+                version,
+                setup.size.0,
+                setup.size.1,
+                setup.komi,
+                if setup.host_color == Stone::White {
+                    "w"
+                } else {
+                    "b"
+                }
+            ),
+            Self::Welcome { version } => write!(f, "WELCOME {}", version),
+            Self::Move(x, y) => write!(f, "MOVE {} {}", x, y),
+            Self::Pass => write!(f, "PASS"),
+            Self::Resign => write!(f, "RESIGN"),
+            Self::UndoRequest => write!(f, "UNDO"),
+            Self::UndoAccept => write!(f, "UNDO-ACCEPT"),
+            Self::UndoDecline => write!(f, "UNDO-DECLINE"),
+            Self::Dead(x, y) => write!(f, "DEAD {} {}", x, y),
+            Self::Accept(result) => write!(f, "ACCEPT {}", result),
+            Self::Resume => write!(f, "RESUME"),
+            // keep the message on one line
+            Self::Error(e) => write!(f, "ERROR {}", e.replace('\n', " ")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod protocol_tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let messages = [
+            Message::Hello {
+                version: VERSION,
+                setup: Setup {
+                    size: (19, 13),
+                    komi: 6.5,
+                    host_color: Stone::White,
+                },
+            },
+            Message::Welcome { version: VERSION },
+            Message::Move(3, 15),
+            Message::Pass,
+            Message::Resign,
+            Message::UndoRequest,
+            Message::UndoAccept,
+            Message::UndoDecline,
+            Message::Dead(2, 16),
+            Message::Accept(String::from("W+3.5")),
+            Message::Resume,
+            Message::Error(String::from("illegal move")),
+        ];
+
+        for m in messages {
+            assert_eq!(Message::parse(&m.to_string()), Some(m));
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(Message::parse(""), None);
+        assert_eq!(Message::parse("MOVE 3"), None);
+        assert_eq!(Message::parse("MOVE a b"), None);
+        assert_eq!(Message::parse("HELLO 1 19 19 6.5 x"), None);
+        assert_eq!(Message::parse("ACCEPT"), None);
+        assert_eq!(Message::parse("JUMP"), None);
+    }
+}
@@ -0,0 +1,74 @@
+use std::fs;
+use std::path::Path;
+
+use crate::sgf::SgfResult;
+use crate::SgfTree;
+
+/// One problem from a collection
+pub struct Entry {
+    /// Identifies the problem in the progress file, as `collection/file#index`
+    pub id: String,
+    /// The difficulty from the `DI` property, if there is one
+    pub tag: Option<String>,
+    pub tree: SgfTree,
+}
+
+pub struct Collection {
+    pub name: String,
+    pub entries: Vec<Entry>,
+}
+impl Collection {
+    /// Every game tree in an sgf file
+    pub fn from_file(path: &Path) -> SgfResult<Self> {
+        let name = file_name(path);
+        let mut c = Self {
+            name: name.clone(),
+            entries: Vec::new(),
+        };
+        c.add_file(path, &name)?;
+
+        Ok(c)
+    }
+
+    /// Every game tree in every sgf file in a directory, in order of file name
+    pub fn from_dir(path: &Path) -> SgfResult<Self> {
+        let mut files = fs::read_dir(path)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().map(|e| e == "sgf").unwrap_or(false))
+            .collect::<Vec<_>>();
+        files.sort();
+
+        let mut c = Self {
+            name: file_name(path),
+            entries: Vec::new(),
+        };
+        for f in files {
+            let name = c.name.clone();
+            c.add_file(&f, &name)?;
+        }
+
+        Ok(c)
+    }
+
+    fn add_file(&mut self, path: &Path, collection: &str) -> SgfResult<()> {
+        let text = fs::read_to_string(path)?;
+        let file = file_name(path);
+
+        for (i, tree) in SgfTree::parse_collection(text)?.into_iter().enumerate() {
+            self.entries.push(Entry {
+                id: format!("{}/{}#{}", collection, file, i),
+                tag: tree.root().get_prop("DI").map(|s| s.trim().to_string()),
+                tree,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
@@ -0,0 +1,252 @@
+//! Solving problems (tsumego) from the variations of an sgf file.
+//!
+//! The user plays one colour and the opponent answers with the first variation.
+//! A line is correct if a node in it says so: through a marker in the comment
+//! (such as `RIGHT`), the words "correct" or "wrong", or `GB`/`GW`.
+//! A line that ends without saying it is correct is wrong.
+
+mod collection;
+mod progress;
+
+pub use collection::Collection;
+pub use progress::{today, Attempt, History, Stats};
+
+use crate::sgf::Action;
+use crate::{Game, SgfNode, Stone};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Verdict {
+    Correct,
+    Wrong,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Status {
+    Solving,
+    Solved,
+    Failed,
+}
+
+/// What `node` says about the line it is in, for someone playing `player`.
+/// `marker` is case sensitive, and ignored if it is empty.
+pub fn verdict(node: &SgfNode, player: Stone, marker: &str) -> Option<Verdict> {
+    for a in &node.actions {
+        if let Action::Comment(c) = a {
+            if !marker.is_empty() && c.contains(marker) {
+                return Some(Verdict::Correct);
+            }
+
+            // "incorrect" contains "correct", so look for wrong answers first
+            let c = c.to_lowercase();
+            if c.contains("wrong") || c.contains("incorrect") {
+                return Some(Verdict::Wrong);
+            }
+            if c.contains("correct") {
+                return Some(Verdict::Correct);
+            }
+        }
+    }
+
+    let good_for = if node.get_prop("GB").is_some() {
+        Stone::Black
+    } else if node.get_prop("GW").is_some() {
+        Stone::White
+    } else {
+        return None;
+    };
+
+    if good_for == player {
+        Some(Verdict::Correct)
+    } else {
+        Some(Verdict::Wrong)
+    }
+}
+
+pub struct Problem {
+    /// The colour the user plays
+    pub player: Stone,
+    pub status: Status,
+}
+impl Problem {
+    /// Starts solving from the root of `game`'s tree.
+    /// The user plays the colour from `PL`, or else the colour of the first move.
+    pub fn start(game: &mut Game) -> Self {
+        game.tree.select_root();
+
+        let player = match game.tree.root().get_prop("PL") {
+            Some("W") | Some("w") => Stone::White,
+            Some(_) => Stone::Black,
+            None => game
+                .tree
+                .select_child(0)
+                .ok()
+                .and_then(|n| n.actions.iter().find_map(crate::util::mover))
+                .unwrap_or(Stone::Black),
+        };
+
+        let mut p = Self {
+            player,
+            status: Status::Solving,
+        };
+        p.retry(game);
+
+        p
+    }
+
+    /// Goes back to the start of the problem
+    pub fn retry(&mut self, game: &mut Game) {
+        game.tree.select_root();
+        game.do_to_now();
+        game.turn = self.player;
+        self.status = Status::Solving;
+    }
+
+    /// Called after the user played `a`, which is already on the board.
+    /// Follows it in the tree and answers with the opponent's move.
+    pub fn play(&mut self, game: &mut Game, a: &Action, marker: &str) {
+        if self.status != Status::Solving {
+            return;
+        }
+
+        if !follow(game, a) {
+            // the stone stays on the board to show what was wrong
+            self.status = Status::Failed;
+            return;
+        }
+        game.do_to_now();
+
+        if !self.check(game, marker) {
+            // `check` has made sure there is a reply
+            let _ = game.tree.select_child(0);
+            game.do_to_now();
+
+            self.check(game, marker);
+        }
+
+        game.turn = self.player;
+    }
+
+    /// Updates the status from the current node. Returns true if the line has ended.
+    fn check(&mut self, game: &Game, marker: &str) -> bool {
+        let v = verdict(game.tree.current_node(), self.player, marker);
+        let v = match v {
+            Some(v) => v,
+            None if game.tree.num_children() == 0 => Verdict::Wrong,
+            None => return false,
+        };
+
+        self.status = match v {
+            Verdict::Correct => Status::Solved,
+            Verdict::Wrong => Status::Failed,
+        };
+
+        true
+    }
+}
+
+/// Selects the child of the current node with the move `a`, if there is one
+fn follow(game: &mut Game, a: &Action) -> bool {
+    for i in 0..game.tree.num_children() {
+        if let Ok(n) = game.tree.select_child(i) {
+            if n.actions.contains(a) {
+                return true;
+            }
+        }
+        let _ = game.tree.select_parent();
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod problem_tests {
+    use super::*;
+    use crate::{GameBuilder, SgfTree};
+
+    fn problem(sgf: &str) -> (Game, Problem) {
+        let mut game = GameBuilder {
+            size: (5, 5),
+            ..Default::default()
+        }
+        .build();
+        game.tree = SgfTree::parse(sgf.to_string()).unwrap();
+
+        let p = Problem::start(&mut game);
+        (game, p)
+    }
+
+    fn play(game: &mut Game, p: &mut Problem, x: usize, y: usize) {
+        let a = match p.player {
+            Stone::Black => Action::PlayBlack(x, y),
+            _ => Action::PlayWhite(x, y),
+        };
+        game.board.attempt_set(x, y, p.player);
+        p.play(game, &a, "RIGHT");
+    }
+
+    const SGF: &str = "(;SZ[5]AW[ba][ab]AB[ca][cb][ac][bc]PL[W]\
+        (;W[bb];B[aa];W[bb]C[RIGHT])\
+        (;W[aa];B[bb]C[Black lives]))";
+
+    #[test]
+    fn correct_line() {
+        let (mut game, mut p) = problem(SGF);
+        assert_eq!(p.player, Stone::White);
+        assert_eq!(game.turn, Stone::White);
+        assert_eq!(game.board.get(1, 0), Stone::White);
+
+        play(&mut game, &mut p, 1, 1);
+        assert_eq!(p.status, Status::Solving);
+        // the opponent answered
+        assert_eq!(game.board.get(0, 0), Stone::Black);
+        assert_eq!(game.turn, Stone::White);
+
+        play(&mut game, &mut p, 1, 1);
+        assert_eq!(p.status, Status::Solved);
+    }
+
+    #[test]
+    fn wrong_lines() {
+        let (mut game, mut p) = problem(SGF);
+
+        // the line ends without a marker
+        play(&mut game, &mut p, 0, 0);
+        assert_eq!(p.status, Status::Failed);
+        assert_eq!(game.board.get(1, 1), Stone::Black);
+
+        p.retry(&mut game);
+        assert_eq!(game.board.get(1, 1), Stone::Empty);
+
+        // not in the tree at all
+        play(&mut game, &mut p, 4, 4);
+        assert_eq!(p.status, Status::Failed);
+    }
+
+    #[test]
+    fn verdicts() {
+        let mut tree = SgfTree::parse(String::from(
+            "(;GB[1](;C[Incorrect, white lives])(;C[Correct!])(;GW[2]))",
+        ))
+        .unwrap();
+
+        let mut found = Vec::new();
+        for i in 0..3 {
+            let n = tree.select_child(i).unwrap();
+            found.push(verdict(n, Stone::Black, ""));
+            tree.select_parent().unwrap();
+        }
+
+        assert_eq!(
+            verdict(tree.root(), Stone::Black, ""),
+            Some(Verdict::Correct)
+        );
+        assert_eq!(
+            found,
+            vec![
+                Some(Verdict::Wrong),
+                Some(Verdict::Correct),
+                Some(Verdict::Wrong)
+            ]
+        );
+    }
+}
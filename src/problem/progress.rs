@@ -0,0 +1,403 @@
+//! The history of attempts at problems, and choosing which ones to show each day.
+//!
+//! Every attempt is appended to a tab separated file as
+//! `<unix time>\t<seconds taken>\t<1 if solved, else 0>\t<problem id>`.
+//! Problems are scheduled with SM-2, using the first attempt at a problem each day.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::collection::Collection;
+
+const DAY: u64 = 24 * 60 * 60;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attempt {
+    pub id: String,
+    /// Seconds since the unix epoch
+    pub time: u64,
+    /// How long the problem took
+    pub taken: Duration,
+    pub solved: bool,
+}
+impl Attempt {
+    pub fn new(id: &str, taken: Duration, solved: bool) -> Self {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        Self {
+            id: id.to_string(),
+            time,
+            taken,
+            solved,
+        }
+    }
+
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(4, '\t');
+
+        let time = fields.next()?.parse().ok()?;
+        let taken = Duration::try_from_secs_f32(fields.next()?.parse().ok()?).ok()?;
+        let solved = match fields.next()? {
+            "1" => true,
+            "0" => false,
+            _ => return None,
+        };
+        let id = fields.next()?.to_string();
+
+        Some(Self {
+            id,
+            time,
+            taken,
+            solved,
+        })
+    }
+
+    fn day(&self) -> u64 {
+        self.time / DAY
+    }
+
+    /// How well the problem was known, from 0 to 5 as in SM-2
+    fn quality(&self) -> u32 {
+        match (self.solved, self.taken.as_secs()) {
+            (false, _) => 1,
+            (true, 0..=29) => 5,
+            (true, 30..=119) => 4,
+            (true, _) => 3,
+        }
+    }
+}
+impl std::fmt::Display for Attempt {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}\t{:.1}\t{}\t{}",
+            self.time,
+            self.taken.as_secs_f32(),
+            if self.solved { 1 } else { 0 },
+            self.id
+        )
+    }
+}
+
+/// The SM-2 state of one problem
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Card {
+    pub repetitions: u32,
+    /// Days until the problem is shown again
+    pub interval: u64,
+    pub ease: f32,
+    /// The day the problem should be shown, in days since the unix epoch
+    pub due: u64,
+    /// The day the problem was first seen
+    pub first_seen: u64,
+}
+impl Card {
+    fn new(day: u64) -> Self {
+        Self {
+            repetitions: 0,
+            interval: 0,
+            ease: 2.5,
+            due: day,
+            first_seen: day,
+        }
+    }
+
+    fn review(&mut self, quality: u32, day: u64) {
+        if quality < 3 {
+            self.repetitions = 0;
+            self.interval = 1;
+        } else {
+            self.interval = match self.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (self.interval as f32 * self.ease).round() as u64,
+            };
+            self.repetitions += 1;
+        }
+
+        let q = (5 - quality) as f32;
+        self.ease = (self.ease + 0.1 - q * (0.08 + q * 0.02)).max(1.3);
+        self.due = day + self.interval;
+    }
+}
+
+/// Totals for a group of problems
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Stats {
+    /// Problems that have been tried at least once
+    pub seen: usize,
+    pub attempts: u32,
+    pub solved: u32,
+    /// Time spent on the problems that were solved
+    pub solve_time: Duration,
+}
+impl Stats {
+    fn add(&mut self, a: &Attempt) {
+        self.attempts += 1;
+        if a.solved {
+            self.solved += 1;
+            self.solve_time += a.taken;
+        }
+    }
+
+    /// The share of attempts that were solved, from 0 to 1
+    pub fn success_rate(&self) -> f32 {
+        if self.attempts == 0 {
+            return 0.0;
+        }
+
+        self.solved as f32 / self.attempts as f32
+    }
+
+    pub fn average_time(&self) -> Duration {
+        if self.solved == 0 {
+            return Duration::ZERO;
+        }
+
+        self.solve_time / self.solved
+    }
+}
+
+#[derive(Default)]
+pub struct History {
+    pub attempts: Vec<Attempt>,
+}
+impl History {
+    /// The progress file in the app's data directory
+    pub fn default_path() -> Option<PathBuf> {
+        crate::util::data_dir().map(|d| d.join("progress.tsv"))
+    }
+
+    /// Reads the history, which is empty if the file doesn't exist yet
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = match fs::read_to_string(path) {
+            Ok(t) => t,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+
+        let mut attempts = Vec::new();
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            match Attempt::parse(line) {
+                Some(a) => attempts.push(a),
+                None => {
+                    #[cfg(debug_assertions)]
+                    crate::log(format!("[WARNING] invalid line in progress file: {}", line));
+                }
+            }
+        }
+
+        Ok(Self { attempts })
+    }
+
+    /// Records an attempt, appending it to the file at `path`
+    pub fn record(&mut self, path: &Path, a: Attempt) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut f = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(f, "{}", a)?;
+
+        self.attempts.push(a);
+        Ok(())
+    }
+
+    /// The SM-2 state of every problem that has been tried
+    pub fn cards(&self) -> HashMap<&str, Card> {
+        let mut cards: HashMap<&str, Card> = HashMap::new();
+        let mut reviewed = HashSet::new();
+
+        for a in &self.attempts {
+            let day = a.day();
+            if !reviewed.insert((a.id.as_str(), day)) {
+                continue;
+            }
+
+            cards
+                .entry(a.id.as_str())
+                .or_insert_with(|| Card::new(day))
+                .review(a.quality(), day);
+        }
+
+        cards
+    }
+
+    /// The problems from `collection` to show on `today`, as indices on its entries:
+    /// those that are due, then up to `new_per_day` problems that haven't been seen.
+    pub fn schedule(&self, collection: &Collection, today: u64, new_per_day: usize) -> Vec<usize> {
+        let cards = self.cards();
+
+        let mut due = Vec::new();
+        let mut new = Vec::new();
+        let mut new_today = 0;
+
+        for (i, e) in collection.entries.iter().enumerate() {
+            match cards.get(e.id.as_str()) {
+                Some(c) if c.due <= today => due.push((c.due, i)),
+                Some(c) if c.first_seen == today => new_today += 1,
+                Some(_) => {}
+                None => new.push(i),
+            }
+        }
+
+        due.sort();
+        new.truncate(new_per_day.saturating_sub(new_today));
+
+        due.into_iter().map(|(_, i)| i).chain(new).collect()
+    }
+
+    /// Stats for each collection that has been tried
+    pub fn collection_stats(&self) -> BTreeMap<String, Stats> {
+        self.group_stats(|id| id.split('/').next().map(|s| s.to_string()))
+    }
+
+    /// Stats for each difficulty tag in `collection`.
+    /// Problems without a tag are counted under "untagged".
+    pub fn tag_stats(&self, collection: &Collection) -> BTreeMap<String, Stats> {
+        let tags: HashMap<&str, &str> = collection
+            .entries
+            .iter()
+            .map(|e| (e.id.as_str(), e.tag.as_deref().unwrap_or("untagged")))
+            .collect();
+
+        self.group_stats(|id| tags.get(id).map(|t| t.to_string()))
+    }
+
+    fn group_stats(&self, group: impl Fn(&str) -> Option<String>) -> BTreeMap<String, Stats> {
+        let mut stats: BTreeMap<String, Stats> = BTreeMap::new();
+        let mut seen = HashSet::new();
+
+        for a in &self.attempts {
+            let g = match group(&a.id) {
+                Some(g) => g,
+                None => continue,
+            };
+
+            let s = stats.entry(g).or_default();
+            s.add(a);
+            if seen.insert(a.id.as_str()) {
+                s.seen += 1;
+            }
+        }
+
+        stats
+    }
+}
+
+/// Days since the unix epoch
+pub fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / DAY)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod progress_tests {
+    use super::*;
+    use crate::problem::collection::Entry;
+    use crate::SgfTree;
+
+    fn attempt(id: &str, day: u64, secs: u64, solved: bool) -> Attempt {
+        Attempt {
+            id: id.to_string(),
+            time: day * DAY + 100,
+            taken: Duration::from_secs(secs),
+            solved,
+        }
+    }
+
+    fn collection(tags: &[Option<&str>]) -> Collection {
+        Collection {
+            name: String::from("c"),
+            entries: tags
+                .iter()
+                .enumerate()
+                .map(|(i, t)| Entry {
+                    id: format!("c/c.sgf#{}", i),
+                    tag: t.map(|t| t.to_string()),
+                    tree: SgfTree::default(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn file_lines() {
+        let a = attempt("life and death/c.sgf#3", 5, 12, true);
+
+        assert_eq!(a.to_string(), "432100\t12.0\t1\tlife and death/c.sgf#3");
+        assert_eq!(Attempt::parse(&a.to_string()), Some(a));
+        assert_eq!(Attempt::parse("432100\t12.0\tyes\tc.sgf#3"), None);
+        for taken in ["-1", "NaN", "inf"] {
+            let line = format!("432100\t{}\t1\tc.sgf#3", taken);
+            assert_eq!(Attempt::parse(&line), None);
+        }
+    }
+
+    #[test]
+    fn sm2() {
+        let mut c = Card::new(0);
+
+        c.review(5, 0);
+        assert_eq!((c.interval, c.due), (1, 1));
+        c.review(5, 1);
+        assert_eq!((c.interval, c.due), (6, 7));
+        c.review(4, 7);
+        assert_eq!(c.interval, 16);
+
+        // forgetting starts over
+        c.review(1, 23);
+        assert_eq!((c.repetitions, c.interval, c.due), (0, 1, 24));
+        assert!(c.ease >= 1.3);
+    }
+
+    #[test]
+    fn schedule() {
+        let c = collection(&[None, None, None, None]);
+        let history = History {
+            attempts: vec![
+                // solved on day 10, so due again on day 11
+                attempt("c/c.sgf#0", 10, 5, true),
+                // failed, then solved later the same day, which doesn't count
+                attempt("c/c.sgf#1", 10, 5, false),
+                attempt("c/c.sgf#1", 10, 5, true),
+            ],
+        };
+
+        assert_eq!(history.schedule(&c, 10, 1), Vec::<usize>::new());
+        assert_eq!(history.schedule(&c, 11, 1), vec![0, 1, 2]);
+        assert_eq!(history.schedule(&c, 11, 5), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn stats() {
+        let c = collection(&[Some("5k"), Some("5k"), None]);
+        let history = History {
+            attempts: vec![
+                attempt("c/c.sgf#0", 10, 20, true),
+                attempt("c/c.sgf#1", 10, 40, false),
+                attempt("c/c.sgf#1", 11, 40, true),
+                attempt("c/c.sgf#2", 11, 10, false),
+                attempt("other/x.sgf#0", 11, 10, true),
+            ],
+        };
+
+        let tags = history.tag_stats(&c);
+        let five = tags["5k"];
+        assert_eq!((five.seen, five.attempts, five.solved), (2, 3, 2));
+        assert_eq!(five.average_time(), Duration::from_secs(30));
+        assert_eq!(tags["untagged"].success_rate(), 0.0);
+
+        let collections = history.collection_stats();
+        assert_eq!(collections.len(), 2);
+        assert_eq!(collections["c"].attempts, 4);
+    }
+}
@@ -0,0 +1,325 @@
+//! Snapshots of the open games, so that they aren't lost if the app crashes.
+//!
+//! Every game is written to the recovery directory when it changes, at most every
+//! `INTERVAL`, as
+//! ```text
+//! path\t<where the game was opened from, if anywhere>
+//! node\t<path_to_current, separated by spaces>
+//! <sgf>
+//! ```
+//! in a file named after the session and the game's tab.
+//! The files of a session are deleted when it closes cleanly,
+//! so any that are left at the start of a session are from one that crashed,
+//! unless that session is still open in another window. Each session holds a lock on
+//! `<session>.lock` for as long as it runs to tell them apart.
+
+use std::collections::HashMap;
+use std::fs::{self, File, TryLockError};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{Game, SgfTree};
+
+/// How often the games are written
+pub const INTERVAL: Duration = Duration::from_secs(30);
+
+/// A game read back from the recovery directory
+#[derive(Debug)]
+pub struct Snapshot {
+    pub path: Option<PathBuf>,
+    /// The path from the root to the node that was current
+    pub node: Vec<usize>,
+    pub tree: SgfTree,
+}
+impl Snapshot {
+    pub fn to_text(game: &Game) -> String {
+        let path = game
+            .path
+            .as_ref()
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let node: Vec<String> = game
+            .tree
+            .path_to_current()
+            .iter()
+            .map(|n| n.to_string())
+            .collect();
+
+        format!(
+            "path\t{}\nnode\t{}\n{}",
+            path,
+            node.join(" "),
+            game.tree.to_text()
+        )
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut lines = text.splitn(3, '\n');
+
+        let path = lines.next()?.strip_prefix("path\t")?;
+        let node = lines
+            .next()?
+            .strip_prefix("node\t")?
+            .split_whitespace()
+            .map(|n| n.parse().ok())
+            .collect::<Option<Vec<usize>>>()?;
+        let tree = SgfTree::parse(lines.next()?.to_string()).ok()?;
+
+        Some(Self {
+            path: (!path.is_empty()).then(|| PathBuf::from(path)),
+            node,
+            tree,
+        })
+    }
+
+    /// The game as it was, which is only unmodified if its file hasn't changed since
+    pub fn into_game(self) -> Game {
+        let saved = self
+            .path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|t| SgfTree::parse(t).ok());
+
+        let mut game = Game::from_tree(self.tree, self.path);
+        game.set_saved(saved);
+        if game.tree.select_path(&self.node).is_ok() {
+            game.do_to_now();
+        }
+
+        game
+    }
+}
+
+pub struct Recovery {
+    dir: PathBuf,
+    /// Starts the name of every file of this session
+    session: String,
+    /// The hash of the last snapshot written for each tab
+    written: HashMap<usize, u64>,
+    last: Instant,
+    /// Held until the session closes, so that other sessions know it is still running
+    lock: Option<File>,
+}
+impl Recovery {
+    /// The recovery directory in the app's data directory
+    pub fn default_dir() -> Option<PathBuf> {
+        crate::util::data_dir().map(|d| d.join("recovery"))
+    }
+
+    pub fn new(dir: PathBuf) -> Self {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        Self::with_session(dir, format!("{}-{}", secs, std::process::id()))
+    }
+
+    fn with_session(dir: PathBuf, session: String) -> Self {
+        let lock = fs::create_dir_all(&dir)
+            .and_then(|_| File::create(lock_file(&dir, &session)))
+            .and_then(|f| f.try_lock().map(|_| f).map_err(io::Error::from));
+        let lock = match lock {
+            Ok(f) => Some(f),
+            Err(e) => {
+                #[cfg(debug_assertions)]
+                crate::log(format!("Failed to lock the recovery session with {:?}", e));
+                #[cfg(not(debug_assertions))]
+                let _ = e;
+                None
+            }
+        };
+
+        Self {
+            dir,
+            session,
+            written: HashMap::new(),
+            last: Instant::now(),
+            lock,
+        }
+    }
+
+    fn file(&self, tab: usize) -> PathBuf {
+        self.dir.join(format!("{}-{}.sgf", self.session, tab))
+    }
+
+    /// Writes the games that changed since their last snapshot, if it is time to
+    pub fn autosave<'a>(&mut self, games: impl Iterator<Item = (usize, &'a Game)>) {
+        if self.last.elapsed() < INTERVAL {
+            return;
+        }
+        self.last = Instant::now();
+
+        for (tab, game) in games {
+            if let Err(e) = self.save(tab, game) {
+                #[cfg(debug_assertions)]
+                crate::log(format!("Failed to write a recovery snapshot with {:?}", e));
+            }
+        }
+    }
+
+    /// Writes a snapshot of the game in `tab`, unless it is the same as the last one
+    pub fn save(&mut self, tab: usize, game: &Game) -> io::Result<()> {
+        let text = Snapshot::to_text(game);
+        let hash = crate::util::calculate_hash(&text);
+        if self.written.get(&tab) == Some(&hash) {
+            return Ok(());
+        }
+
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.file(tab), text)?;
+        self.written.insert(tab, hash);
+
+        Ok(())
+    }
+
+    /// Deletes the snapshot of a tab that was closed
+    pub fn remove(&mut self, tab: usize) {
+        if self.written.remove(&tab).is_some() {
+            let _ = fs::remove_file(self.file(tab));
+        }
+    }
+
+    /// Deletes every snapshot of this session, once it closes cleanly
+    pub fn finish(&mut self) {
+        let tabs: Vec<usize> = self.written.keys().copied().collect();
+        for t in tabs {
+            self.remove(t);
+        }
+
+        if self.lock.take().is_some() {
+            let _ = fs::remove_file(lock_file(&self.dir, &self.session));
+        }
+        self.remove_stale_locks();
+    }
+
+    /// Deletes the locks of sessions that crashed and whose snapshots are all gone
+    fn remove_stale_locks(&self) {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+        let names: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| file_name(&e.path()))
+            .collect();
+
+        for session in names.iter().filter_map(|n| n.strip_suffix(".lock")) {
+            let prefix = format!("{}-", session);
+            if !names.iter().any(|n| n.starts_with(&prefix)) && !is_alive(&self.dir, session) {
+                let _ = fs::remove_file(lock_file(&self.dir, session));
+            }
+        }
+    }
+
+    /// The snapshots left by sessions that didn't close cleanly
+    pub fn lost(&self) -> Vec<PathBuf> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(e) => e,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut alive = HashMap::new();
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|e| e == "sgf"))
+            .filter(|p| {
+                // `<session>-<tab>.sgf`
+                let name = file_name(p);
+                let session = name.rsplit_once('-').map_or("", |(s, _)| s).to_string();
+                session != self.session
+                    && !*alive
+                        .entry(session)
+                        .or_insert_with_key(|s| is_alive(&self.dir, s))
+            })
+            .collect();
+        files.sort();
+
+        files
+    }
+}
+
+fn lock_file(dir: &Path, session: &str) -> PathBuf {
+    dir.join(format!("{}.lock", session))
+}
+
+/// If the session is still running, and holding its lock
+fn is_alive(dir: &Path, session: &str) -> bool {
+    // sessions that crashed before locks were kept have no lock file
+    let Ok(f) = File::open(lock_file(dir, session)) else {
+        return false;
+    };
+
+    matches!(f.try_lock(), Err(TryLockError::WouldBlock))
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod recovery_tests {
+    use super::*;
+    use crate::sgf::Action;
+
+    #[test]
+    fn snapshot() {
+        let text = "(;GM[1]SZ[9](;B[aa];W[bb])(;B[cc]))".to_string();
+        let mut game = Game::from_tree(SgfTree::parse(text).unwrap(), None);
+        game.tree.select_child(1).unwrap();
+        game.do_to_now();
+
+        let s = Snapshot::parse(&Snapshot::to_text(&game)).unwrap();
+        assert_eq!(s.path, None);
+        assert_eq!(s.node, vec![1]);
+
+        let restored = s.into_game();
+        assert!(restored.tree.same_nodes(&game.tree));
+        assert_eq!(
+            restored.tree.current_node().actions,
+            vec![Action::PlayBlack(2, 2)]
+        );
+        // it was never saved
+        assert!(restored.is_modified());
+
+        assert!(Snapshot::parse("(;GM[1])").is_none());
+    }
+
+    #[test]
+    fn sessions() {
+        let dir = std::env::temp_dir().join(format!("go-recovery-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let text = "(;GM[1]SZ[9];B[aa])".to_string();
+        let game = Game::from_tree(SgfTree::parse(text).unwrap(), None);
+
+        let mut crashed = Recovery::with_session(dir.clone(), String::from("0-0"));
+        crashed.save(0, &game).unwrap();
+        crashed.save(1, &game).unwrap();
+
+        let mut current = Recovery::new(dir.clone());
+        current.save(0, &game).unwrap();
+        // still running in another window
+        assert!(current.lost().is_empty());
+
+        // the lock is let go of when the process dies
+        crashed.lock = None;
+        assert_eq!(current.lost().len(), 2);
+
+        current.finish();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+
+        crashed.remove(1);
+        assert_eq!(current.lost(), vec![dir.join("0-0-0.sgf")]);
+
+        // the crashed session's lock goes with its last snapshot
+        crashed.remove(0);
+        current.finish();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
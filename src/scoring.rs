@@ -0,0 +1,305 @@
+use crate::Board;
+use crate::Stone;
+use std::collections::HashSet;
+
+/// Area (Chinese/Tromp-Taylor) score of a position: stones on the board
+/// plus empty regions that only touch stones of one colour.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Score {
+    pub black: usize,
+    pub white: usize,
+}
+impl Score {
+    /// Black's margin of victory after komi. Negative means white wins.
+    pub fn margin(&self, komi: f32) -> f32 {
+        self.black as f32 - self.white as f32 - komi
+    }
+
+    /// The winner after komi, or `Stone::Empty` for a draw
+    pub fn winner(&self, komi: f32) -> Stone {
+        let m = self.margin(komi);
+
+        if m > 0.0 {
+            Stone::Black
+        } else if m < 0.0 {
+            Stone::White
+        } else {
+            Stone::Empty
+        }
+    }
+}
+
+/// The outcome of a game, as written in the sgf `RE` property
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameResult {
+    /// The winner and their margin, ex: `B+3.5`
+    Score(Stone, f32),
+    /// The winner by resignation, ex: `W+R`
+    Resign(Stone),
+    /// The winner on time, ex: `B+T`
+    Time(Stone),
+    /// The winner by forfeit, ex: `W+F`
+    Forfeit(Stone),
+    /// `0`
+    Draw,
+}
+impl GameResult {
+    /// The result of a game that was counted
+    pub fn from_score(score: Score, komi: f32) -> Self {
+        match score.winner(komi) {
+            Stone::Empty => Self::Draw,
+            s => Self::Score(s, score.margin(komi).abs()),
+        }
+    }
+
+    /// The winner, or `Stone::Empty` for a draw
+    pub fn winner(&self) -> Stone {
+        match self {
+            Self::Score(s, _) | Self::Resign(s) | Self::Time(s) | Self::Forfeit(s) => *s,
+            Self::Draw => Stone::Empty,
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if s == "0" || s.eq_ignore_ascii_case("draw") {
+            return Some(Self::Draw);
+        }
+
+        let (color, reason) = s.split_once('+')?;
+        let winner = match color {
+            "B" | "b" => Stone::Black,
+            "W" | "w" => Stone::White,
+            _ => return None,
+        };
+
+        Some(match reason {
+            "R" | "Resign" => Self::Resign(winner),
+            "T" | "Time" => Self::Time(winner),
+            "F" | "Forfeit" => Self::Forfeit(winner),
+            m => Self::Score(winner, m.parse().ok()?),
+        })
+    }
+}
+impl std::fmt::Display for GameResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let color = match self.winner() {
+            Stone::Black => "B",
+            _ => "W",
+        };
+
+        match self {
+            Self::Score(_, m) => write!(f, "{}+{}", color, m),
+            Self::Resign(_) => write!(f, "{}+R", color),
+            Self::Time(_) => write!(f, "{}+T", color),
+            Self::Forfeit(_) => write!(f, "{}+F", color),
+            Self::Draw => write!(f, "0"),
+        }
+    }
+}
+
+/// Scores `board` assuming every stone on it is alive
+pub fn area_score(board: &Board) -> Score {
+    area_score_with_dead(board, &HashSet::new())
+}
+
+/// Scores `board` after removing the `dead` stones
+pub fn area_score_with_dead(board: &Board, dead: &HashSet<(usize, usize)>) -> Score {
+    let mut score = Score::default();
+
+    for_each_owner(board, dead, |_, s| match s {
+        Stone::Black => score.black += 1,
+        Stone::White => score.white += 1,
+        Stone::Empty => {}
+    });
+
+    score
+}
+
+/// Who each point belongs to after removing the `dead` stones, in the same order as
+/// `Board::stones`. Neutral points are `Stone::Empty`.
+pub fn ownership(board: &Board, dead: &HashSet<(usize, usize)>) -> Vec<Stone> {
+    let w = board.size().0;
+    let mut owners = board.stones().clone();
+
+    for_each_owner(board, dead, |(x, y), s| owners[y * w + x] = s);
+
+    owners
+}
+
+/// Calls `f` with each point and who it belongs to, with the `dead` stones taken as empty.
+/// The bot scores every playout with this, so it doesn't copy the board.
+fn for_each_owner(
+    board: &Board,
+    dead: &HashSet<(usize, usize)>,
+    mut f: impl FnMut((usize, usize), Stone),
+) {
+    let (w, h) = board.size();
+    let stone = |x: usize, y: usize| {
+        if dead.contains(&(x, y)) {
+            Stone::Empty
+        } else {
+            board.get(x, y)
+        }
+    };
+    let mut seen = vec![false; w * h];
+
+    for y in 0..h {
+        for x in 0..w {
+            match stone(x, y) {
+                Stone::Empty if seen[y * w + x] => {}
+                Stone::Empty => {
+                    let (points, owner) = empty_region(&stone, (w, h), x, y, &mut seen);
+                    for p in points {
+                        f(p, owner);
+                    }
+                }
+                s => f((x, y), s),
+            }
+        }
+    }
+}
+
+/// Flood fills the empty region containing (`x`, `y`), marking every point in `seen`.
+/// Returns the points in the region and the colour of the stones bordering it,
+/// or `Stone::Empty` if it touches both colours (or none).
+fn empty_region(
+    stone: &impl Fn(usize, usize) -> Stone,
+    (w, h): (usize, usize),
+    x: usize,
+    y: usize,
+    seen: &mut [bool],
+) -> (Vec<(usize, usize)>, Stone) {
+    let mut stack = vec![(x, y)];
+    let mut points = Vec::new();
+    let mut touches_black = false;
+    let mut touches_white = false;
+
+    while let Some(p) = stack.pop() {
+        match stone(p.0, p.1) {
+            Stone::Black => touches_black = true,
+            Stone::White => touches_white = true,
+            Stone::Empty => {
+                if std::mem::replace(&mut seen[p.1 * w + p.0], true) {
+                    continue;
+                }
+                points.push(p);
+
+                if p.0 < w - 1 {
+                    stack.push((p.0 + 1, p.1));
+                }
+                if p.1 < h - 1 {
+                    stack.push((p.0, p.1 + 1));
+                }
+                if p.0 > 0 {
+                    stack.push((p.0 - 1, p.1));
+                }
+                if p.1 > 0 {
+                    stack.push((p.0, p.1 - 1));
+                }
+            }
+        }
+    }
+
+    let owner = match (touches_black, touches_white) {
+        (true, false) => Stone::Black,
+        (false, true) => Stone::White,
+        _ => Stone::Empty,
+    };
+
+    (points, owner)
+}
+
+#[cfg(test)]
+mod scoring_tests {
+    use super::*;
+
+    #[test]
+    fn split_board() {
+        let mut board = Board::new(5, 5);
+
+        // + b w + +
+        // + b w + +
+        // + b w + +
+        // + b w + +
+        // + b w + +
+        for y in 0..5 {
+            board.set(1, y, Stone::Black);
+            board.set(2, y, Stone::White);
+        }
+
+        let score = area_score(&board);
+
+        assert_eq!(
+            score,
+            Score {
+                black: 10,
+                white: 15
+            }
+        );
+        assert_eq!(score.winner(0.5), Stone::White);
+    }
+
+    #[test]
+    fn result_text() {
+        let r = GameResult::from_score(
+            Score {
+                black: 40,
+                white: 41,
+            },
+            6.5,
+        );
+
+        assert_eq!(r, GameResult::Score(Stone::White, 7.5));
+        assert_eq!(r.to_string(), "W+7.5");
+        assert_eq!(GameResult::parse("W+7.5"), Some(r));
+
+        assert_eq!(GameResult::Resign(Stone::Black).to_string(), "B+R");
+        assert_eq!(GameResult::Time(Stone::White).to_string(), "W+T");
+        assert_eq!(GameResult::Draw.to_string(), "0");
+        assert_eq!(
+            GameResult::parse("B+T"),
+            Some(GameResult::Time(Stone::Black))
+        );
+        assert_eq!(GameResult::parse("0"), Some(GameResult::Draw));
+        assert_eq!(GameResult::parse("?"), None);
+    }
+
+    #[test]
+    fn dead_stones() {
+        let mut board = Board::new(5, 5);
+
+        // + b w + +
+        // + b w + +
+        // + b w b +
+        // + b w + +
+        // + b w + +
+        for y in 0..5 {
+            board.set(1, y, Stone::Black);
+            board.set(2, y, Stone::White);
+        }
+        board.set(3, 2, Stone::Black);
+
+        assert_eq!(area_score(&board).black, 11);
+        assert_eq!(area_score(&board).white, 5);
+
+        let dead = HashSet::from([(3, 2)]);
+        assert_eq!(
+            area_score_with_dead(&board, &dead),
+            Score {
+                black: 10,
+                white: 15
+            }
+        );
+    }
+
+    #[test]
+    fn neutral_points() {
+        let mut board = Board::new(5, 5);
+
+        board.set(0, 0, Stone::Black);
+        board.set(4, 4, Stone::White);
+
+        assert_eq!(area_score(&board), Score { black: 1, white: 1 });
+    }
+}
@@ -0,0 +1,107 @@
+//! The files that were opened recently, and the ones that were open when the app closed,
+//! kept in eframe's storage between runs.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ui::ToolType;
+
+/// How many recent files are remembered
+const MAX_RECENT: usize = 10;
+
+#[derive(Clone, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Recent {
+    /// The most recent first
+    pub files: Vec<PathBuf>,
+}
+impl Recent {
+    /// Moves `path` to the front, adding it if it isn't there yet
+    pub fn add(&mut self, path: &Path) {
+        self.files.retain(|p| p != path);
+        self.files.insert(0, path.to_path_buf());
+        self.files.truncate(MAX_RECENT);
+    }
+
+    /// Forgets the files that don't exist anymore
+    pub fn prune(&mut self) {
+        self.files.retain(|p| p.is_file());
+    }
+}
+
+/// A file that was open in a tab
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct OpenFile {
+    pub path: PathBuf,
+    /// The path from the root to the current node, as given by `SgfTree::path_to_current`
+    pub node: Vec<usize>,
+    pub tool: ToolType,
+}
+
+#[derive(Clone, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Session {
+    pub files: Vec<OpenFile>,
+    /// Index on `files` of the one that was shown
+    pub current: Option<usize>,
+}
+impl Session {
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Forgets the files that don't exist anymore, keeping the same one current if it is left
+    pub fn prune(&mut self) {
+        let current = self.current.and_then(|i| self.files.get(i)).cloned();
+        self.files.retain(|f| f.path.is_file());
+        self.current = current.and_then(|c| self.files.iter().position(|f| *f == c));
+    }
+}
+
+#[cfg(test)]
+mod session_tests {
+    use super::*;
+
+    #[test]
+    fn recent() {
+        let mut r = Recent::default();
+        for i in 0..12 {
+            r.add(Path::new(&format!("{}.sgf", i)));
+        }
+        assert_eq!(r.files.len(), MAX_RECENT);
+        assert_eq!(r.files[0], PathBuf::from("11.sgf"));
+
+        r.add(Path::new("5.sgf"));
+        assert_eq!(r.files.len(), MAX_RECENT);
+        assert_eq!(r.files[0], PathBuf::from("5.sgf"));
+        assert_eq!(r.files[1], PathBuf::from("11.sgf"));
+    }
+
+    #[test]
+    fn prune() {
+        let dir = std::env::temp_dir().join(format!("go-session-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let kept = dir.join("kept.sgf");
+        std::fs::write(&kept, "(;GM[1])").unwrap();
+
+        let file = |path: PathBuf| OpenFile {
+            path,
+            node: vec![0],
+            tool: ToolType::Play,
+        };
+        let mut s = Session {
+            files: vec![file(dir.join("missing.sgf")), file(kept.clone())],
+            current: Some(1),
+        };
+        s.prune();
+        assert_eq!(s.files, vec![file(kept.clone())]);
+        assert_eq!(s.current, Some(0));
+
+        let mut r = Recent::default();
+        r.add(&kept);
+        r.add(&dir.join("missing.sgf"));
+        r.prune();
+        assert_eq!(r.files, vec![kept]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
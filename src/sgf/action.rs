@@ -1,9 +1,16 @@
 use super::util::*;
 use super::SgfError;
 use super::SgfResult;
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
 
 /// An action done on the ui that can be converted to an sgf prop
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "json",
+    serde(tag = "type", content = "value", rename_all = "snake_case")
+)]
 pub enum Action {
     NoOp,
     /// B[xy]
@@ -281,7 +288,7 @@ pub fn to_actions(s: &str) -> Vec<Action> {
     for fragment in fragments {
         match fragment {
             PropFragment::Name(n) => {
-                if props.len() == 0 {
+                if props.is_empty() {
                     name = n;
                     continue;
                 }
@@ -306,7 +313,7 @@ pub fn to_actions(s: &str) -> Vec<Action> {
         };
     }
 
-    if !is_list(&name) && props.len() != 0 {
+    if !is_list(&name) && !props.is_empty() {
         let a = Action::from_pair(&name, &props[0]);
         match a {
             Ok(i) => actions.push(i),
@@ -2,6 +2,8 @@
 pub enum SgfError {
     /// Trying to select a child of a node which doesn't exist
     ChildDoesntExist,
+    /// Trying to select a node with an index past the end of the tree
+    NodeDoesntExist,
     /// Trying to accesss the parent of the root node
     ParentOfRoot,
     /// A coordinate > 51 (The max sgf allows)
@@ -17,6 +19,8 @@ pub enum SgfError {
     /// The wrong number of items in a composed value
     /// ex: `LN` prop takes 2 composed coords: `LN[aa:bb]`. `LN[aa:bb:cc]` would be invalid.
     InvalidComposedLength,
+    /// A game in another format, such as gib, that couldn't be read
+    Import(String),
 
     Io(std::io::Error),
 }
@@ -0,0 +1,245 @@
+//! Serde support for `SgfTree` and `Board`, for scripts that read or write games as json.
+//! It is behind the `json` feature.
+//!
+//! A tree is its nodes in order, the root first, and the index of the selected node:
+//!
+//! ```text
+//! {
+//!   "nodes": [
+//!     {
+//!       "actions": [
+//!         {"type": "size", "value": [19, 19]},
+//!         {"type": "other_many", "value": ["PB", ["Alice"]]}
+//!       ],
+//!       "children": [1],
+//!       "parent": null
+//!     },
+//!     {"actions": [{"type": "play_black", "value": [15, 3]}], "children": [], "parent": 0}
+//!   ],
+//!   "current": 0
+//! }
+//! ```
+//!
+//! `children` are indices into `nodes`, the first being the main line, and every node
+//! except the root has a `parent` that comes before it. `current` may be left out, for the root.
+//! Points are from 0 to 51 and sizes from 1 to 52, as in sgf.
+//!
+//! Each action is `{"type": ..., "value": ...}`, with points as `[x, y]` from the top left:
+//!
+//! | type         | sgf      | value                            |
+//! |--------------|----------|----------------------------------|
+//! | `no_op`      |          | none                             |
+//! | `play_black` | `B[xy]`  | `[x, y]`                         |
+//! | `play_white` | `W[xy]`  | `[x, y]`                         |
+//! | `pass_black` | `B[]`    | none                             |
+//! | `pass_white` | `W[]`    | none                             |
+//! | `add_black`  | `AB`     | `[[x, y], ...]`                  |
+//! | `add_white`  | `AW`     | `[[x, y], ...]`                  |
+//! | `size`       | `SZ`     | `[width, height]`                |
+//! | `circle`     | `CR`     | `[[x, y], ...]`                  |
+//! | `cross`      | `MA`     | `[[x, y], ...]`                  |
+//! | `square`     | `SQ`     | `[[x, y], ...]`                  |
+//! | `triangle`   | `TR`     | `[[x, y], ...]`                  |
+//! | `dim`        | `DD`     | `[[x, y], ...]`                  |
+//! | `label`      | `LB`     | `[[x, y, "text"], ...]`          |
+//! | `arrow`      | `AR`     | `[[[x, y], [x, y]], ...]`        |
+//! | `line`       | `LN`     | `[[[x, y], [x, y]], ...]`        |
+//! | `comment`    | `C`      | `"text"`                         |
+//! | `other`      | any      | `["PB", "value"]`                |
+//! | `other_many` | any      | `["AE", ["value", ...]]`         |
+//!
+//! Properties without their own type are read from sgf as `other_many`, even with one value.
+//!
+//! A board is its size, and its stones and markup in rows from the top left:
+//!
+//! ```text
+//! {
+//!   "stones": ["empty", "black", "white", ...],
+//!   "markup": [{"type": "empty"}, {"type": "label", "value": "A"}, {"type": "arrow", "value": [x, y]}, ...],
+//!   "size": [19, 19]
+//! }
+//! ```
+//!
+//! where markup is one of `empty`, `circle`, `cross`, `square`, `triangle`, `dim`,
+//! `label` with its text, or `arrow` and `line` with the point they end at.
+//! The positions a board has seen for ko aren't kept.
+
+use serde::Deserialize;
+
+use super::{Action, SgfNode, SgfTree};
+
+/// A `SgfTree` read from json, before its links are checked
+#[derive(Deserialize)]
+pub(super) struct UncheckedTree {
+    nodes: Vec<SgfNode>,
+    #[serde(default)]
+    current: usize,
+}
+
+impl TryFrom<UncheckedTree> for SgfTree {
+    type Error = String;
+
+    fn try_from(t: UncheckedTree) -> Result<Self, String> {
+        let nodes = t.nodes;
+        if nodes.is_empty() {
+            return Err(String::from("a tree needs a root node"));
+        }
+        if t.current >= nodes.len() {
+            return Err(format!(
+                "current node {} is past the end of the tree",
+                t.current
+            ));
+        }
+
+        for (i, n) in nodes.iter().enumerate() {
+            match n.parent {
+                None if i == 0 => {}
+                None => return Err(format!("node {} has no parent", i)),
+                Some(_) if i == 0 => return Err(String::from("the root has a parent")),
+                // which also means that there are no cycles
+                Some(p) if p >= i => {
+                    return Err(format!("node {} comes before its parent {}", i, p))
+                }
+                Some(p) => {
+                    if nodes[p].children.iter().filter(|&&c| c == i).count() != 1 {
+                        return Err(format!("node {} isn't a child of its parent {}", i, p));
+                    }
+                }
+            }
+
+            for &c in &n.children {
+                if nodes.get(c).and_then(|c| c.parent) != Some(i) {
+                    return Err(format!("node {} isn't the parent of {}", i, c));
+                }
+            }
+
+            for a in &n.actions {
+                match a {
+                    Action::Size(w, h) if !(1..=52).contains(w) || !(1..=52).contains(h) => {
+                        return Err(format!("node {} has a board size of {}x{}", i, w, h));
+                    }
+                    // points past what sgf can write, up to 51
+                    _ if a.to_sgf_text().is_err() => {
+                        return Err(format!("node {} has a point off the board in {:?}", i, a));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(Self {
+            nodes,
+            current: t.current,
+        })
+    }
+}
+
+#[cfg(test)]
+mod json_tests {
+    use super::*;
+    use crate::diagram::ascii;
+    use crate::Board;
+
+    #[test]
+    fn schema() {
+        let mut tree =
+            SgfTree::parse(String::from("(;SZ[9]PB[Alice]LB[aa:A](;B[ee])(;W[]))")).unwrap();
+        tree.select_child(1).unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&tree).unwrap(),
+            concat!(
+                r#"{"nodes":["#,
+                r#"{"actions":[{"type":"size","value":[9,9]},{"type":"other_many","value":["PB",["Alice"]]},"#,
+                r#"{"type":"label","value":[[0,0,"A"]]}],"children":[1,2],"parent":null},"#,
+                r#"{"actions":[{"type":"play_black","value":[4,4]}],"children":[],"parent":0},"#,
+                r#"{"actions":[{"type":"pass_white"}],"children":[],"parent":0}"#,
+                r#"],"current":2}"#
+            )
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let sgf = "(;GM[1]FF[4]SZ[19]PB[Alice]PW[Bob]KM[6.5]AB[dd][pp]AW[dp]\
+            C[a \"game\"\\]];B[qd]CR[qd]MA[aa]SQ[bb]TR[cc]DD[dd]LB[ee:one][ff:2]\
+            (;W[]AR[aa:cc]LN[bb:dd];B[tt])(;W[qp]AE[dd]C[the other one]))";
+        let tree = SgfTree::parse(String::from(sgf)).unwrap();
+
+        let json = serde_json::to_string(&tree).unwrap();
+        let read: SgfTree = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(read, tree);
+        assert_eq!(read.to_text(), tree.to_text());
+        assert_eq!(SgfTree::parse(read.to_text()).unwrap(), tree);
+    }
+
+    #[test]
+    fn hand_written() {
+        // as a script might write it, without `current`
+        let json = r#"{"nodes":[
+            {"actions":[{"type":"size","value":[9,9]},{"type":"add_black","value":[[2,2]]}],"children":[1],"parent":null},
+            {"actions":[{"type":"play_white","value":[6,6]},{"type":"comment","value":"hi"}],"children":[],"parent":0}
+        ]}"#;
+        let tree: SgfTree = serde_json::from_str(json).unwrap();
+
+        assert_eq!(tree.current_index(), 0);
+        assert_eq!(tree.to_text(), "(;SZ[9]AB[cc];W[gg]C[hi])");
+        assert_eq!(
+            tree.node(1).unwrap().actions,
+            vec![Action::PlayWhite(6, 6), Action::Comment(String::from("hi"))]
+        );
+    }
+
+    #[test]
+    fn broken_links() {
+        let broken = [
+            r#"{"nodes":[]}"#,
+            r#"{"nodes":[{"actions":[],"children":[],"parent":null}],"current":1}"#,
+            r#"{"nodes":[{"actions":[],"children":[1],"parent":null}]}"#,
+            r#"{"nodes":[{"actions":[],"children":[],"parent":null},{"actions":[],"children":[],"parent":0}]}"#,
+            r#"{"nodes":[{"actions":[],"children":[1],"parent":null},{"actions":[],"children":[1],"parent":1}]}"#,
+            r#"{"nodes":[{"actions":[],"children":[1,1],"parent":null},{"actions":[],"children":[],"parent":0}]}"#,
+            r#"{"nodes":[{"actions":[{"type":"play_black","value":[60,0]}],"children":[],"parent":null}]}"#,
+            r#"{"nodes":[{"actions":[{"type":"arrow","value":[[[0,0],[0,52]]]}],"children":[],"parent":null}]}"#,
+            r#"{"nodes":[{"actions":[{"type":"size","value":[0,19]}],"children":[],"parent":null}]}"#,
+        ];
+
+        for json in broken {
+            assert!(serde_json::from_str::<SgfTree>(json).is_err(), "{}", json);
+        }
+
+        let edge = r#"{"nodes":[{"actions":[{"type":"play_black","value":[51,0]}],"children":[],"parent":null}]}"#;
+        let tree: SgfTree = serde_json::from_str(edge).unwrap();
+        assert_eq!(tree.to_text(), "(;B[Za])");
+    }
+
+    #[test]
+    fn board() {
+        let mut board = ascii::board(
+            "$$
+             $$ +-------+
+             $$ | . X . |
+             $$ | O X . |
+             $$ | . . . |
+             $$ +-------+",
+        );
+        board.set_markup(2, 2, crate::board::Markup::Label(String::from("A")));
+        board.set_markup(0, 0, crate::board::Markup::Arrow(2, 0));
+
+        let json = serde_json::to_string(&board).unwrap();
+        assert!(json.starts_with(r#"{"stones":["empty","black","empty","white","black""#));
+        assert!(json.contains(r#"{"type":"arrow","value":[2,0]}"#));
+        assert!(json.ends_with(r#"{"type":"label","value":"A"}],"size":[3,3]}"#));
+
+        let read: Board = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.size(), (3, 3));
+        assert_eq!(read.stones(), board.stones());
+        assert_eq!(read.get_markup(0, 0), board.get_markup(0, 0));
+
+        let short = r#"{"stones":["empty"],"markup":[{"type":"empty"}],"size":[3,3]}"#;
+        assert!(serde_json::from_str::<Board>(short).is_err());
+        let huge = format!(r#"{{"stones":[],"markup":[],"size":[{},2]}}"#, usize::MAX);
+        assert!(serde_json::from_str::<Board>(&huge).is_err());
+    }
+}
@@ -0,0 +1,138 @@
+//! Placing the nodes of a tree on a grid, to draw the variations.
+//!
+//! Each node's column is its depth. A line of play (a node followed by its first
+//! children) stays on one row, and each variation gets the first row below its
+//! parent's that is free for the whole length of its line, and the column before it
+//! so that it doesn't look like it follows another line.
+
+use std::collections::HashMap;
+
+use super::{Action, SgfTree};
+
+pub struct TreeLayout {
+    /// The (column, row) of each node
+    pub positions: Vec<(usize, usize)>,
+    /// The number of moves from the root to each node
+    pub move_numbers: Vec<usize>,
+    pub columns: usize,
+    pub rows: usize,
+    cells: HashMap<(usize, usize), usize>,
+}
+impl TreeLayout {
+    pub fn new(tree: &SgfTree) -> Self {
+        let n = tree.num_nodes();
+        let mut positions = vec![(0, 0); n];
+        let mut move_numbers = vec![0; n];
+        let mut cells = HashMap::with_capacity(n);
+        // the first free row in each column
+        let mut free: Vec<usize> = Vec::new();
+
+        // nodes that start a line, with the row of their parent
+        let mut stack = vec![(0, 0)];
+        while let Some((start, parent_row)) = stack.pop() {
+            let mut line = vec![start];
+            while let Some(&c) = tree.nodes[*line.last().unwrap()].children.first() {
+                line.push(c);
+            }
+
+            let column = match tree.nodes[start].parent {
+                Some(p) => positions[p].0 + 1,
+                None => 0,
+            };
+            if free.len() < column + line.len() {
+                free.resize(column + line.len(), 0);
+            }
+            let row = free[column.saturating_sub(1)..column + line.len()]
+                .iter()
+                .copied()
+                .fold(parent_row, usize::max);
+            if column > 0 {
+                free[column - 1] = free[column - 1].max(row + 1);
+            }
+
+            for (k, &i) in line.iter().enumerate() {
+                positions[i] = (column + k, row);
+                free[column + k] = row + 1;
+                cells.insert((column + k, row), i);
+
+                let node = &tree.nodes[i];
+                let before = node.parent.map(|p| move_numbers[p]).unwrap_or(0);
+                move_numbers[i] = before + node.actions.iter().any(is_move) as usize;
+            }
+
+            // the variations nearest the end of the line are placed first,
+            // so the short ones near the end don't push the others far down
+            for &i in &line {
+                for &c in tree.nodes[i].children.iter().skip(1).rev() {
+                    stack.push((c, row));
+                }
+            }
+        }
+
+        Self {
+            columns: free.len(),
+            rows: free.iter().copied().max().unwrap_or(0),
+            positions,
+            move_numbers,
+            cells,
+        }
+    }
+
+    /// The node at a position on the grid
+    pub fn node_at(&self, column: usize, row: usize) -> Option<usize> {
+        self.cells.get(&(column, row)).copied()
+    }
+}
+
+fn is_move(a: &Action) -> bool {
+    crate::util::mover(a).is_some()
+}
+
+#[cfg(test)]
+mod layout_tests {
+    use super::*;
+
+    #[test]
+    fn variations() {
+        // 0 - 1 - 2 - 3
+        //      \- 4 - 5
+        //          \- 6
+        //  \- 7
+        let tree = SgfTree::parse(String::from(
+            "(;GM[1](;B[aa](;W[bb];B[cc])(;W[dd](;B[ee])(;B[ff])))(;B[gg]))",
+        ))
+        .unwrap();
+        let l = TreeLayout::new(&tree);
+
+        assert_eq!(
+            l.positions,
+            vec![
+                (0, 0),
+                (1, 0),
+                (2, 0),
+                (3, 0),
+                (2, 1),
+                (3, 1),
+                (3, 2),
+                (1, 2)
+            ]
+        );
+        assert_eq!(l.move_numbers, vec![0, 1, 2, 3, 2, 3, 3, 1]);
+        assert_eq!((l.columns, l.rows), (4, 3));
+        assert_eq!(l.node_at(3, 2), Some(6));
+        assert_eq!(l.node_at(2, 2), None);
+    }
+
+    #[test]
+    fn long_game() {
+        let mut s = String::from("(;GM[1]");
+        for i in 0..5000 {
+            s.push_str(if i % 2 == 0 { ";B[aa]" } else { ";W[bb]" });
+        }
+        s.push(')');
+
+        let l = TreeLayout::new(&SgfTree::parse(s).unwrap());
+        assert_eq!((l.columns, l.rows), (5001, 1));
+        assert_eq!(l.move_numbers[5000], 5000);
+    }
+}
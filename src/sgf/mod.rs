@@ -1,10 +1,20 @@
 mod action;
 mod error;
+#[cfg(feature = "json")]
+mod json;
+mod layout;
 mod util;
 pub use action::{to_actions, Action};
 pub use error::{SgfError, SgfResult};
+pub use layout::TreeLayout;
+pub use util::{string_coords, to_sgf_coord};
 
-#[derive(Debug, PartialEq)]
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json", serde(try_from = "json::UncheckedTree"))]
 pub struct SgfTree {
     /// The root is stored at nodes[0]
     nodes: Vec<SgfNode>,
@@ -15,6 +25,34 @@ impl SgfTree {
         parse(lex(s))
     }
 
+    /// Parses every game tree in a collection, such as a file of problems
+    pub fn parse_collection(s: String) -> SgfResult<Vec<SgfTree>> {
+        let mut trees = Vec::new();
+        let mut tokens = Vec::new();
+        let mut depth = 0;
+
+        for t in lex(s) {
+            match t {
+                ParserToken::LParen => depth += 1,
+                // ignore anything between game trees
+                _ if depth == 0 => continue,
+                ParserToken::RParen => depth -= 1,
+                ParserToken::Node(_) => {}
+            }
+            tokens.push(t);
+
+            if depth == 0 {
+                trees.push(parse(std::mem::take(&mut tokens))?);
+            }
+        }
+
+        if trees.is_empty() {
+            return Err(SgfError::MissingLParen);
+        }
+
+        Ok(trees)
+    }
+
     pub fn to_text(&self) -> String {
         let mut s = String::from('(');
         self.stringify_node(0, &mut s);
@@ -57,6 +95,39 @@ impl SgfTree {
         &self.nodes[0]
     }
 
+    pub fn root_mut(&mut self) -> &mut SgfNode {
+        &mut self.nodes[0]
+    }
+
+    /// The number of nodes in the tree
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// If both trees have the same nodes, wherever their current nodes are
+    pub fn same_nodes(&self, other: &SgfTree) -> bool {
+        self.nodes == other.nodes
+    }
+
+    /// The node at index `i`, where the root is 0
+    pub fn node(&self, i: usize) -> Option<&SgfNode> {
+        self.nodes.get(i)
+    }
+
+    pub fn current_index(&self) -> usize {
+        self.current
+    }
+
+    /// Makes the node at index `i` the current node
+    pub fn select_node(&mut self, i: usize) -> SgfResult<()> {
+        if i >= self.nodes.len() {
+            return Err(SgfError::NodeDoesntExist);
+        }
+
+        self.current = i;
+        Ok(())
+    }
+
     /// The number of children the current node has
     pub fn num_children(&self) -> usize {
         self.nodes[self.current].children.len()
@@ -84,6 +155,106 @@ impl SgfTree {
         Err(SgfError::ParentOfRoot)
     }
 
+    /// Moves to the next (or previous, if `offset` is negative) variation
+    /// of the current node's parent
+    pub fn select_sibling(&mut self, offset: isize) -> SgfResult<()> {
+        let parent = self.nodes[self.current]
+            .parent
+            .ok_or(SgfError::ParentOfRoot)?;
+        let siblings = &self.nodes[parent].children;
+
+        let i = siblings
+            .iter()
+            .position(|&c| c == self.current)
+            .unwrap_or(0) as isize;
+        let sibling = usize::try_from(i + offset)
+            .ok()
+            .and_then(|j| siblings.get(j))
+            .ok_or(SgfError::ChildDoesntExist)?;
+
+        self.current = *sibling;
+        Ok(())
+    }
+
+    /// The index among its siblings of every node from the root to the current node,
+    /// which, unlike the node's index, doesn't depend on the order the nodes were added in
+    pub fn path_to_current(&self) -> Vec<usize> {
+        let mut path = Vec::new();
+        let mut i = self.current;
+
+        while let Some(p) = self.nodes[i].parent {
+            let n = self.nodes[p].children.iter().position(|&c| c == i);
+            path.push(n.unwrap_or(0));
+            i = p;
+        }
+        path.reverse();
+
+        path
+    }
+
+    /// Goes to the node at the end of `path`, as returned by `path_to_current`
+    pub fn select_path(&mut self, path: &[usize]) -> SgfResult<()> {
+        let mut i = 0;
+        for &n in path {
+            i = *self.nodes[i]
+                .children
+                .get(n)
+                .ok_or(SgfError::ChildDoesntExist)?;
+        }
+
+        self.current = i;
+        Ok(())
+    }
+
+    /// The nodes from `from` down to `to`, or None if `to` isn't `from` or below it
+    pub fn path_between(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        let mut path = vec![to];
+        let mut i = to;
+
+        while i != from {
+            i = self.nodes.get(i)?.parent?;
+            path.push(i);
+        }
+        path.reverse();
+
+        Some(path)
+    }
+
+    /// The nodes from `from` to the end of its main line, following the first children
+    pub fn main_line_from(&self, from: usize) -> Vec<usize> {
+        let mut line = vec![from];
+        while let Some(&c) = self.nodes[*line.last().unwrap()].children.first() {
+            line.push(c);
+        }
+
+        line
+    }
+
+    /// A tree of only the nodes from the root to the current node, without other variations
+    pub fn line_to_current(&self) -> SgfTree {
+        let mut line = vec![self.current];
+        while let Some(p) = self.nodes[*line.last().unwrap()].parent {
+            line.push(p);
+        }
+        line.reverse();
+
+        let len = line.len();
+        let nodes = line
+            .iter()
+            .enumerate()
+            .map(|(j, &i)| SgfNode {
+                actions: self.nodes[i].actions.clone(),
+                children: if j + 1 < len { vec![j + 1] } else { Vec::new() },
+                parent: j.checked_sub(1),
+            })
+            .collect();
+
+        SgfTree {
+            nodes,
+            current: len - 1,
+        }
+    }
+
     pub fn set_root(&mut self, s: String) -> SgfResult<()> {
         self.nodes[0].actions = to_actions(&s);
 
@@ -154,7 +325,8 @@ impl Default for SgfTree {
     }
 }
 
-#[derive(Default, Debug, PartialEq)]
+#[derive(Clone, Default, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
 pub struct SgfNode {
     pub actions: Vec<Action>,
 
@@ -163,6 +335,48 @@ pub struct SgfNode {
     /// All nodes have a parent except the root node
     parent: Option<usize>,
 }
+impl SgfNode {
+    /// Indices of the children, the first being the main line
+    pub fn children(&self) -> &[usize] {
+        &self.children
+    }
+
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+
+    /// The (first) value of a property that isn't represented by its own `Action`,
+    /// such as `KM` or `PB`.
+    pub fn get_prop(&self, name: &str) -> Option<&str> {
+        for a in &self.actions {
+            match a {
+                Action::Other(k, v) if k == name => return Some(v),
+                Action::OtherMany(k, v) if k == name => return v.first().map(|s| s.as_str()),
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    /// Sets a property that isn't represented by its own `Action`,
+    /// replacing any previous value.
+    pub fn set_prop(&mut self, name: &str, value: impl Into<String>) {
+        let a = Action::Other(name.to_string(), value.into());
+
+        for old in self.actions.iter_mut() {
+            match old {
+                Action::Other(k, _) | Action::OtherMany(k, _) if k == name => {
+                    *old = a;
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        self.actions.push(a);
+    }
+}
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum ParserToken {
@@ -356,6 +570,96 @@ mod sgf_tests {
         assert_eq!(parse(l).unwrap(), t);
     }
 
+    #[test]
+    fn get_prop_test() {
+        let t = SgfTree::parse("(;FF[4]KM[6.5]PB[someone])".to_string()).unwrap();
+
+        assert_eq!(t.root().get_prop("KM"), Some("6.5"));
+        assert_eq!(t.root().get_prop("FF"), Some("4"));
+        assert_eq!(t.root().get_prop("PW"), None);
+    }
+
+    #[test]
+    fn set_prop_test() {
+        let mut t = SgfTree::parse("(;FF[4]RE[?])".to_string()).unwrap();
+
+        t.root_mut().set_prop("RE", "B+R");
+        t.root_mut().set_prop("KM", "6.5");
+
+        assert_eq!(t.to_text(), "(;FF[4]RE[B+R]KM[6.5])");
+    }
+
+    #[test]
+    fn collection_test() {
+        let s = "(;GM[1];B[aa](;W[bb])(;W[cc]))\n(;GM[1]C[second];W[dd])".to_string();
+        let trees = SgfTree::parse_collection(s).unwrap();
+
+        assert_eq!(trees.len(), 2);
+        assert_eq!(trees[0].to_text(), "(;GM[1];B[aa](;W[bb])(;W[cc]))");
+        assert_eq!(trees[1].to_text(), "(;GM[1]C[second];W[dd])");
+    }
+
+    #[test]
+    fn sibling_test() {
+        let mut t = SgfTree::parse("(;GM[1](;B[aa])(;B[bb])(;B[cc]))".to_string()).unwrap();
+        assert!(t.select_sibling(1).is_err());
+
+        t.select_child(1).unwrap();
+        t.select_sibling(1).unwrap();
+        assert_eq!(t.current_node().actions, vec![Action::PlayBlack(2, 2)]);
+        assert!(t.select_sibling(1).is_err());
+
+        t.select_sibling(-2).unwrap();
+        assert_eq!(t.current_node().actions, vec![Action::PlayBlack(0, 0)]);
+        assert!(t.select_sibling(-1).is_err());
+    }
+
+    #[test]
+    fn path_test() {
+        let mut t = SgfTree::default();
+        t.handle_new_action(Action::PlayBlack(0, 0), true);
+        t.select_root();
+        t.handle_new_action(Action::PlayBlack(1, 1), true);
+        t.select_sibling(-1).unwrap();
+        t.handle_new_action(Action::PlayWhite(2, 2), true);
+        assert_eq!(t.current_index(), 3);
+        assert_eq!(t.path_to_current(), vec![0, 0]);
+
+        // the nodes are in a different order once written and parsed again
+        let mut parsed = SgfTree::parse(t.to_text()).unwrap();
+        parsed.select_path(&t.path_to_current()).unwrap();
+        assert_eq!(parsed.current_index(), 2);
+        assert_eq!(parsed.current_node().actions, vec![Action::PlayWhite(2, 2)]);
+
+        assert!(parsed.select_path(&[1, 0]).is_err());
+        parsed.select_path(&[]).unwrap();
+        assert_eq!(parsed.current_index(), 0);
+    }
+
+    #[test]
+    fn path_between_test() {
+        let t = SgfTree::parse("(;GM[1];B[aa](;W[bb];B[cc])(;W[dd]))".to_string()).unwrap();
+
+        assert_eq!(t.main_line_from(0), vec![0, 1, 2, 3]);
+        assert_eq!(t.main_line_from(4), vec![4]);
+        assert_eq!(t.path_between(0, 4), Some(vec![0, 1, 4]));
+        assert_eq!(t.path_between(2, 2), Some(vec![2]));
+        assert_eq!(t.path_between(2, 4), None);
+        assert_eq!(t.path_between(0, 9), None);
+    }
+
+    #[test]
+    fn line_test() {
+        let mut t = SgfTree::parse("(;GM[1](;B[aa];W[bb])(;B[cc];W[dd]))".to_string()).unwrap();
+        assert_eq!(t.line_to_current().to_text(), "(;GM[1])");
+
+        t.select_child(1).unwrap();
+        t.select_child(0).unwrap();
+        let line = t.line_to_current();
+        assert_eq!(line.to_text(), "(;GM[1];B[cc];W[dd])");
+        assert_eq!(line.current_node().actions, vec![Action::PlayWhite(3, 3)]);
+    }
+
     #[test]
     fn to_text_test() {
         let s = "(;FF[4];B[pd];W[dp];B[dd](;W[qp];B[oq])(;W[pq];B[qo]))".to_string();
@@ -83,10 +83,7 @@ pub fn points_pair_list(v: &Vec<String>) -> SgfResult<Vec<[(usize, usize); 2]>>
 
 /// Determines if a property accepts a list as its value
 pub fn is_list(name: &str) -> bool {
-    match name {
-        "B" | "W" | "SZ" | "C" | "FF" | "CA" | "GM" => false,
-        _ => true,
-    }
+    !matches!(name, "B" | "W" | "SZ" | "C" | "FF" | "CA" | "GM")
 }
 
 #[test]
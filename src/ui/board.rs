@@ -2,43 +2,148 @@ use super::shapes;
 use super::ToolType;
 use super::UiTool;
 use crate::board::Markup;
+use crate::game::{Numbering, Variation};
 use crate::sgf::Action;
 use crate::Board;
+use crate::Game;
 use crate::Stone;
 
+use std::path::PathBuf;
+
 use eframe::egui;
-use egui::{pos2, vec2, Color32, Pos2, Ui};
+use egui::{pos2, vec2, Align2, Color32, FontFamily, FontId, Pos2, Ui};
+use serde::{Deserialize, Serialize};
+
+/// How stones are lit
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Shading {
+    Flat,
+    /// Lighter towards the top left
+    Gradient,
+    /// A shadow below and to the right
+    Shadow,
+}
 
-#[derive(Clone, Copy)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct BoardStyle {
-    background_color: Color32,
+    pub name: String,
+    pub background_color: Color32,
+    /// An image stretched over the background, such as wood grain
+    pub texture: Option<PathBuf>,
     /// as a proportion of the board width/height
-    padding: f32,
-    line_thickness: f32,
-    star_point_radius: f32,
+    pub padding: f32,
+    pub line_color: Color32,
+    pub line_thickness: f32,
+    pub star_point_radius: f32,
     /// as a proportion of the min of the spacing between
     /// horizontal lines and the spacing between vertical lines
-    stone_radius: f32,
+    pub stone_radius: f32,
+    pub black_color: Color32,
+    pub white_color: Color32,
+    /// The width of the line around stones, which is drawn in `line_color`
+    pub outline: f32,
+    pub shading: Shading,
+    pub markup_color: Color32,
+    /// For labels, move numbers and coordinates
+    pub font: FontFamily,
 }
 
 impl Default for BoardStyle {
     fn default() -> Self {
         Self {
+            name: String::from("Classic"),
             background_color: Color32::from_rgb(0xDE, 0xB8, 0x87),
+            texture: None,
             padding: 0.05,
+            line_color: Color32::BLACK,
             line_thickness: 2.0,
             star_point_radius: 5.0,
             stone_radius: 0.4,
+            black_color: Color32::BLACK,
+            white_color: Color32::WHITE,
+            outline: 0.0,
+            shading: Shading::Flat,
+            markup_color: Color32::RED,
+            font: FontFamily::Monospace,
+        }
+    }
+}
+impl BoardStyle {
+    pub fn stone_color(&self, s: Stone) -> Color32 {
+        match s {
+            Stone::Black => self.black_color,
+            Stone::White => self.white_color,
+            Stone::Empty => self.background_color,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Coordinates {
+    Off,
+    /// Letters without I from the left, and numbers from the bottom
+    Gtp,
+    /// The letters used in sgf properties, from the top left
+    Sgf,
+}
+
+/// Optional information drawn over the board
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Overlays {
+    pub numbering: Numbering,
+    pub last_move: bool,
+    pub coordinates: Coordinates,
+}
+impl Default for Overlays {
+    fn default() -> Self {
+        Self {
+            numbering: Numbering::Off,
+            last_move: true,
+            coordinates: Coordinates::Off,
         }
     }
 }
 
+pub fn overlay_settings(ui: &mut Ui, overlays: &mut Overlays) {
+    egui::CollapsingHeader::new("Display").show(ui, |ui| {
+        ui.label("move numbers");
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut overlays.numbering, Numbering::Off, "off");
+            ui.radio_value(&mut overlays.numbering, Numbering::All, "all");
+            ui.radio_value(
+                &mut overlays.numbering,
+                Numbering::SinceVariation,
+                "since variation",
+            );
+
+            let last = matches!(overlays.numbering, Numbering::Last(_));
+            if ui.radio(last, "last").clicked() && !last {
+                overlays.numbering = Numbering::Last(10);
+            }
+            if let Numbering::Last(n) = &mut overlays.numbering {
+                ui.add(egui::DragValue::new(n).clamp_range(1..=999));
+            }
+        });
+
+        ui.checkbox(&mut overlays.last_move, "mark the last move");
+
+        ui.horizontal(|ui| {
+            ui.label("coordinates");
+            ui.radio_value(&mut overlays.coordinates, Coordinates::Off, "off");
+            ui.radio_value(&mut overlays.coordinates, Coordinates::Gtp, "A1");
+            ui.radio_value(&mut overlays.coordinates, Coordinates::Sgf, "aa");
+        });
+    });
+}
+
 pub(super) struct BoardRenderer {
     response: egui::Response,
     painter: egui::Painter,
     inner_rect: egui::Rect,
     spacing: egui::Vec2,
     stone_radius: f32,
+    style: BoardStyle,
 }
 impl BoardRenderer {
     pub fn build(ui: &mut Ui, board: &Board, size: egui::Vec2, style: &BoardStyle) -> Self {
@@ -74,11 +179,14 @@ impl BoardRenderer {
             inner_rect,
             spacing,
             stone_radius,
+            style: style.clone(),
         }
     }
 
-    pub fn render_board(&self, board: &Board, style: &BoardStyle) {
+    /// Draws the board and the stones on it, over `texture` if there is one
+    pub fn render_board(&self, board: &Board, texture: Option<egui::TextureId>) {
         let (w, h) = board.size();
+        let style = &self.style;
 
         // Draw background color
         self.painter.rect_filled(
@@ -86,12 +194,17 @@ impl BoardRenderer {
             egui::Rounding::none(),
             style.background_color,
         );
+        if let Some(t) = texture {
+            let uv = egui::Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0));
+            self.painter
+                .image(t, self.response.rect, uv, Color32::WHITE);
+        }
 
         // draw outermost lines
         self.painter.rect_stroke(
             self.inner_rect,
             egui::Rounding::none(),
-            egui::Stroke::new(style.line_thickness, Color32::BLACK),
+            egui::Stroke::new(style.line_thickness, style.line_color),
         );
 
         // draw vertical lines
@@ -102,7 +215,7 @@ impl BoardRenderer {
 
             self.painter.line_segment(
                 [pos2(x_pos, start_y), pos2(x_pos, end_y)],
-                egui::Stroke::from((style.line_thickness, Color32::BLACK)),
+                egui::Stroke::from((style.line_thickness, style.line_color)),
             );
         }
 
@@ -114,7 +227,7 @@ impl BoardRenderer {
 
             self.painter.line_segment(
                 [pos2(start_x, y_pos), pos2(end_x, y_pos)],
-                egui::Stroke::from((style.line_thickness, Color32::BLACK)),
+                egui::Stroke::from((style.line_thickness, style.line_color)),
             );
         }
 
@@ -124,7 +237,7 @@ impl BoardRenderer {
                 y: self.inner_rect.min.y + self.spacing.y * (p.1 as f32),
             };
             self.painter
-                .circle_filled(pos, style.star_point_radius, Color32::BLACK);
+                .circle_filled(pos, style.star_point_radius, style.line_color);
         }
 
         // draw stones
@@ -135,15 +248,7 @@ impl BoardRenderer {
                     y: self.inner_rect.min.y + self.spacing.y * (y as f32),
                 };
 
-                if board.get(x, y) == crate::Stone::Black {
-                    self.painter
-                        .circle_filled(center, self.stone_radius, Color32::BLACK);
-                }
-
-                if board.get(x, y) == crate::Stone::White {
-                    self.painter
-                        .circle_filled(center, self.stone_radius, Color32::WHITE);
-                }
+                self.draw_stone(board.get(x, y), center);
             }
         }
 
@@ -160,20 +265,162 @@ impl BoardRenderer {
         }
     }
 
+    /// Draws the moves of other variations as see-through stones with their letters
+    pub fn render_variations(&self, board: &Board, variations: &[Variation]) {
+        for v in variations {
+            if board.get(v.x, v.y) != Stone::Empty || board.get_markup(v.x, v.y) != Markup::Empty {
+                continue;
+            }
+
+            let center = self.point_pos(v.x, v.y);
+            let [r, g, b, _] = self.style.stone_color(v.stone).to_array();
+            let fill = Color32::from_rgba_unmultiplied(r, g, b, 120);
+            self.painter.circle_filled(center, self.stone_radius, fill);
+
+            self.painter.text(
+                center,
+                Align2::CENTER_CENTER,
+                v.label,
+                FontId::new(self.stone_radius, self.style.font.clone()),
+                contrast(self.style.stone_color(v.stone)),
+            );
+        }
+    }
+
+    /// Draws move numbers, the last move marker and coordinates
+    pub fn render_overlays(&self, game: &Game, overlays: &Overlays) {
+        let board = &game.board;
+        let last = game.last_move().filter(|_| overlays.last_move);
+        let mut last_numbered = false;
+
+        for (x, y, n) in game.move_numbers(overlays.numbering) {
+            // markup is more important than the number
+            if board.get_markup(x, y) != Markup::Empty {
+                continue;
+            }
+
+            let color = if last == Some((x, y)) {
+                last_numbered = true;
+                self.style.markup_color
+            } else {
+                contrast(self.style.stone_color(board.get(x, y)))
+            };
+            let size = if n < 100 { 1.0 } else { 0.75 } * self.stone_radius;
+
+            self.painter.text(
+                self.point_pos(x, y),
+                Align2::CENTER_CENTER,
+                n.to_string(),
+                FontId::new(size, self.style.font.clone()),
+                color,
+            );
+        }
+
+        if let Some((x, y)) = last.filter(|_| !last_numbered) {
+            self.painter.circle_stroke(
+                self.point_pos(x, y),
+                0.5 * self.stone_radius,
+                (2.0, contrast(self.style.stone_color(board.get(x, y)))),
+            );
+        }
+
+        self.render_coordinates(board, overlays.coordinates);
+    }
+
+    fn render_coordinates(&self, board: &Board, coordinates: Coordinates) {
+        let (w, h) = board.size();
+
+        let column: &dyn Fn(usize) -> String = match coordinates {
+            Coordinates::Off => return,
+            Coordinates::Gtp if w <= 25 => &|x| crate::gtp::column_letter(x).to_string(),
+            Coordinates::Gtp => &|x| (x + 1).to_string(),
+            Coordinates::Sgf => &|x| crate::sgf::to_sgf_coord(x).unwrap_or('?').to_string(),
+        };
+        let row: &dyn Fn(usize) -> String = match coordinates {
+            Coordinates::Sgf => &|y| crate::sgf::to_sgf_coord(y).unwrap_or('?').to_string(),
+            _ => &|y| (h - y).to_string(),
+        };
+
+        // the labels go in the middle of the padding around the lines
+        let margin = self.inner_rect.min - self.response.rect.min;
+        let size = (0.5 * margin.x.min(margin.y)).min(self.stone_radius);
+        let font = FontId::new(size, self.style.font.clone());
+
+        for x in 0..w {
+            let pos_x = self.point_pos(x, 0).x;
+            for pos_y in [
+                self.inner_rect.min.y - 0.5 * margin.y,
+                self.inner_rect.max.y + 0.5 * margin.y,
+            ] {
+                self.painter.text(
+                    pos2(pos_x, pos_y),
+                    Align2::CENTER_CENTER,
+                    column(x),
+                    font.clone(),
+                    self.style.line_color,
+                );
+            }
+        }
+
+        for y in 0..h {
+            let pos_y = self.point_pos(0, y).y;
+            for pos_x in [
+                self.inner_rect.min.x - 0.5 * margin.x,
+                self.inner_rect.max.x + 0.5 * margin.x,
+            ] {
+                self.painter.text(
+                    pos2(pos_x, pos_y),
+                    Align2::CENTER_CENTER,
+                    row(y),
+                    font.clone(),
+                    self.style.line_color,
+                );
+            }
+        }
+    }
+
+    fn point_pos(&self, x: usize, y: usize) -> Pos2 {
+        pos2(
+            self.inner_rect.min.x + self.spacing.x * (x as f32),
+            self.inner_rect.min.y + self.spacing.y * (y as f32),
+        )
+    }
+
+    fn draw_stone(&self, stone: Stone, center: Pos2) {
+        if stone == Stone::Empty {
+            return;
+        }
+
+        let style = &self.style;
+        for (c, r, color) in stone_circles(style, stone, center, self.stone_radius) {
+            self.painter.circle_filled(c, r, color);
+        }
+
+        if style.outline > 0.0 {
+            self.painter.circle_stroke(
+                center,
+                self.stone_radius,
+                (style.outline, style.line_color),
+            );
+        }
+    }
+
     fn draw_markup(&self, markup: Markup, center: Pos2) {
+        let color = self.style.markup_color;
+
         match markup {
             Markup::Empty => {}
             Markup::Circle => {
-                shapes::circle(&self.painter, center, self.stone_radius);
+                shapes::circle(&self.painter, center, self.stone_radius, color);
             }
             Markup::Cross => {
-                shapes::cross(&self.painter, center, self.stone_radius);
+                shapes::cross(&self.painter, center, self.stone_radius, color);
             }
             Markup::Square => {
-                shapes::square(&self.painter, center, self.stone_radius);
+                shapes::square(&self.painter, center, self.stone_radius, color);
             }
             Markup::Triangle => {
-                shapes::triangle(&self.painter, center, self.stone_radius);
+                shapes::triangle(&self.painter, center, self.stone_radius, color);
             }
             Markup::Dim => {
                 shapes::dim(&self.painter, center, self.spacing);
@@ -183,30 +430,51 @@ impl BoardRenderer {
                     x: self.inner_rect.min.x + self.spacing.x * (end_x as f32),
                     y: self.inner_rect.min.y + self.spacing.y * (end_y as f32),
                 };
-                shapes::arrow(&self.painter, center, end);
+                shapes::arrow(&self.painter, center, end, color);
             }
             Markup::Line(end_x, end_y) => {
                 let end = egui::Pos2 {
                     x: self.inner_rect.min.x + self.spacing.x * (end_x as f32),
                     y: self.inner_rect.min.y + self.spacing.y * (end_y as f32),
                 };
-                shapes::line(&self.painter, center, end);
+                shapes::line(&self.painter, center, end, color);
             }
             Markup::Label(s) => {
-                shapes::label(&self.painter, &s, center, self.stone_radius);
+                let family = self.style.font.clone();
+                shapes::label(&self.painter, &s, center, self.stone_radius, family, color);
             }
         }
     }
 
-    pub fn handle_click(
-        &self,
-        ui: &mut Ui,
-        board: &mut Board,
-        tool: &mut UiTool,
-        turn: &mut Stone,
-    ) -> Action {
+    /// Marks the owner of each point while counting:
+    /// small squares on territory and on dead stones
+    pub fn render_ownership(&self, board: &Board, owners: &[Stone]) {
+        let (w, h) = board.size();
+
+        for y in 0..h {
+            for x in 0..w {
+                let owner = owners[y * w + x];
+                if owner == Stone::Empty || owner == board.get(x, y) {
+                    continue;
+                }
+
+                let center = egui::Pos2 {
+                    x: self.inner_rect.min.x + self.spacing.x * (x as f32),
+                    y: self.inner_rect.min.y + self.spacing.y * (y as f32),
+                };
+                let color = self.style.stone_color(owner);
+
+                let r = 0.4 * self.stone_radius;
+                let rect = egui::Rect::from_center_size(center, vec2(2.0 * r, 2.0 * r));
+                self.painter.rect_filled(rect, 0.0, color);
+            }
+        }
+    }
+
+    /// The point on the board that was clicked this frame, if any
+    pub fn clicked_point(&self, ui: &mut Ui, board: &Board) -> Option<(usize, usize)> {
         if !self.response.clicked() {
-            return Action::NoOp;
+            return None;
         }
 
         let (w, h) = board.size();
@@ -216,15 +484,28 @@ impl BoardRenderer {
             #[cfg(debug_assertions)]
             crate::log("pointer interact pos is None");
 
-            return Action::NoOp;
+            return None;
         }
 
         let p = op_pos.unwrap();
 
-        let (x, y) = (
+        Some((
             (((p.x - self.inner_rect.min.x) / self.spacing.x).round() as usize).min(w - 1),
             (((p.y - self.inner_rect.min.y) / self.spacing.y).round() as usize).min(h - 1),
-        );
+        ))
+    }
+
+    pub fn handle_click(
+        &self,
+        ui: &mut Ui,
+        board: &mut Board,
+        tool: &mut UiTool,
+        turn: &mut Stone,
+    ) -> Action {
+        let (x, y) = match self.clicked_point(ui, board) {
+            Some(p) => p,
+            None => return Action::NoOp,
+        };
 
         match tool.tool {
             ToolType::Play => {
@@ -315,3 +596,67 @@ impl BoardRenderer {
         Action::NoOp
     }
 }
+
+/// The filled circles that make up a stone of radius `r`, from the bottom up,
+/// as `(center, radius, colour)`
+pub fn stone_circles(
+    style: &BoardStyle,
+    stone: Stone,
+    center: Pos2,
+    r: f32,
+) -> Vec<(Pos2, f32, Color32)> {
+    let color = style.stone_color(stone);
+
+    match style.shading {
+        Shading::Flat => vec![(center, r, color)],
+        Shading::Shadow => {
+            let shadow = Color32::from_black_alpha(90);
+            vec![
+                (center + vec2(0.12 * r, 0.12 * r), r, shadow),
+                (center, r, color),
+            ]
+        }
+        Shading::Gradient => {
+            // egui can't fill with a gradient, so smaller and lighter circles
+            // are drawn towards the highlight
+            const STEPS: usize = 8;
+            let edge = mix(color, Color32::BLACK, 0.25);
+            let highlight = mix(color, Color32::WHITE, 0.35);
+
+            (0..STEPS)
+                .map(|i| {
+                    let t = i as f32 / STEPS as f32;
+                    let offset = -0.3 * r * t;
+                    (
+                        center + vec2(offset, offset),
+                        r * (1.0 - 0.65 * t),
+                        mix(edge, highlight, t),
+                    )
+                })
+                .collect()
+        }
+    }
+}
+
+/// Black or white, whichever shows up better on `c`
+pub fn contrast(c: Color32) -> Color32 {
+    let brightness = 0.299 * c.r() as f32 + 0.587 * c.g() as f32 + 0.114 * c.b() as f32;
+
+    if brightness > 127.0 {
+        Color32::BLACK
+    } else {
+        Color32::WHITE
+    }
+}
+
+/// The colour `t` of the way from `a` to `b`
+fn mix(a: Color32, b: Color32, t: f32) -> Color32 {
+    let channel = |x: u8, y: u8| (x as f32 + (y as f32 - x as f32) * t).round() as u8;
+
+    Color32::from_rgba_unmultiplied(
+        channel(a.r(), b.r()),
+        channel(a.g(), b.g()),
+        channel(a.b(), b.b()),
+        channel(a.a(), b.a()),
+    )
+}
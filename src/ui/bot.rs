@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use crate::bot::{BotConfig, SearchLimit, Thinking};
+use crate::sgf::Action;
+use crate::util::calculate_hash;
+use crate::{Game, Stone};
+use eframe::egui;
+use egui::Ui;
+
+#[derive(Default)]
+pub struct BotState {
+    /// The colour the bot plays, if it is enabled
+    pub color: Option<Stone>,
+    pub config: BotConfig,
+    thinking: Option<Thinking>,
+}
+impl BotState {
+    /// If the bot is the one who should move next
+    pub fn to_move(&self, turn: Stone) -> bool {
+        self.color == Some(turn)
+    }
+}
+
+/// Starts the bot thinking when it is its turn, and returns its move once it is ready
+pub fn update(ui: &mut Ui, bot: &mut BotState, game: &mut Game) -> Action {
+    if !bot.to_move(game.turn) {
+        bot.thinking = None;
+        return Action::NoOp;
+    }
+
+    let position = calculate_hash(game.board.stones());
+    let stale = match &bot.thinking {
+        Some(t) => t.color != game.turn || t.position != position,
+        None => true,
+    };
+    if stale {
+        bot.thinking = Some(Thinking::start(
+            &game.board,
+            game.turn,
+            game.komi(),
+            bot.config,
+        ));
+    }
+
+    if let Some(a) = bot.thinking.as_ref().and_then(|t| t.poll()) {
+        bot.thinking = None;
+
+        game.do_action(&a);
+        return a;
+    }
+
+    ui.ctx().request_repaint_after(Duration::from_millis(50));
+
+    Action::NoOp
+}
+
+pub fn bot_settings(ui: &mut Ui, bot: &mut BotState) {
+    egui::CollapsingHeader::new("Bot").show(ui, |ui| {
+        egui::ComboBox::from_label("bot plays")
+            .selected_text(match bot.color {
+                None => "Nobody",
+                Some(Stone::Black) => "Black",
+                Some(_) => "White",
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut bot.color, None, "Nobody");
+                ui.selectable_value(&mut bot.color, Some(Stone::Black), "Black");
+                ui.selectable_value(&mut bot.color, Some(Stone::White), "White");
+            });
+
+        ui.horizontal(|ui| {
+            let mut by_time = matches!(bot.config.limit, SearchLimit::Time(_));
+
+            if ui.radio_value(&mut by_time, false, "playouts").clicked() {
+                bot.config.limit = SearchLimit::Playouts(2000);
+            }
+            if ui.radio_value(&mut by_time, true, "seconds").clicked() {
+                bot.config.limit = SearchLimit::Time(Duration::from_secs(3));
+            }
+        });
+
+        match &mut bot.config.limit {
+            SearchLimit::Playouts(n) => {
+                ui.add(egui::Slider::new(n, 100..=50_000).logarithmic(true));
+            }
+            SearchLimit::Time(d) => {
+                let mut secs = d.as_secs_f32();
+                ui.add(egui::Slider::new(&mut secs, 0.5..=60.0));
+                *d = Duration::from_secs_f32(secs);
+            }
+        }
+
+        if bot.thinking.is_some() {
+            ui.label("thinking...");
+        }
+    });
+}
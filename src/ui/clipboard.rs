@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+
+use crate::{Game, SgfTree};
+use eframe::egui;
+use egui::Context;
+
+/// The game in sgf text, or an ascii diagram, pasted this frame, unless the text was pasted into a text field
+pub fn pasted_game(ctx: &Context) -> Option<Game> {
+    if ctx.wants_keyboard_input() {
+        return None;
+    }
+
+    let text = ctx.input(|i| {
+        i.events.iter().find_map(|e| match e {
+            egui::Event::Paste(t) => Some(t.clone()),
+            _ => None,
+        })
+    })?;
+
+    // sensei's library diagrams start their lines with `$$`
+    let tree = if text.contains("$$") && !text.trim_start().starts_with('(') {
+        crate::diagram::ascii::parse(&text)
+    } else {
+        SgfTree::parse(text).map_err(|e| format!("{:?}", e))
+    };
+
+    match tree {
+        Ok(tree) => {
+            let mut game = Game::from_tree(tree, None);
+            // so that it isn't closed without asking to save it
+            game.set_saved(None);
+            Some(game)
+        }
+        Err(e) => {
+            #[cfg(debug_assertions)]
+            crate::log(format!("Failed to parse the pasted text with {}", e));
+            None
+        }
+    }
+}
+
+/// The sgf files, or games that can be imported, dropped onto the window this frame
+pub fn dropped_files(ctx: &Context) -> Vec<PathBuf> {
+    ctx.input(|i| {
+        i.raw
+            .dropped_files
+            .iter()
+            .filter_map(|f| f.path.clone())
+            .filter(|p| {
+                p.extension().is_some_and(|e| e.eq_ignore_ascii_case("sgf"))
+                    || crate::import::Format::from_path(p).is_some()
+            })
+            .collect()
+    })
+}
+
+/// Copies the whole game, with every variation
+pub fn copy_game(ctx: &Context, game: &Game) {
+    let text = game.tree.to_text();
+    ctx.output_mut(|o| o.copied_text = text);
+}
+
+/// Copies the moves from the start of the game to the current one
+pub fn copy_variation(ctx: &Context, game: &Game) {
+    let text = game.tree.line_to_current().to_text();
+    ctx.output_mut(|o| o.copied_text = text);
+}
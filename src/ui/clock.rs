@@ -0,0 +1,140 @@
+use std::time::{Duration, Instant};
+
+use crate::clock::{format_duration, TimeControl};
+use crate::{Game, Stone};
+use eframe::egui;
+use egui::{RichText, Ui};
+
+/// Shows both players' clocks, highlighting the one that is running
+pub fn clocks(ui: &mut Ui, game: &mut Game) {
+    if let Some(loser) = game.check_timeout() {
+        #[cfg(debug_assertions)]
+        crate::log(format!("{:?} ran out of time", loser));
+    }
+
+    let clock = match &game.clock {
+        Some(c) => c,
+        None => return,
+    };
+
+    let now = Instant::now();
+    for (color, name) in [(Stone::Black, "Black"), (Stone::White, "White")] {
+        let c = clock.get(color, now);
+
+        let mut text = format!("{}: {}", name, format_duration(c.time_left()));
+        match (clock.control, c.overtime_left()) {
+            (TimeControl::ByoYomi { .. }, Some(n)) => text.push_str(&format!(" ({} left)", n)),
+            (TimeControl::Canadian { .. }, Some(n)) => text.push_str(&format!(" ({} stones)", n)),
+            _ => {}
+        }
+        if c.flagged {
+            text.push_str(" - out of time");
+        }
+
+        let mut text = RichText::new(text).monospace();
+        if clock.running() == Some(color) {
+            text = text.strong();
+        }
+        ui.label(text);
+    }
+
+    if clock.running().is_some() {
+        ui.ctx().request_repaint_after(Duration::from_millis(100));
+    }
+}
+
+/// Lets the user pick the time control when creating a game
+pub fn time_control_editor(ui: &mut Ui, control: &mut Option<TimeControl>) {
+    let minutes = Duration::from_secs(60);
+    let seconds = Duration::from_secs(1);
+
+    egui::ComboBox::from_label("time control")
+        .selected_text(match control {
+            None => "None",
+            Some(TimeControl::Absolute { .. }) => "Absolute",
+            Some(TimeControl::ByoYomi { .. }) => "Byo-yomi",
+            Some(TimeControl::Canadian { .. }) => "Canadian",
+            Some(TimeControl::Fischer { .. }) => "Fischer",
+        })
+        .show_ui(ui, |ui| {
+            if ui.selectable_label(control.is_none(), "None").clicked() {
+                *control = None;
+            }
+
+            let absolute = TimeControl::Absolute { main: 30 * minutes };
+            let byo_yomi = TimeControl::ByoYomi {
+                main: 10 * minutes,
+                periods: 5,
+                period: 30 * seconds,
+            };
+            let canadian = TimeControl::Canadian {
+                main: 10 * minutes,
+                stones: 25,
+                period: 5 * minutes,
+            };
+            let fischer = TimeControl::Fischer {
+                main: 5 * minutes,
+                increment: 10 * seconds,
+            };
+
+            for (c, name) in [
+                (absolute, "Absolute"),
+                (byo_yomi, "Byo-yomi"),
+                (canadian, "Canadian"),
+                (fischer, "Fischer"),
+            ] {
+                let selected = control
+                    .map(|old| std::mem::discriminant(&old) == std::mem::discriminant(&c))
+                    .unwrap_or(false);
+
+                if ui.selectable_label(selected, name).clicked() && !selected {
+                    *control = Some(c);
+                }
+            }
+        });
+
+    match control {
+        None => {}
+        Some(TimeControl::Absolute { main }) => {
+            duration_editor(ui, "main time (minutes)", main, minutes);
+        }
+        Some(TimeControl::ByoYomi {
+            main,
+            periods,
+            period,
+        }) => {
+            duration_editor(ui, "main time (minutes)", main, minutes);
+            ui.add(egui::DragValue::new(periods).prefix("periods: "));
+            duration_editor(ui, "period (seconds)", period, seconds);
+        }
+        Some(TimeControl::Canadian {
+            main,
+            stones,
+            period,
+        }) => {
+            duration_editor(ui, "main time (minutes)", main, minutes);
+            ui.add(
+                egui::DragValue::new(stones)
+                    .clamp_range(1..=1000)
+                    .prefix("stones: "),
+            );
+            duration_editor(ui, "period (minutes)", period, minutes);
+        }
+        Some(TimeControl::Fischer { main, increment }) => {
+            duration_editor(ui, "main time (minutes)", main, minutes);
+            duration_editor(ui, "increment (seconds)", increment, seconds);
+        }
+    }
+}
+
+/// Edits `d` as a whole number of `unit`s
+fn duration_editor(ui: &mut Ui, label: &str, d: &mut Duration, unit: Duration) {
+    let mut n = d.as_secs() / unit.as_secs();
+
+    ui.horizontal(|ui| {
+        ui.add(egui::DragValue::new(&mut n).clamp_range(0..=600));
+        ui.label(label);
+    });
+
+    *d = unit * n as u32;
+}
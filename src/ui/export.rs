@@ -0,0 +1,292 @@
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::time::Duration;
+
+use super::{BoardStyle, Overlays};
+use crate::diagram::{ascii, Animation, Diagram, Kifu, Options, Region};
+use crate::Game;
+use eframe::egui;
+use egui::Ui;
+
+pub struct ExportState {
+    /// If only `region` is exported, rather than the whole board
+    crop: bool,
+    region: Region,
+    spacing: f32,
+    /// How many of the last moves are numbered in ascii diagrams
+    ascii_moves: usize,
+    /// Which nodes are animated
+    sequence: Sequence,
+    delay: u32,
+    last_delay: u32,
+    captions: bool,
+    kifu: Kifu,
+    /// The animation being written on a background thread
+    saving: Option<Receiver<Result<(), String>>>,
+    error: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Sequence {
+    MainLine,
+    ToCurrent,
+    FromMarked,
+}
+impl Default for ExportState {
+    fn default() -> Self {
+        Self {
+            crop: false,
+            region: Region::whole(19, 19),
+            spacing: Options::default().spacing,
+            ascii_moves: 0,
+            sequence: Sequence::MainLine,
+            delay: Animation::default().delay,
+            last_delay: Animation::default().last_delay,
+            captions: false,
+            kifu: Kifu::default(),
+            saving: None,
+            error: None,
+        }
+    }
+}
+
+/// Saves the current position as an svg or png diagram, with the board's style and overlays.
+/// `marked` is the node of the tab's game that an animation from the mark starts at.
+pub fn export_panel(
+    ui: &mut Ui,
+    ex: &mut ExportState,
+    marked: &mut Option<usize>,
+    game: &Game,
+    style: &BoardStyle,
+    overlays: &Overlays,
+) {
+    egui::CollapsingHeader::new("Export diagram").show(ui, |ui| {
+        let (w, h) = game.board.size();
+
+        ui.checkbox(&mut ex.crop, "only part of the board");
+        if ex.crop {
+            egui::Grid::new("export region").show(ui, |ui| {
+                ui.label("columns");
+                point_value(ui, &mut ex.region.min.0, w);
+                point_value(ui, &mut ex.region.max.0, w);
+                ui.end_row();
+
+                ui.label("rows");
+                point_value(ui, &mut ex.region.min.1, h);
+                point_value(ui, &mut ex.region.max.1, h);
+                ui.end_row();
+            });
+        }
+        ui.add(egui::Slider::new(&mut ex.spacing, 10.0..=100.0).text("line spacing"));
+
+        ui.horizontal(|ui| {
+            for (extension, button) in [("svg", "save svg"), ("png", "save png")] {
+                if !ui.button(button).clicked() {
+                    continue;
+                }
+
+                let path = match rfd::FileDialog::new()
+                    .add_filter(extension, &[extension])
+                    .save_file()
+                {
+                    Some(p) => p,
+                    None => continue,
+                };
+
+                let diagram = Diagram::new(game, style, &ex.options(overlays));
+                ex.error = diagram.write(&path).err();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button("copy ascii").clicked() {
+                let region = ex.crop.then_some(ex.region);
+                let text = ascii::to_ascii(game, ex.ascii_moves, region);
+                ui.ctx().output_mut(|o| o.copied_text = text);
+            }
+            ui.add(
+                egui::DragValue::new(&mut ex.ascii_moves)
+                    .clamp_range(0..=ascii::MAX_MOVES)
+                    .suffix(" numbered moves"),
+            );
+        });
+
+        ui.separator();
+        animation_settings(ui, ex, marked, game, style, overlays);
+
+        ui.separator();
+        print_settings(ui, ex, game);
+
+        ui.separator();
+        if ui
+            .button("save web page")
+            .on_hover_text("a viewer for the whole game, with its variations and comments")
+            .clicked()
+        {
+            if let Some(path) = rfd::FileDialog::new()
+                .add_filter("html", &["html", "htm"])
+                .save_file()
+            {
+                ex.error = crate::html::write(&game.tree, &path).err();
+            }
+        }
+
+        if let Some(e) = &ex.error {
+            ui.label(format!("failed to export: {}", e));
+        }
+    });
+}
+
+/// Saves a sequence of nodes as an animated gif or apng
+fn animation_settings(
+    ui: &mut Ui,
+    ex: &mut ExportState,
+    marked: &mut Option<usize>,
+    game: &Game,
+    style: &BoardStyle,
+    overlays: &Overlays,
+) {
+    ui.label("animation");
+    ui.horizontal(|ui| {
+        ui.selectable_value(&mut ex.sequence, Sequence::MainLine, "main line");
+        ui.selectable_value(&mut ex.sequence, Sequence::ToCurrent, "to this move");
+        ui.selectable_value(&mut ex.sequence, Sequence::FromMarked, "from mark");
+    });
+    if ex.sequence == Sequence::FromMarked {
+        ui.horizontal(|ui| {
+            if ui.button("mark this node").clicked() {
+                *marked = Some(game.tree.current_index());
+            }
+            match marked {
+                Some(m) => ui.label(format!("marked node {}", m)),
+                None => ui.label("no node marked"),
+            };
+        });
+    }
+
+    ui.add(
+        egui::Slider::new(&mut ex.delay, 100..=5000)
+            .text("ms per move")
+            .logarithmic(true),
+    );
+    ui.add(
+        egui::Slider::new(&mut ex.last_delay, 100..=10000)
+            .text("ms on the last move")
+            .logarithmic(true),
+    );
+    ui.checkbox(&mut ex.captions, "show comments");
+
+    if let Some(r) = &ex.saving {
+        match r.try_recv() {
+            Ok(result) => {
+                ex.error = result.err();
+                ex.saving = None;
+            }
+            Err(TryRecvError::Empty) => {
+                ui.label("saving the animation...");
+                ui.ctx().request_repaint_after(Duration::from_millis(100));
+                return;
+            }
+            Err(TryRecvError::Disconnected) => {
+                ex.error = Some(String::from("the animation stopped before it was saved"));
+                ex.saving = None;
+            }
+        }
+    }
+
+    ui.horizontal(|ui| {
+        for (extension, button) in [("gif", "save gif"), ("png", "save apng")] {
+            if !ui.button(button).clicked() {
+                continue;
+            }
+
+            let nodes = match ex.nodes(game, *marked) {
+                Some(n) => n,
+                None => {
+                    ex.error = Some(String::from("the marked node isn't above this one"));
+                    continue;
+                }
+            };
+            let path = match rfd::FileDialog::new()
+                .add_filter(extension, &[extension])
+                .save_file()
+            {
+                Some(p) => p,
+                None => continue,
+            };
+
+            let animation = Animation {
+                diagram: ex.options(overlays),
+                delay: ex.delay,
+                last_delay: ex.last_delay,
+                caption_lines: if ex.captions { 3 } else { 0 },
+            };
+            // drawing every frame takes a while, so it doesn't hold up the ui
+            let (sender, receiver) = channel();
+            let tree = game.tree.clone();
+            let style = style.clone();
+            std::thread::spawn(move || {
+                let _ = sender.send(animation.write(tree, &nodes, &style, &path));
+            });
+            ex.saving = Some(receiver);
+        }
+    });
+}
+
+/// Saves the main line as a printable game record
+fn print_settings(ui: &mut Ui, ex: &mut ExportState, game: &Game) {
+    ui.label("print game");
+    ui.horizontal(|ui| {
+        ui.label("moves per figure");
+        ui.add(egui::DragValue::new(&mut ex.kifu.per_figure).clamp_range(1..=500));
+    })
+    .response
+    .on_hover_text("figures marked in the sgf are used instead");
+
+    ui.horizontal(|ui| {
+        for (extension, button) in [("pdf", "save pdf"), ("svg", "save svg")] {
+            if !ui.button(button).clicked() {
+                continue;
+            }
+
+            let path = match rfd::FileDialog::new()
+                .add_filter(extension, &[extension])
+                .save_file()
+            {
+                Some(p) => p,
+                None => continue,
+            };
+
+            ex.error = ex.kifu.write(game, &path).err();
+        }
+    });
+}
+
+impl ExportState {
+    fn options(&self, overlays: &Overlays) -> Options {
+        Options {
+            overlays: *overlays,
+            region: self.crop.then_some(self.region),
+            spacing: self.spacing,
+        }
+    }
+
+    /// The nodes to animate, or None if the marked node isn't above the current one
+    fn nodes(&self, game: &Game, marked: Option<usize>) -> Option<Vec<usize>> {
+        let tree = &game.tree;
+        match self.sequence {
+            Sequence::MainLine => Some(tree.main_line_from(0)),
+            Sequence::ToCurrent => tree.path_between(0, tree.current_index()),
+            Sequence::FromMarked => tree.path_between(marked?, tree.current_index()),
+        }
+    }
+}
+
+/// Edits a coordinate from the top left, shown counting from 1
+fn point_value(ui: &mut Ui, value: &mut usize, size: usize) {
+    ui.add(
+        egui::DragValue::new(value)
+            .clamp_range(0..=size - 1)
+            .custom_formatter(|n, _| (n as usize + 1).to_string())
+            .custom_parser(|s| s.parse::<f64>().ok().map(|n| n - 1.0)),
+    );
+}
@@ -0,0 +1,124 @@
+use crate::guess::{Grade, Guessing, Record};
+use crate::{Game, Stone};
+use eframe::egui;
+use egui::Ui;
+
+#[derive(Default)]
+pub struct GuessState {
+    /// The colour whose moves are guessed, or `None` for both
+    side: Option<Stone>,
+    guessing: Option<Guessing>,
+    /// Earlier results for the same game
+    previous: Vec<Record>,
+    error: Option<String>,
+}
+impl GuessState {
+    /// If moves are being guessed, so the rest of the game should stay hidden
+    pub fn is_active(&self) -> bool {
+        self.guessing.is_some()
+    }
+
+    /// Saves the result once there are no more moves to guess
+    fn end(&mut self) {
+        let g = match self.guessing.take() {
+            Some(g) => g,
+            None => return,
+        };
+
+        let r = g.finish();
+        if r.total() == 0 {
+            return;
+        }
+
+        match Record::default_path() {
+            Some(p) => {
+                if let Err(e) = r.append(&p) {
+                    self.error = Some(format!("failed to save the result: {}", e));
+                }
+            }
+            None => self.error = Some(String::from("there is nowhere to save the result")),
+        }
+        self.previous.push(r);
+    }
+}
+
+/// Guesses the next move at (`x`, `y`)
+pub fn guess(gs: &mut GuessState, game: &mut Game, x: usize, y: usize) {
+    let g = match &mut gs.guessing {
+        Some(g) => g,
+        None => return,
+    };
+
+    g.guess(game, x, y);
+    if g.is_over(game) {
+        gs.end();
+    }
+}
+
+pub fn guess_panel(ui: &mut Ui, gs: &mut GuessState, game: &mut Game) {
+    egui::CollapsingHeader::new("Guess the move").show(ui, |ui| {
+        match &gs.guessing {
+            None => {
+                ui.horizontal(|ui| {
+                    ui.label("guess");
+                    ui.radio_value(&mut gs.side, None, "both");
+                    ui.radio_value(&mut gs.side, Some(Stone::Black), "Black");
+                    ui.radio_value(&mut gs.side, Some(Stone::White), "White");
+                });
+
+                if ui.button("start guessing").clicked() {
+                    let g = Guessing::start(game, gs.side);
+
+                    gs.previous = Record::default_path()
+                        .map(|p| Record::load_all(&p))
+                        .unwrap_or_else(|| Ok(Vec::new()))
+                        .unwrap_or_else(|e| {
+                            gs.error = Some(format!("failed to read results: {}", e));
+                            Vec::new()
+                        });
+                    gs.previous.retain(|r| r.game == g.record.game);
+
+                    gs.guessing = Some(g);
+                }
+            }
+
+            Some(g) => {
+                let r = &g.record;
+                ui.label(format!(
+                    "exact: {}  near: {}  miss: {}  ({:.0}% exact)",
+                    r.exact,
+                    r.near,
+                    r.miss,
+                    100.0 * r.accuracy()
+                ));
+
+                if let Some(f) = g.last {
+                    ui.label(match f.grade {
+                        Grade::Exact => String::from("Exact!"),
+                        Grade::Near => format!("Near: {} away", f.distance),
+                        Grade::Miss => format!("Miss: {} away", f.distance),
+                    });
+                }
+
+                if ui.button("stop guessing").clicked() {
+                    gs.end();
+                }
+            }
+        }
+
+        if !gs.previous.is_empty() {
+            ui.label("results for this game:");
+            for r in gs.previous.iter().rev().take(5) {
+                ui.label(format!(
+                    "{:.0}% exact over {} moves",
+                    100.0 * r.accuracy(),
+                    r.total()
+                ));
+            }
+        }
+
+        if let Some(e) = &gs.error {
+            ui.label(e.as_str());
+        }
+    });
+}
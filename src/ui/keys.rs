@@ -0,0 +1,216 @@
+use super::ToolType;
+use eframe::egui;
+use egui::{Context, Key, KeyboardShortcut, Modifiers, Ui};
+use serde::{Deserialize, Serialize};
+
+/// Something that can be done with a keyboard shortcut
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Command {
+    Back,
+    Forward,
+    /// Ten moves back
+    BackMany,
+    /// Ten moves forward
+    ForwardMany,
+    Start,
+    End,
+    PreviousVariation,
+    NextVariation,
+    Tool(ToolType),
+    Pass,
+    Save,
+    SaveAs,
+    Open,
+    NewGame,
+    /// Copies the game as sgf, with every variation
+    CopyGame,
+    /// Copies the moves from the root to the current node as sgf
+    CopyVariation,
+}
+impl Command {
+    fn name(&self) -> String {
+        match self {
+            Self::Back => String::from("previous move"),
+            Self::Forward => String::from("next move"),
+            Self::BackMany => String::from("10 moves back"),
+            Self::ForwardMany => String::from("10 moves forward"),
+            Self::Start => String::from("start of the game"),
+            Self::End => String::from("end of the variation"),
+            Self::PreviousVariation => String::from("previous variation"),
+            Self::NextVariation => String::from("next variation"),
+            Self::Tool(t) => format!("{:?} tool", t),
+            Self::Pass => String::from("pass"),
+            Self::Save => String::from("save"),
+            Self::SaveAs => String::from("save as"),
+            Self::Open => String::from("open file"),
+            Self::NewGame => String::from("new game"),
+            Self::CopyGame => String::from("copy game"),
+            Self::CopyVariation => String::from("copy variation"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Binding {
+    pub command: Command,
+    pub modifiers: Modifiers,
+    pub key: Key,
+}
+impl Binding {
+    const fn new(command: Command, modifiers: Modifiers, key: Key) -> Self {
+        Self {
+            command,
+            modifiers,
+            key,
+        }
+    }
+}
+
+const DEFAULT_BINDINGS: [Binding; 27] = {
+    use Command::*;
+    const NONE: Modifiers = Modifiers::NONE;
+    const SHIFT: Modifiers = Modifiers::SHIFT;
+
+    [
+        Binding::new(Back, NONE, Key::ArrowLeft),
+        Binding::new(Forward, NONE, Key::ArrowRight),
+        Binding::new(BackMany, NONE, Key::PageUp),
+        Binding::new(ForwardMany, NONE, Key::PageDown),
+        Binding::new(Start, NONE, Key::Home),
+        Binding::new(End, NONE, Key::End),
+        Binding::new(PreviousVariation, NONE, Key::ArrowUp),
+        Binding::new(NextVariation, NONE, Key::ArrowDown),
+        Binding::new(Tool(ToolType::Play), NONE, Key::Num1),
+        Binding::new(Tool(ToolType::AddBlack), NONE, Key::Num2),
+        Binding::new(Tool(ToolType::AddWhite), NONE, Key::Num3),
+        Binding::new(Tool(ToolType::Circle), NONE, Key::Num4),
+        Binding::new(Tool(ToolType::Cross), NONE, Key::Num5),
+        Binding::new(Tool(ToolType::Square), NONE, Key::Num6),
+        Binding::new(Tool(ToolType::Triangle), NONE, Key::Num7),
+        Binding::new(Tool(ToolType::Dim), NONE, Key::Num8),
+        Binding::new(Tool(ToolType::Arrow), NONE, Key::Num9),
+        Binding::new(Tool(ToolType::Line), NONE, Key::Num0),
+        Binding::new(Tool(ToolType::Number), SHIFT, Key::Num1),
+        Binding::new(Tool(ToolType::Letter), SHIFT, Key::Num2),
+        Binding::new(Pass, NONE, Key::P),
+        Binding::new(Save, Modifiers::COMMAND, Key::S),
+        Binding::new(SaveAs, Modifiers::COMMAND.plus(SHIFT), Key::S),
+        Binding::new(Open, Modifiers::COMMAND, Key::O),
+        Binding::new(NewGame, Modifiers::COMMAND, Key::N),
+        Binding::new(CopyGame, Modifiers::COMMAND.plus(SHIFT), Key::C),
+        Binding::new(
+            CopyVariation,
+            Modifiers::COMMAND.plus(Modifiers::ALT),
+            Key::C,
+        ),
+    ]
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct Keybindings {
+    pub bindings: Vec<Binding>,
+    /// If the cheat sheet is shown
+    #[serde(skip)]
+    pub open: bool,
+    /// Index on `bindings` waiting for a new key
+    #[serde(skip)]
+    editing: Option<usize>,
+}
+impl Default for Keybindings {
+    fn default() -> Self {
+        Self {
+            bindings: DEFAULT_BINDINGS.to_vec(),
+            open: false,
+            editing: None,
+        }
+    }
+}
+impl Keybindings {
+    /// Adds the default binding of every command that doesn't have one,
+    /// such as ones added since the bindings were saved
+    pub fn with_defaults(mut self) -> Self {
+        for d in DEFAULT_BINDINGS {
+            if !self.bindings.iter().any(|b| b.command == d.command) {
+                self.bindings.push(d);
+            }
+        }
+
+        self
+    }
+
+    /// The commands whose shortcuts were pressed this frame.
+    /// Nothing is pressed while typing or choosing a new key.
+    pub fn pressed(&self, ctx: &Context) -> Vec<Command> {
+        if self.editing.is_some() || ctx.wants_keyboard_input() {
+            return Vec::new();
+        }
+
+        ctx.input_mut(|i| {
+            self.bindings
+                .iter()
+                .filter(|b| i.consume_key(b.modifiers, b.key))
+                .map(|b| b.command)
+                .collect()
+        })
+    }
+}
+
+/// Lists every shortcut, and lets them be changed
+pub fn cheat_sheet(ui: &mut Ui, keys: &mut Keybindings) {
+    if let Some(i) = keys.editing {
+        let pressed = ui.input(|input| {
+            input.events.iter().find_map(|e| match e {
+                egui::Event::Key {
+                    key,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } => Some((*key, *modifiers)),
+                _ => None,
+            })
+        });
+
+        match pressed {
+            Some((Key::Escape, _)) => keys.editing = None,
+            Some((key, modifiers)) => {
+                keys.bindings[i].key = key;
+                keys.bindings[i].modifiers = modifiers;
+                keys.editing = None;
+            }
+            None => {}
+        }
+    }
+
+    let mut open = keys.open;
+    egui::Window::new("keyboard shortcuts")
+        .open(&mut open)
+        .show(ui.ctx(), |ui| {
+            egui::Grid::new("shortcuts").striped(true).show(ui, |ui| {
+                for (i, b) in keys.bindings.iter().enumerate() {
+                    ui.label(b.command.name());
+
+                    if keys.editing == Some(i) {
+                        ui.label("press a key, or escape to cancel");
+                    } else {
+                        let s = KeyboardShortcut::new(b.modifiers, b.key);
+                        if ui.button(ui.ctx().format_shortcut(&s)).clicked() {
+                            keys.editing = Some(i);
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+
+            if ui.button("reset to defaults").clicked() {
+                *keys = Keybindings {
+                    open: true,
+                    ..Default::default()
+                };
+            }
+        });
+
+    keys.open = open;
+    if !open {
+        keys.editing = None;
+    }
+}
@@ -1,103 +1,793 @@
 use std::ops::DerefMut;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use eframe::egui;
 use egui::{vec2, Ui, Vec2};
 
+use crate::game::GameState;
+use crate::session::{OpenFile, Recent, Session};
 use crate::sgf::Action;
 use crate::{Game, State, Stone};
+use keys::Command;
 
 mod board;
+mod bot;
+mod clipboard;
+mod clock;
+mod export;
+mod guess;
+mod keys;
+mod net;
+mod problem;
+mod result;
 mod sgf;
 mod shapes;
+mod theme;
 mod tool;
+mod tree;
 
-pub use board::BoardStyle;
+pub use board::{contrast, stone_circles, BoardStyle, Coordinates, Overlays};
+pub use theme::presets;
 pub use tool::*;
 
+#[derive(Default)]
 pub struct UiState {
     style: BoardStyle,
+    themes: theme::ThemeState,
+    overlays: Overlays,
     debug_window: bool,
+    keys: keys::Keybindings,
+    /// The network lobby and problem collection of the game creator,
+    /// which are moved to the tab of the game they start
+    creator: TabState,
+    /// Index on `State::tabs` of a tab waiting to be closed, that has unsaved changes
+    closing: Option<usize>,
+    quit: Quit,
+    /// Snapshots from sessions that didn't close cleanly, which can be restored
+    pub lost: Vec<PathBuf>,
+    /// Why snapshots in `lost` couldn't be restored, which are kept until the user discards them
+    recovery_errors: Vec<String>,
+    recent: Recent,
+    export: export::ExportState,
+    /// The files that were open when the app last closed
+    last_session: Session,
+}
+impl UiState {
+    /// Whether the app can close now.
+    /// If there are unsaved changes, the user is asked what to do with them first.
+    pub fn allow_close(&mut self, tabs: &[Tab]) -> bool {
+        if self.quit == Quit::Confirmed || !tabs.iter().any(|t| t.game.is_modified()) {
+            return true;
+        }
+
+        self.quit = Quit::Asking;
+        false
+    }
+
+    /// Restores the settings that are kept between runs
+    pub fn load(&mut self, storage: &dyn eframe::Storage) {
+        if let Some(k) = eframe::get_value::<keys::Keybindings>(storage, KEYS) {
+            self.keys = k.with_defaults();
+        }
+        if let Some(o) = eframe::get_value(storage, OVERLAYS) {
+            self.overlays = o;
+        }
+        if let Some(s) = eframe::get_value(storage, STYLE) {
+            self.style = s;
+        }
+        if let Some(t) = eframe::get_value(storage, THEMES) {
+            self.themes.saved = t;
+        }
+        if let Some(r) = eframe::get_value::<Recent>(storage, RECENT) {
+            self.recent = r;
+            self.recent.prune();
+        }
+        if let Some(s) = eframe::get_value::<Session>(storage, SESSION) {
+            self.last_session = s;
+            self.last_session.prune();
+        }
+    }
+
+    /// Keeps the settings, and the files open in `tabs`
+    pub fn save(&self, tabs: &[Tab], current: Option<usize>, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, KEYS, &self.keys);
+        eframe::set_value(storage, OVERLAYS, &self.overlays);
+        eframe::set_value(storage, STYLE, &self.style);
+        eframe::set_value(storage, THEMES, &self.themes.saved);
+        eframe::set_value(storage, RECENT, &self.recent);
+
+        // games that were never saved can't be reopened
+        let mut session = Session::default();
+        for (i, t) in tabs.iter().enumerate() {
+            if let Some(path) = &t.game.path {
+                if current == Some(i) {
+                    session.current = Some(session.files.len());
+                }
+                session.files.push(OpenFile {
+                    path: path.clone(),
+                    node: t.game.tree.path_to_current(),
+                    tool: t.state.tool.tool,
+                });
+            }
+        }
+        eframe::set_value(storage, SESSION, &session);
+    }
+}
+
+const KEYS: &str = "keybindings";
+const OVERLAYS: &str = "overlays";
+const STYLE: &str = "style";
+const THEMES: &str = "themes";
+const RECENT: &str = "recent_files";
+const SESSION: &str = "session";
+
+#[derive(Default, PartialEq, Eq)]
+enum Quit {
+    #[default]
+    Running,
+    /// Asking what to do with unsaved changes before closing
+    Asking,
+    /// The user chose to close the app
+    Confirmed,
+}
+
+/// The parts of the ui that belong to one game
+#[derive(Default)]
+pub struct TabState {
     tool: UiTool,
     /// Index on the current node
     comment: Option<usize>,
+    bot: bot::BotState,
+    net: net::NetState,
+    problem: problem::ProblemState,
+    guess: guess::GuessState,
+    tree: tree::TreeView,
+    /// The node an animation from the mark starts at
+    marked: Option<usize>,
 }
-impl Default for UiState {
-    fn default() -> Self {
+impl TabState {
+    fn clear_comment(&mut self) {
+        self.comment = None;
+    }
+
+    /// Moving around the tree would put the board out of sync with a network opponent's,
+    /// or give away a problem's solution or the moves being guessed
+    fn browsable(&self, game: &Game) -> bool {
+        !self.net.in_game(game) && !self.problem.is_active() && !self.guess.is_active()
+    }
+}
+
+static NEXT_TAB: AtomicUsize = AtomicUsize::new(0);
+
+/// An open game
+pub struct Tab {
+    /// Unique for the session, naming the tab's recovery snapshot
+    pub id: usize,
+    pub game: Game,
+    state: TabState,
+}
+impl Tab {
+    pub fn new(game: Game) -> Self {
         Self {
-            style: BoardStyle::default(),
-            debug_window: false,
-            tool: UiTool {
-                tool: ToolType::Play,
-                base: None,
-                letter: 'A',
-                number: 1,
-            },
-            comment: None,
+            id: NEXT_TAB.fetch_add(1, Ordering::Relaxed),
+            game,
+            state: TabState::default(),
+        }
+    }
+
+    /// The file name, marked with a * if there are unsaved changes
+    fn title(&self) -> String {
+        let name = self
+            .game
+            .path
+            .as_ref()
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| String::from("untitled"));
+
+        if self.game.is_modified() {
+            format!("{} *", name)
+        } else {
+            name
         }
     }
 }
-impl UiState {
-    fn clear_comment(&mut self) {
-        self.comment = None;
+
+/// Switching between open games, closing them, and starting new ones
+pub fn tab_bar(state: &mut State, ui: &mut Ui) {
+    let mut close = None;
+
+    ui.horizontal_wrapped(|ui| {
+        for (i, t) in state.tabs.iter().enumerate() {
+            if ui
+                .selectable_label(state.current == Some(i), t.title())
+                .clicked()
+            {
+                state.current = Some(i);
+            }
+            if ui.small_button("x").clicked() {
+                close = Some(i);
+            }
+            ui.separator();
+        }
+
+        if ui
+            .selectable_label(state.current.is_none(), "new game")
+            .clicked()
+        {
+            state.current = None;
+        }
+    });
+
+    match close {
+        Some(i) if state.tabs[i].game.is_modified() => state.ui_state.closing = Some(i),
+        Some(i) => close_tab(state, i),
+        None => {}
     }
+
+    close_prompt(ui, state);
 }
 
-pub fn render(state: &mut State, ui: &mut Ui, size: Vec2) {
-    if state.game.is_none() {
-        ui.vertical(|ui| {
-            if game_creator(&mut state.builder, ui) {
-                state.game = Some(state.builder.build());
+fn close_tab(state: &mut State, i: usize) {
+    let tab = state.tabs.remove(i);
+    if let Some(r) = &mut state.recovery {
+        r.remove(tab.id);
+    }
+
+    state.current = match state.current {
+        Some(c) if c > i => Some(c - 1),
+        Some(c) if c == i && state.tabs.is_empty() => None,
+        Some(c) if c == i => Some(i.min(state.tabs.len() - 1)),
+        c => c,
+    };
+}
+
+/// Asks what to do with the unsaved changes of a tab that is being closed
+fn close_prompt(ui: &mut Ui, state: &mut State) {
+    let i = match state.ui_state.closing {
+        Some(i) if i < state.tabs.len() => i,
+        _ => {
+            state.ui_state.closing = None;
+            return;
+        }
+    };
+
+    let mut close = false;
+    let mut cancel = false;
+    egui::Window::new("unsaved changes")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ui.ctx(), |ui| {
+            ui.label(format!(
+                "{} has unsaved changes. Save them before closing?",
+                state.tabs[i].title().trim_end_matches(" *")
+            ));
+
+            ui.horizontal(|ui| {
+                if ui.button("save").clicked() {
+                    match state.tabs[i].game.write_to_file() {
+                        Ok(saved) => close = saved,
+                        Err(e) => {
+                            #[cfg(debug_assertions)]
+                            crate::log(format!("Failed to save with {:?}", e));
+                        }
+                    }
+                }
+                close |= ui.button("don't save").clicked();
+                cancel = ui.button("cancel").clicked();
+            });
+        });
+
+    if close {
+        close_tab(state, i);
+    }
+    if close || cancel {
+        state.ui_state.closing = None;
+    }
+}
+
+/// The window's title, naming the game being shown
+pub fn window_title(state: &State) -> String {
+    match state.current.and_then(|i| state.tabs.get(i)) {
+        Some(t) => format!("{} - Go", t.title()),
+        None => String::from("Go"),
+    }
+}
+
+/// Asks what to do with unsaved changes when the app is being closed.
+/// Returns true once the app should close.
+pub fn quit_prompt(ui: &mut Ui, state: &mut State) -> bool {
+    if state.ui_state.quit != Quit::Asking {
+        return false;
+    }
+
+    let mut quit = false;
+    let mut cancel = false;
+    egui::Window::new("quit")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ui.ctx(), |ui| {
+            ui.label("Some games have unsaved changes:");
+            for t in state.tabs.iter().filter(|t| t.game.is_modified()) {
+                ui.label(t.title().trim_end_matches(" *"));
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button("save all").clicked() {
+                    // stop at the first game that wasn't saved, so that it isn't lost
+                    quit = state
+                        .tabs
+                        .iter_mut()
+                        .filter(|t| t.game.is_modified())
+                        .all(|t| match t.game.write_to_file() {
+                            Ok(saved) => saved,
+                            Err(e) => {
+                                #[cfg(debug_assertions)]
+                                crate::log(format!("Failed to save with {:?}", e));
+                                false
+                            }
+                        });
+                }
+                quit |= ui.button("don't save").clicked();
+                cancel = ui.button("cancel").clicked();
+            });
+        });
+
+    if quit {
+        state.ui_state.quit = Quit::Confirmed;
+    }
+    if cancel {
+        state.ui_state.quit = Quit::Running;
+    }
+
+    quit
+}
+
+/// Offers to restore the games of sessions that didn't close cleanly
+pub fn recovery_prompt(ui: &mut Ui, state: &mut State) {
+    if state.ui_state.lost.is_empty() {
+        return;
+    }
+
+    let mut restore = false;
+    let mut discard = false;
+    let mut later = false;
+    egui::Window::new("recover games")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ui.ctx(), |ui| {
+            ui.label(format!(
+                "{} games weren't closed properly last time. Restore them?",
+                state.ui_state.lost.len()
+            ));
+            for e in &state.ui_state.recovery_errors {
+                ui.label(format!("couldn't restore {}", e));
             }
+
+            ui.horizontal(|ui| {
+                restore = ui.button("restore").clicked();
+                discard = ui.button("discard").clicked();
+                later = ui.button("ask again next time").clicked();
+            });
         });
-    } else {
-        let game_mut = state.game.as_mut().unwrap();
-        let a = render_game(&mut state.ui_state, game_mut, ui, size);
-
-        if a != crate::sgf::Action::NoOp {
-            let n = crate::util::new_node(&a);
-            if n {
-                game_mut.board.clear_markup();
-                state.ui_state.tool.clear();
-                state.ui_state.clear_comment();
+
+    if restore {
+        let mut failed = Vec::new();
+        state.ui_state.recovery_errors.clear();
+
+        for p in std::mem::take(&mut state.ui_state.lost) {
+            let snapshot = std::fs::read_to_string(&p)
+                .map_err(|e| e.to_string())
+                .and_then(|t| {
+                    crate::recovery::Snapshot::parse(&t)
+                        .ok_or_else(|| String::from("it isn't a snapshot this version can read"))
+                });
+            match snapshot {
+                Ok(s) => add_tab(state, Tab::new(s.into_game())),
+                // it may be the only copy of the game, so it stays until the user discards it
+                Err(e) => {
+                    state
+                        .ui_state
+                        .recovery_errors
+                        .push(format!("{}: {}", p.display(), e));
+                    failed.push(p);
+                    continue;
+                }
+            }
+
+            // the restored game is snapshotted again as part of this session,
+            // before the old snapshot goes so that it is never without one
+            let tab = &state.tabs[state.tabs.len() - 1];
+            let saved = match &mut state.recovery {
+                Some(r) => r.save(tab.id, &tab.game),
+                None => Ok(()),
+            };
+            match saved {
+                Ok(()) => {
+                    let _ = std::fs::remove_file(p);
+                }
+                Err(e) => {
+                    #[cfg(debug_assertions)]
+                    crate::log(format!("Failed to write a recovery snapshot with {:?}", e));
+                    #[cfg(not(debug_assertions))]
+                    let _ = e;
+                }
+            }
+        }
+
+        state.ui_state.lost = failed;
+    }
+    if discard {
+        for p in std::mem::take(&mut state.ui_state.lost) {
+            let _ = std::fs::remove_file(p);
+        }
+        state.ui_state.recovery_errors.clear();
+    }
+    if later {
+        state.ui_state.lost.clear();
+        state.ui_state.recovery_errors.clear();
+    }
+}
+
+/// Opens a game in a new tab
+fn add_tab(state: &mut State, tab: Tab) {
+    state.tabs.push(tab);
+    state.current = Some(state.tabs.len() - 1);
+}
+
+pub fn render(state: &mut State, ui: &mut Ui, size: Vec2) {
+    let commands = state.ui_state.keys.pressed(ui.ctx());
+    keys::cheat_sheet(ui, &mut state.ui_state.keys);
+
+    if commands.contains(&Command::Open) {
+        open_file(state);
+    }
+    if commands.contains(&Command::NewGame) {
+        state.current = None;
+    }
+    for p in clipboard::dropped_files(ui.ctx()) {
+        open_path(state, p);
+    }
+    if let Some(g) = clipboard::pasted_game(ui.ctx()) {
+        add_tab(state, Tab::new(g));
+    }
+
+    match state.current {
+        Some(i) if i < state.tabs.len() => {
+            let a = render_game(&mut state.ui_state, &mut state.tabs[i], ui, size, &commands);
+            play(&mut state.tabs[i], a);
+
+            #[cfg(debug_assertions)]
+            debug_window(ui, &state.ui_state, &state.tabs[i].game);
+        }
+        _ => {
+            state.current = None;
+            ui.vertical(|ui| creator(ui, state));
+        }
+    }
+
+    // games in the other tabs go on while they aren't shown
+    for tab in &mut state.tabs {
+        let a = opponent_move(ui, tab);
+        play(tab, a);
+    }
+}
+
+/// The network opponent's or the bot's move, if one has arrived
+fn opponent_move(ui: &mut Ui, tab: &mut Tab) -> Action {
+    let (game_mut, tab) = (&mut tab.game, &mut tab.state);
+    if tab.problem.is_active() || tab.guess.is_active() {
+        return Action::NoOp;
+    }
+
+    let mut a = net::update(ui, &mut tab.net, game_mut);
+    if a == Action::NoOp && game_mut.state == GameState::InProgress {
+        a = bot::update(ui, &mut tab.bot, game_mut);
+    }
+
+    a
+}
+
+/// Adds a move or markup to the tab's tree
+fn play(tab: &mut Tab, a: Action) {
+    if a == Action::NoOp {
+        return;
+    }
+
+    let (game_mut, tab) = (&mut tab.game, &mut tab.state);
+    let n = crate::util::new_node(&a);
+    if n {
+        game_mut.board.clear_markup();
+        tab.tool.clear();
+        tab.clear_comment();
+    }
+    let mover = crate::util::mover(&a);
+    game_mut.tree.handle_new_action(a.clone(), n);
+
+    if let Some(c) = mover {
+        game_mut.press_clock(c);
+        game_mut.check_passes();
+    }
+
+    net::record(&mut tab.net, game_mut, &a);
+}
+
+/// The game creator, and the other ways of starting a game
+fn creator(ui: &mut Ui, state: &mut State) {
+    if game_creator(&mut state.builder, ui) {
+        // so that the next new game doesn't open the same file
+        match state.builder.path.take() {
+            Some(p) => {
+                open_path(state, p);
             }
-            game_mut.tree.handle_new_action(a, n);
+            None => add_tab(state, Tab::new(state.builder.build())),
+        }
+    }
+    ui.label("or drop an sgf file here, or paste one");
+    recent_files(ui, state);
+    ui.checkbox(&mut state.ui_state.keys.open, "show keyboard shortcuts");
+
+    let creator = &mut state.ui_state.creator;
+    if let Some(g) = net::lobby(ui, &mut creator.net, &state.builder) {
+        let mut tab = Tab::new(g);
+        tab.state.net = std::mem::take(&mut creator.net);
+        add_tab(state, tab);
+        return;
+    }
+
+    let creator = &mut state.ui_state.creator;
+    if let Some(g) = problem::practice_menu(ui, &mut creator.problem) {
+        let mut tab = Tab::new(g);
+        tab.state.problem = std::mem::take(&mut creator.problem);
+        add_tab(state, tab);
+    }
+}
+
+/// Opens a file the user picks in a new tab
+fn open_file(state: &mut State) {
+    let path = match rfd::FileDialog::new()
+        .add_filter("sgf", &["sgf"])
+        .add_filter("other servers", &crate::import::Format::EXTENSIONS)
+        .pick_file()
+    {
+        Some(p) => p,
+        None => return,
+    };
+
+    open_path(state, path);
+}
+
+/// Opens the file at `path` in a new tab, returning the tab's index.
+/// Files that can't be opened are taken off the recent files.
+fn open_path(state: &mut State, path: PathBuf) -> Option<usize> {
+    match crate::game::build_game_from_path(path.clone()) {
+        Ok(g) => {
+            state.ui_state.recent.add(&path);
+            add_tab(state, Tab::new(g));
+            state.current
         }
+        Err(e) => {
+            state.ui_state.recent.files.retain(|p| *p != path);
 
-        #[cfg(debug_assertions)]
-        debug_window(ui, state);
+            #[cfg(debug_assertions)]
+            crate::log(format!("Failed to open {} with {:?}", path.display(), e));
+            None
+        }
     }
 }
 
-fn render_game(state: &mut UiState, game_mut: &mut Game, ui: &mut Ui, size: Vec2) -> Action {
+/// Lists the files opened recently, and offers to reopen the ones open last time
+fn recent_files(ui: &mut Ui, state: &mut State) {
+    if !state.ui_state.last_session.is_empty() && ui.button("reopen last session").clicked() {
+        let session = std::mem::take(&mut state.ui_state.last_session);
+        let mut current = None;
+
+        for (i, f) in session.files.into_iter().enumerate() {
+            let t = match open_path(state, f.path) {
+                Some(t) => t,
+                None => continue,
+            };
+            let tab = &mut state.tabs[t];
+            if tab.game.tree.select_path(&f.node).is_ok() {
+                tab.game.do_to_now();
+            }
+            tab.state.tool.tool = f.tool;
+
+            if session.current == Some(i) {
+                current = Some(t);
+            }
+        }
+
+        if current.is_some() {
+            state.current = current;
+        }
+    }
+
+    if state.ui_state.recent.files.is_empty() {
+        return;
+    }
+
+    let mut open = None;
+    egui::CollapsingHeader::new("recent files")
+        .default_open(true)
+        .show(ui, |ui| {
+            for p in &state.ui_state.recent.files {
+                let name = p
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                if ui
+                    .button(name)
+                    .on_hover_text(p.display().to_string())
+                    .clicked()
+                {
+                    open = Some(p.clone());
+                }
+            }
+        });
+
+    if let Some(p) = open {
+        open_path(state, p);
+    }
+}
+
+fn render_game(
+    state: &mut UiState,
+    tab: &mut Tab,
+    ui: &mut Ui,
+    size: Vec2,
+    commands: &[Command],
+) -> Action {
+    let (game_mut, tab) = (&mut tab.game, &mut tab.state);
     let min_size = size.x.min(size.y);
     let size = vec2(min_size, min_size);
 
     let board_render = board::BoardRenderer::build(ui, &game_mut.board, size, &state.style);
-    board_render.render_board(&game_mut.board, &state.style);
+    let variations = if tab.browsable(game_mut) {
+        game_mut.variations()
+    } else {
+        Vec::new()
+    };
+
+    let texture = state
+        .themes
+        .texture
+        .get(ui.ctx(), state.style.texture.as_deref());
+    board_render.render_board(&game_mut.board, texture);
+    board_render.render_variations(&game_mut.board, &variations);
+    board_render.render_overlays(game_mut, &state.overlays);
+
+    if game_mut.state == GameState::Counting {
+        let owners = crate::scoring::ownership(&game_mut.board, &game_mut.dead);
+        board_render.render_ownership(&game_mut.board, &owners);
+
+        if let Some((x, y)) = board_render.clicked_point(ui, &game_mut.board) {
+            game_mut.toggle_dead(x, y);
+            net::mark_dead(&mut tab.net, game_mut, x, y);
+        }
+    }
+
+    // don't let the user play the bot's or the network opponent's moves, or edit a finished game.
+    // Stones can't be added during a network game either, since the opponent wouldn't see them.
+    let playing = tab.tool.tool == ToolType::Play;
+    let adding = matches!(tab.tool.tool, ToolType::AddBlack | ToolType::AddWhite);
+    let variation = board_render
+        .clicked_point(ui, &game_mut.board)
+        .filter(|_| playing)
+        .and_then(|(x, y)| variations.iter().find(|v| (v.x, v.y) == (x, y)));
 
-    let mut a =
-        board_render.handle_click(ui, &mut game_mut.board, &mut state.tool, &mut game_mut.turn);
+    let mut a = if let Some(v) = variation {
+        // clicking a variation's move goes to it, even in a game that can't be edited
+        if game_mut.tree.select_node(v.node).is_ok() {
+            game_mut.do_to_now();
+            tab.tool.clear();
+            tab.clear_comment();
+        }
+        Action::NoOp
+    } else if tab.guess.is_active() {
+        // a guess isn't a move, the real move is played instead
+        if let Some((x, y)) = board_render.clicked_point(ui, &game_mut.board) {
+            guess::guess(&mut tab.guess, game_mut, x, y);
+        }
+        Action::NoOp
+    } else if tab.problem.is_active() {
+        // only moves can be played while solving a problem, and only until it is over
+        if playing && tab.problem.accepts_moves() {
+            board_render.handle_click(ui, &mut game_mut.board, &mut tab.tool, &mut game_mut.turn)
+        } else {
+            Action::NoOp
+        }
+    } else if !game_mut.is_editable()
+        || (playing && tab.bot.to_move(game_mut.turn))
+        || (playing && tab.net.waiting_for(game_mut))
+        || (adding && tab.net.in_game(game_mut))
+    {
+        Action::NoOp
+    } else {
+        board_render.handle_click(ui, &mut game_mut.board, &mut tab.tool, &mut game_mut.turn)
+    };
+
+    if tab.problem.is_active() {
+        a = problem::update(&mut tab.problem, game_mut, a);
+    }
 
     // TODO: put these in the center of the screen vertically
     ui.vertical(|ui| {
-        sidebar(ui, state, game_mut, &mut a);
-        sgf::edit_comment(ui, &mut game_mut.tree.current_node_mut().actions, state);
+        sidebar(ui, state, tab, game_mut, &mut a, commands);
+        let editable = game_mut.is_editable() && !tab.problem.is_active() && !tab.guess.is_active();
+        sgf::edit_comment(
+            ui,
+            &mut game_mut.tree.current_node_mut().actions,
+            tab,
+            editable,
+        );
     });
 
     a
 }
 
-fn sidebar(ui: &mut Ui, state: &mut UiState, game_mut: &mut Game, a: &mut Action) {
-    if ui.button("save").clicked() {
-        if let Err(e) = game_mut.write_to_file() {
-            ui.label("FAILED TO SAVE!!");
+fn sidebar(
+    ui: &mut Ui,
+    state: &mut UiState,
+    tab: &mut TabState,
+    game_mut: &mut Game,
+    a: &mut Action,
+    commands: &[Command],
+) {
+    clock::clocks(ui, game_mut);
+    let resigned = result::game_state(ui, game_mut, tab.net.local());
+    if resigned.is_some() && resigned == tab.net.local() {
+        net::resign(&mut tab.net);
+    }
+    net::session_panel(ui, &mut tab.net, game_mut);
 
-            #[cfg(debug_assertions)]
-            crate::log(format!("Failed to save with {:?}", e));
+    let save = ui.button("save").clicked() || commands.contains(&Command::Save);
+    let save_as = ui.button("save as").clicked() || commands.contains(&Command::SaveAs);
+    if save || save_as {
+        let result = if save_as {
+            game_mut.save_as()
+        } else {
+            game_mut.write_to_file()
+        };
+
+        match result {
+            Ok(true) => {
+                if let Some(p) = &game_mut.path {
+                    state.recent.add(p);
+                }
+            }
+            Ok(false) => {}
+            Err(e) => {
+                ui.label("FAILED TO SAVE!!");
+
+                #[cfg(debug_assertions)]
+                crate::log(format!("Failed to save with {:?}", e));
+            }
         }
     }
 
-    if ui.button("pass").clicked() {
+    ui.horizontal(|ui| {
+        if ui.button("copy game").clicked() || commands.contains(&Command::CopyGame) {
+            clipboard::copy_game(ui.ctx(), game_mut);
+        }
+        if ui.button("copy variation").clicked() || commands.contains(&Command::CopyVariation) {
+            clipboard::copy_variation(ui.ctx(), game_mut);
+        }
+    });
+
+    let can_pass = game_mut.is_editable()
+        && !tab.net.waiting_for(game_mut)
+        && !tab.bot.to_move(game_mut.turn)
+        && !tab.problem.is_active()
+        && !tab.guess.is_active();
+    let pass = ui
+        .add_enabled(can_pass, egui::Button::new("pass"))
+        .clicked();
+    if pass || (can_pass && commands.contains(&Command::Pass)) {
         if game_mut.turn == Stone::Black {
             *a = Action::PassBlack;
         }
@@ -108,30 +798,58 @@ fn sidebar(ui: &mut Ui, state: &mut UiState, game_mut: &mut Game, a: &mut Action
         game_mut.turn = !game_mut.turn;
     }
 
+    for c in commands {
+        if let Command::Tool(t) = c {
+            tab.tool.tool = *t;
+        }
+    }
+
     egui::ComboBox::from_label("Tool")
-        .selected_text(format!("{:?}", state.tool.tool))
+        .selected_text(format!("{:?}", tab.tool.tool))
         .show_ui(ui, |ui| {
-            ui.selectable_value(&mut state.tool.tool, ToolType::Play, "Play");
-            ui.selectable_value(&mut state.tool.tool, ToolType::AddBlack, "Add Black");
-            ui.selectable_value(&mut state.tool.tool, ToolType::AddWhite, "Add White");
-            ui.selectable_value(&mut state.tool.tool, ToolType::Circle, "Circle");
-            ui.selectable_value(&mut state.tool.tool, ToolType::Cross, "Cross");
-            ui.selectable_value(&mut state.tool.tool, ToolType::Square, "Square");
-            ui.selectable_value(&mut state.tool.tool, ToolType::Triangle, "Triangle");
-            ui.selectable_value(&mut state.tool.tool, ToolType::Dim, "Dim");
-            ui.selectable_value(&mut state.tool.tool, ToolType::Arrow, "Arrow");
-            ui.selectable_value(&mut state.tool.tool, ToolType::Line, "Line");
-            ui.selectable_value(&mut state.tool.tool, ToolType::Number, "Number");
-            ui.selectable_value(&mut state.tool.tool, ToolType::Letter, "Letter");
+            ui.selectable_value(&mut tab.tool.tool, ToolType::Play, "Play");
+            ui.selectable_value(&mut tab.tool.tool, ToolType::AddBlack, "Add Black");
+            ui.selectable_value(&mut tab.tool.tool, ToolType::AddWhite, "Add White");
+            ui.selectable_value(&mut tab.tool.tool, ToolType::Circle, "Circle");
+            ui.selectable_value(&mut tab.tool.tool, ToolType::Cross, "Cross");
+            ui.selectable_value(&mut tab.tool.tool, ToolType::Square, "Square");
+            ui.selectable_value(&mut tab.tool.tool, ToolType::Triangle, "Triangle");
+            ui.selectable_value(&mut tab.tool.tool, ToolType::Dim, "Dim");
+            ui.selectable_value(&mut tab.tool.tool, ToolType::Arrow, "Arrow");
+            ui.selectable_value(&mut tab.tool.tool, ToolType::Line, "Line");
+            ui.selectable_value(&mut tab.tool.tool, ToolType::Number, "Number");
+            ui.selectable_value(&mut tab.tool.tool, ToolType::Letter, "Letter");
         });
 
+    board::overlay_settings(ui, &mut state.overlays);
+    theme::theme_editor(ui, &mut state.style, &mut state.themes);
+    export::export_panel(
+        ui,
+        &mut state.export,
+        &mut tab.marked,
+        game_mut,
+        &state.style,
+        &state.overlays,
+    );
+    bot::bot_settings(ui, &mut tab.bot);
+    problem::problem_panel(ui, &mut tab.problem, game_mut);
+    guess::guess_panel(ui, &mut tab.guess, game_mut);
+
+    ui.checkbox(&mut state.keys.open, "show keyboard shortcuts");
     if cfg!(debug_assertions) {
         ui.checkbox(&mut state.debug_window, "show debug window");
     }
 
-    if sgf::sgf_arrows(ui, game_mut) {
-        state.tool.clear();
-        state.clear_comment();
+    if tab.browsable(game_mut) {
+        ui.checkbox(&mut tab.tree.open, "show game tree");
+
+        let arrows = sgf::sgf_arrows(ui, game_mut);
+        let jumped = tree::tree_window(ui, &mut tab.tree, game_mut);
+        let navigated = sgf::navigate(game_mut, commands);
+        if arrows || jumped || navigated {
+            tab.tool.clear();
+            tab.clear_comment();
+        }
     }
 }
 
@@ -141,6 +859,7 @@ fn game_creator(builder: &mut crate::GameBuilder, ui: &mut Ui) -> bool {
     if ui.button("open file:").clicked() {
         builder.path = rfd::FileDialog::new()
             .add_filter("sgf", &["sgf"])
+            .add_filter("other servers", &crate::import::Format::EXTENSIONS)
             .pick_file();
 
         if builder.path.is_some() {
@@ -155,6 +874,8 @@ fn game_creator(builder: &mut crate::GameBuilder, ui: &mut Ui) -> bool {
     ui.label("board height");
     ui.add(egui::Slider::new(&mut builder.size.1, 5..=52));
 
+    clock::time_control_editor(ui, &mut builder.time_control);
+
     if ui.button("finish").clicked() {
         return true;
     }
@@ -162,10 +883,8 @@ fn game_creator(builder: &mut crate::GameBuilder, ui: &mut Ui) -> bool {
     false
 }
 
-fn debug_window(ui: &mut Ui, state: &State) {
-    let game = state.game.as_ref().unwrap();
-
-    if state.ui_state.debug_window {
+fn debug_window(ui: &mut Ui, state: &UiState, game: &Game) {
+    if state.debug_window {
         egui::Window::new("debug").show(ui.ctx(), |ui| {
             egui::ScrollArea::both().show(ui, |ui| {
                 egui::CollapsingHeader::new("Game Tree").show(ui, |ui| {
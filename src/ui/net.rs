@@ -0,0 +1,613 @@
+use std::sync::mpsc::TryRecvError;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::game::GameState;
+use crate::net::{Connection, Event, Handshake, Message, Pending, Setup};
+use crate::sgf::Action;
+use crate::{Game, GameBuilder, Stone};
+use eframe::egui;
+use egui::Ui;
+
+enum Stage {
+    Idle,
+    /// Waiting for a guest, or connecting to a host.
+    /// `setup` is only `Some` when hosting.
+    Waiting {
+        pending: Pending,
+        setup: Option<Setup>,
+        text: String,
+    },
+    Handshaking(Handshake),
+    Playing(Session),
+}
+
+struct Session {
+    conn: Connection,
+    /// The colour played on this side
+    local: Stone,
+    /// The opponent asked to take back the last move
+    undo_requested: bool,
+    /// This side asked to take back the last move and is waiting for an answer
+    awaiting_undo: bool,
+    /// This side accepted the count
+    accepted: bool,
+    /// The result the opponent accepted while counting
+    opponent_accepted: Option<String>,
+    status: Option<String>,
+    /// The state when the game was last saved
+    saved: GameState,
+}
+impl Session {
+    /// Checks a message from the opponent, returning their move if it is legal
+    fn receive(&mut self, m: Message, game: &mut Game) -> Action {
+        let remote = !self.local;
+        let their_turn = game.state == GameState::InProgress && game.turn == remote;
+
+        match m {
+            Message::Move(x, y) => {
+                let (w, h) = game.board.size();
+                if !their_turn || x >= w || y >= h || !game.board.attempt_set(x, y, remote) {
+                    self.reject(format!("illegal move {} {}", x, y));
+                    return Action::NoOp;
+                }
+
+                game.turn = self.local;
+                self.answered_by_move();
+                return match remote {
+                    Stone::Black => Action::PlayBlack(x, y),
+                    _ => Action::PlayWhite(x, y),
+                };
+            }
+            Message::Pass => {
+                if !their_turn {
+                    self.reject(String::from("it isn't your turn"));
+                    return Action::NoOp;
+                }
+
+                game.turn = self.local;
+                self.answered_by_move();
+                return match remote {
+                    Stone::Black => Action::PassBlack,
+                    _ => Action::PassWhite,
+                };
+            }
+            Message::Resign => {
+                if game.state != GameState::Finished {
+                    game.resign(remote);
+                }
+            }
+
+            // only the move just played can be taken back, so the opponent's reply isn't
+            Message::UndoRequest if game.state == GameState::InProgress && !their_turn => {
+                self.undo_requested = true
+            }
+            Message::UndoRequest => self.reject(String::from("the undo came after a move")),
+            Message::UndoAccept if self.awaiting_undo => {
+                self.awaiting_undo = false;
+                game.undo();
+                save(game);
+            }
+            Message::UndoDecline if self.awaiting_undo => {
+                self.awaiting_undo = false;
+                self.status = Some(String::from("the opponent declined the undo"));
+            }
+
+            Message::Dead(x, y) if game.state == GameState::Counting => {
+                let (w, h) = game.board.size();
+                if x >= w || y >= h {
+                    self.reject(format!("{} {} isn't on the board", x, y));
+                    return Action::NoOp;
+                }
+
+                game.toggle_dead(x, y);
+                self.reset_count();
+            }
+            Message::Accept(result) if game.state == GameState::Counting => {
+                self.opponent_accepted = Some(result);
+                self.finish_count(game);
+            }
+            Message::Resume if game.state == GameState::Counting => {
+                game.resume();
+                self.reset_count();
+            }
+
+            Message::Error(e) => self.status = Some(format!("the opponent says: {}", e)),
+            m => self.reject(format!("unexpected message `{}`", m)),
+        }
+
+        Action::NoOp
+    }
+
+    /// A move from the opponent instead of an answer to this side's undo request
+    fn answered_by_move(&mut self) {
+        if self.awaiting_undo {
+            self.awaiting_undo = false;
+            self.status = Some(String::from(
+                "the opponent played instead of answering the undo",
+            ));
+        }
+    }
+
+    /// Any change to the dead stones needs both sides to accept again
+    fn reset_count(&mut self) {
+        self.accepted = false;
+        self.opponent_accepted = None;
+    }
+
+    /// Ends the game once both sides have accepted the same result,
+    /// or this side has and the opponent has left
+    fn finish_count(&mut self, game: &mut Game) {
+        if !self.accepted {
+            return;
+        }
+
+        let result = game.count_result().to_string();
+        match &self.opponent_accepted {
+            Some(r) if *r == result => game.accept_count(),
+            _ if self.conn.is_closed() => game.accept_count(),
+            // accepted before one of this side's changes reached them
+            Some(r) => {
+                self.status = Some(format!(
+                    "the opponent accepted {}, but the count here is {}",
+                    r, result
+                ))
+            }
+            None => {}
+        }
+    }
+
+    fn reject(&mut self, e: String) {
+        #[cfg(debug_assertions)]
+        crate::log(format!("[WARNING] rejected a network message: {}", e));
+
+        self.send(&Message::Error(e));
+    }
+
+    fn send(&mut self, m: &Message) {
+        if let Err(e) = self.conn.send(m) {
+            self.status = Some(format!("failed to send: {}", e));
+        }
+    }
+}
+
+pub struct NetState {
+    port: u16,
+    /// The host to connect to
+    address: String,
+    host_color: Stone,
+    komi: f32,
+    stage: Stage,
+    error: Option<String>,
+}
+impl Default for NetState {
+    fn default() -> Self {
+        Self {
+            port: 4650,
+            address: String::from("127.0.0.1:4650"),
+            host_color: Stone::Black,
+            komi: 6.5,
+            stage: Stage::Idle,
+            error: None,
+        }
+    }
+}
+impl NetState {
+    fn session(&self) -> Option<&Session> {
+        match &self.stage {
+            Stage::Playing(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The colour played on this side, in a network game
+    pub fn local(&self) -> Option<Stone> {
+        self.session().map(|s| s.local)
+    }
+
+    /// If a network game is being played, so the board must stay in sync with the opponent's
+    pub fn in_game(&self, game: &Game) -> bool {
+        match self.session() {
+            Some(s) => !s.conn.is_closed() && game.state == GameState::InProgress,
+            None => false,
+        }
+    }
+
+    /// If it is the opponent's turn in a network game
+    pub fn waiting_for(&self, game: &Game) -> bool {
+        self.in_game(game) && self.local() != Some(game.turn)
+    }
+}
+
+/// Hosting or joining a game from the game creator.
+/// Returns the game once both sides have agreed on it.
+pub fn lobby(ui: &mut Ui, net: &mut NetState, builder: &GameBuilder) -> Option<Game> {
+    let mut game = None;
+
+    egui::CollapsingHeader::new("Network game").show(ui, |ui| {
+        match &mut net.stage {
+            Stage::Idle => {
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut net.port).prefix("port: "));
+                    ui.add(
+                        egui::DragValue::new(&mut net.komi)
+                            .speed(0.5)
+                            .prefix("komi: "),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("host plays");
+                    ui.radio_value(&mut net.host_color, Stone::Black, "Black");
+                    ui.radio_value(&mut net.host_color, Stone::White, "White");
+                });
+
+                if ui.button("host").clicked() {
+                    let setup = Setup {
+                        size: builder.size,
+                        komi: net.komi,
+                        host_color: net.host_color,
+                    };
+
+                    match crate::net::listen(&format!("0.0.0.0:{}", net.port)) {
+                        Ok((addr, pending)) => {
+                            net.error = None;
+                            net.stage = Stage::Waiting {
+                                pending,
+                                setup: Some(setup),
+                                text: format!("waiting for a guest on port {}", addr.port()),
+                            };
+                        }
+                        Err(e) => net.error = Some(e.to_string()),
+                    }
+                }
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut net.address);
+                    if ui.button("connect").clicked() {
+                        net.error = None;
+                        net.stage = Stage::Waiting {
+                            pending: crate::net::connect(&net.address),
+                            setup: None,
+                            text: format!("connecting to {}", net.address),
+                        };
+                    }
+                });
+
+                if let Some(e) = &net.error {
+                    ui.label(format!("error: {}", e));
+                }
+
+                return;
+            }
+
+            Stage::Waiting {
+                pending,
+                setup,
+                text,
+            } => {
+                ui.label(text.as_str());
+
+                let handshake = match pending.try_recv() {
+                    Err(TryRecvError::Empty) => None,
+                    Err(TryRecvError::Disconnected) => {
+                        Some(Err(String::from("the connection failed")))
+                    }
+                    Ok(Err(e)) => Some(Err(e.to_string())),
+                    Ok(Ok(conn)) => Some(match setup {
+                        Some(setup) => Handshake::host(conn, *setup).map_err(|e| e.to_string()),
+                        None => Ok(Handshake::guest(conn)),
+                    }),
+                };
+
+                match handshake {
+                    Some(Ok(h)) => net.stage = Stage::Handshaking(h),
+                    Some(Err(e)) => {
+                        net.error = Some(e);
+                        net.stage = Stage::Idle;
+                    }
+                    None => {}
+                }
+            }
+
+            Stage::Handshaking(h) => {
+                ui.label("starting the game...");
+
+                match h.poll() {
+                    Ok(None) => {}
+                    Ok(Some(setup)) => {
+                        let host = h.is_host();
+                        let local = if host {
+                            setup.host_color
+                        } else {
+                            !setup.host_color
+                        };
+                        game = Some(start_game(&setup, host));
+
+                        if let Stage::Handshaking(h) =
+                            std::mem::replace(&mut net.stage, Stage::Idle)
+                        {
+                            net.stage = Stage::Playing(Session {
+                                conn: h.into_connection(),
+                                local,
+                                undo_requested: false,
+                                awaiting_undo: false,
+                                accepted: false,
+                                opponent_accepted: None,
+                                status: None,
+                                saved: GameState::InProgress,
+                            });
+                        }
+                    }
+                    Err(e) => {
+                        net.error = Some(e);
+                        net.stage = Stage::Idle;
+                    }
+                }
+            }
+
+            // the game has already started
+            Stage::Playing(_) => return,
+        }
+
+        if ui.button("cancel").clicked() {
+            net.stage = Stage::Idle;
+        }
+        ui.ctx().request_repaint_after(Duration::from_millis(50));
+    });
+
+    game
+}
+
+fn start_game(setup: &Setup, host: bool) -> Game {
+    let mut game = GameBuilder {
+        size: setup.size,
+        ..Default::default()
+    }
+    .build();
+
+    let root = game.tree.root_mut();
+    root.set_prop("KM", setup.komi.to_string());
+    let (black, white) = match setup.host_color {
+        Stone::Black => ("Host", "Guest"),
+        _ => ("Guest", "Host"),
+    };
+    root.set_prop("PB", black);
+    root.set_prop("PW", white);
+
+    // both sides keep their own copy of the game, in the data directory
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let role = if host { "host" } else { "guest" };
+    game.path = crate::util::data_dir()
+        .map(|d| d.join("network").join(format!("net-{}-{}.sgf", role, secs)));
+    if let Some(dir) = game.path.as_ref().and_then(|p| p.parent()) {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            #[cfg(debug_assertions)]
+            crate::log(format!("Failed to create {} with {:?}", dir.display(), e));
+        }
+    }
+    save(&mut game);
+
+    game
+}
+
+fn save(game: &mut Game) {
+    // without a data directory, the game is only saved when the player asks
+    if game.path.is_none() {
+        return;
+    }
+
+    if let Err(e) = game.write_to_file() {
+        #[cfg(debug_assertions)]
+        crate::log(format!("Failed to save the network game with {:?}", e));
+    }
+}
+
+/// Reads the opponent's messages, returning their move once it arrives
+pub fn update(ui: &mut Ui, net: &mut NetState, game: &mut Game) -> Action {
+    let s = match &mut net.stage {
+        Stage::Playing(s) => s,
+        _ => return Action::NoOp,
+    };
+
+    // the local player resigned, ran out of time or accepted the count
+    if s.saved != game.state {
+        s.saved = game.state;
+        save(game);
+    }
+
+    if s.conn.is_closed() {
+        return Action::NoOp;
+    }
+    ui.ctx().request_repaint_after(Duration::from_millis(50));
+
+    while let Some(e) = s.conn.poll() {
+        match e {
+            Event::Message(m) => {
+                let a = s.receive(m, game);
+                if a != Action::NoOp {
+                    return a;
+                }
+            }
+            Event::Invalid(line) => s.reject(format!("unknown message `{}`", line)),
+            Event::Closed => s.status = Some(String::from("the opponent disconnected")),
+        }
+    }
+
+    Action::NoOp
+}
+
+/// Sends the local player's moves to the opponent, and saves the game after any move
+pub fn record(net: &mut NetState, game: &mut Game, a: &Action) {
+    // passing may have just started counting, which still has to be sent
+    let finished = game.state == GameState::Finished;
+    let s = match &mut net.stage {
+        Stage::Playing(s) => s,
+        _ => return,
+    };
+
+    let mover = crate::util::mover(a);
+    if mover.is_none() {
+        // markup and comments are saved with the next move
+        return;
+    }
+
+    if !finished && !s.conn.is_closed() && mover == Some(s.local) {
+        match a {
+            Action::PlayBlack(x, y) | Action::PlayWhite(x, y) => s.send(&Message::Move(*x, *y)),
+            _ => s.send(&Message::Pass),
+        }
+        // a move answers any undo request
+        s.undo_requested = false;
+    }
+
+    save(game);
+}
+
+/// Tells the opponent about a group marked or unmarked as dead on this side
+pub fn mark_dead(net: &mut NetState, game: &Game, x: usize, y: usize) {
+    let s = match &mut net.stage {
+        Stage::Playing(s) => s,
+        _ => return,
+    };
+
+    if game.state == GameState::Counting && game.board.get(x, y) != Stone::Empty {
+        s.send(&Message::Dead(x, y));
+        s.reset_count();
+    }
+}
+
+/// Tells the opponent that the local player resigned
+pub fn resign(net: &mut NetState) {
+    if let Stage::Playing(s) = &mut net.stage {
+        s.send(&Message::Resign);
+    }
+}
+
+/// Shows who is playing which colour, and handles undo requests and agreeing on the count
+pub fn session_panel(ui: &mut Ui, net: &mut NetState, game: &mut Game) {
+    let in_game = net.in_game(game);
+    // only this side's last move can be taken back, before the opponent answers it
+    let can_undo = net.waiting_for(game) && game.tree.current_node().parent().is_some();
+    let s = match &mut net.stage {
+        Stage::Playing(s) => s,
+        _ => return,
+    };
+
+    ui.label(format!(
+        "Network game: you play {}",
+        if s.local == Stone::White {
+            "White"
+        } else {
+            "Black"
+        }
+    ));
+    if let Some(status) = &s.status {
+        ui.label(status.as_str());
+    }
+
+    if game.state == GameState::Counting {
+        if let Some(r) = &s.opponent_accepted {
+            ui.label(format!("The opponent accepts {}", r));
+        }
+        ui.horizontal(|ui| {
+            if s.accepted {
+                ui.label("waiting for the opponent to accept...");
+            } else if ui.button("accept").clicked() {
+                s.accepted = true;
+                s.send(&Message::Accept(game.count_result().to_string()));
+                s.finish_count(game);
+            }
+            if ui.button("resume play").clicked() {
+                s.send(&Message::Resume);
+                s.reset_count();
+                game.resume();
+            }
+        });
+        return;
+    }
+
+    if !in_game {
+        return;
+    }
+
+    if s.undo_requested {
+        ui.horizontal(|ui| {
+            ui.label("The opponent asks to undo");
+            if ui.button("accept").clicked() {
+                s.undo_requested = false;
+                s.send(&Message::UndoAccept);
+                game.undo();
+                save(game);
+            }
+            if ui.button("decline").clicked() {
+                s.undo_requested = false;
+                s.send(&Message::UndoDecline);
+            }
+        });
+    } else if s.awaiting_undo {
+        ui.label("waiting for the opponent to answer...");
+    } else if ui
+        .add_enabled(can_undo, egui::Button::new("request undo"))
+        .clicked()
+    {
+        s.awaiting_undo = true;
+        s.send(&Message::UndoRequest);
+    }
+}
+
+#[cfg(test)]
+mod net_ui_tests {
+    use super::*;
+    use crate::net::{connect, listen};
+    use std::time::Instant;
+
+    fn wait<T>(mut f: impl FnMut() -> Option<T>) -> T {
+        let start = Instant::now();
+        loop {
+            if let Some(t) = f() {
+                return t;
+            }
+            assert!(start.elapsed() < Duration::from_secs(10), "timed out");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn dead_off_the_board() {
+        let (addr, pending) = listen("127.0.0.1:0").unwrap();
+        let guest = connect(&addr.to_string());
+        let conn = wait(|| pending.try_recv().ok()).unwrap();
+        let mut opponent = wait(|| guest.try_recv().ok()).unwrap();
+
+        let mut s = Session {
+            conn,
+            local: Stone::Black,
+            undo_requested: false,
+            awaiting_undo: false,
+            accepted: false,
+            opponent_accepted: None,
+            status: None,
+            saved: GameState::Counting,
+        };
+        let mut game = GameBuilder {
+            size: (9, 9),
+            ..Default::default()
+        }
+        .build();
+        game.board.set(2, 2, Stone::White);
+        game.state = GameState::Counting;
+
+        assert_eq!(s.receive(Message::Dead(99, 99), &mut game), Action::NoOp);
+        assert!(game.dead.is_empty());
+        match wait(|| opponent.poll()) {
+            Event::Message(Message::Error(e)) => assert!(e.contains("99 99")),
+            _ => panic!("expected an error"),
+        }
+
+        s.receive(Message::Dead(2, 2), &mut game);
+        assert!(game.dead.contains(&(2, 2)));
+    }
+}
@@ -0,0 +1,304 @@
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use crate::clock::format_duration;
+use crate::problem::{today, Attempt, Collection, History, Problem, Stats, Status};
+use crate::sgf::Action;
+use crate::{Game, Stone};
+use eframe::egui;
+use egui::Ui;
+
+/// Going through the problems scheduled for today
+struct Practice {
+    /// Indices on the collection's entries
+    queue: Vec<usize>,
+    /// Index on `queue`
+    current: usize,
+    started: Instant,
+    recorded: bool,
+}
+
+pub struct ProblemState {
+    /// Text in a comment that marks a correct answer
+    pub marker: String,
+    problem: Option<Problem>,
+
+    collection: Option<Collection>,
+    practice: Option<Practice>,
+    new_per_day: usize,
+    /// Loaded when it is first needed
+    history: Option<History>,
+    error: Option<String>,
+}
+impl Default for ProblemState {
+    fn default() -> Self {
+        Self {
+            marker: String::from("RIGHT"),
+            problem: None,
+            collection: None,
+            practice: None,
+            new_per_day: 10,
+            history: None,
+            error: None,
+        }
+    }
+}
+impl ProblemState {
+    /// If a problem is being solved, so the tree should stay hidden
+    pub fn is_active(&self) -> bool {
+        self.problem.is_some()
+    }
+
+    /// If the user can play the next move
+    pub fn accepts_moves(&self) -> bool {
+        matches!(&self.problem, Some(p) if p.status == Status::Solving)
+    }
+
+    fn history(&mut self) -> &mut History {
+        if self.history.is_none() {
+            let h = History::default_path()
+                .map(|p| History::load(&p))
+                .unwrap_or_else(|| Ok(History::default()));
+
+            self.history = Some(h.unwrap_or_else(|e| {
+                self.error = Some(format!("failed to read progress: {}", e));
+                History::default()
+            }));
+        }
+
+        self.history.get_or_insert_with(History::default)
+    }
+
+    /// Loads the problem at `queue[current]` into a new game
+    fn load_current(&mut self) -> Option<Game> {
+        let practice = self.practice.as_mut()?;
+        let collection = self.collection.as_ref()?;
+        let entry = &collection.entries[*practice.queue.get(practice.current)?];
+
+        let mut game = Game::from_tree(entry.tree.clone(), None);
+        self.problem = Some(Problem::start(&mut game));
+        practice.started = Instant::now();
+        practice.recorded = false;
+
+        Some(game)
+    }
+
+    /// Saves the first result of each try at a practice problem
+    fn record(&mut self) {
+        let status = match &self.problem {
+            Some(p) if p.status != Status::Solving => p.status,
+            _ => return,
+        };
+        let (id, taken) = match (&mut self.practice, &self.collection) {
+            (Some(pr), Some(c)) if !pr.recorded => {
+                pr.recorded = true;
+                (
+                    c.entries[pr.queue[pr.current]].id.clone(),
+                    pr.started.elapsed(),
+                )
+            }
+            _ => return,
+        };
+
+        let path = match History::default_path() {
+            Some(p) => p,
+            None => {
+                self.error = Some(String::from("there is nowhere to save progress"));
+                return;
+            }
+        };
+
+        let a = Attempt::new(&id, taken, status == Status::Solved);
+        if let Err(e) = self.history().record(&path, a) {
+            self.error = Some(format!("failed to save progress: {}", e));
+        }
+    }
+
+    fn stop(&mut self) {
+        self.problem = None;
+        self.practice = None;
+    }
+}
+
+/// Passes the user's moves to the problem, which answers them itself.
+/// Nothing is added to the tree while solving.
+pub fn update(ps: &mut ProblemState, game: &mut Game, a: Action) -> Action {
+    let p = match &mut ps.problem {
+        Some(p) => p,
+        None => return a,
+    };
+
+    if let Action::PlayBlack(_, _) | Action::PlayWhite(_, _) = a {
+        p.play(game, &a, &ps.marker);
+        ps.record();
+    }
+
+    Action::NoOp
+}
+
+pub fn problem_panel(ui: &mut Ui, ps: &mut ProblemState, game: &mut Game) {
+    egui::CollapsingHeader::new("Problem").show(ui, |ui| {
+        let p = match &mut ps.problem {
+            Some(p) => p,
+            None => {
+                ui.horizontal(|ui| {
+                    ui.label("correct marker");
+                    ui.text_edit_singleline(&mut ps.marker);
+                });
+
+                if ui.button("solve as a problem").clicked() {
+                    ps.problem = Some(Problem::start(game));
+                }
+
+                return;
+            }
+        };
+
+        ui.label(match p.player {
+            Stone::White => "White to play",
+            _ => "Black to play",
+        });
+        ui.label(match p.status {
+            Status::Solving => "",
+            Status::Solved => "Correct!",
+            Status::Failed => "Wrong",
+        });
+
+        let mut stop = false;
+        let mut next = false;
+        ui.horizontal(|ui| {
+            if ui.button("retry").clicked() {
+                p.retry(game);
+                if let Some(pr) = &mut ps.practice {
+                    pr.started = Instant::now();
+                    pr.recorded = false;
+                }
+            }
+            stop = ui.button("stop solving").clicked();
+        });
+
+        if let Some(pr) = &ps.practice {
+            ui.label(format!("problem {} of {}", pr.current + 1, pr.queue.len()));
+            next = ui.button("next problem").clicked();
+        }
+        if let Some(e) = &ps.error {
+            ui.label(e.as_str());
+        }
+
+        if next {
+            if let Some(pr) = &mut ps.practice {
+                pr.current += 1;
+            }
+
+            match ps.load_current() {
+                Some(g) => *game = g,
+                // that was the last one for today
+                None => stop = true,
+            }
+        }
+
+        if stop {
+            ps.stop();
+            game.tree.select_root();
+            game.do_to_now();
+        }
+    });
+}
+
+/// Opening a collection of problems from the game creator, and showing progress on it.
+/// Returns the first problem when practice starts.
+pub fn practice_menu(ui: &mut Ui, ps: &mut ProblemState) -> Option<Game> {
+    let mut game = None;
+    let mut started = false;
+
+    egui::CollapsingHeader::new("Practice problems").show(ui, |ui| {
+        ui.horizontal(|ui| {
+            let mut opened = None;
+
+            if ui.button("open collection").clicked() {
+                if let Some(p) = rfd::FileDialog::new()
+                    .add_filter("sgf", &["sgf"])
+                    .pick_file()
+                {
+                    opened = Some(Collection::from_file(&p));
+                }
+            }
+            if ui.button("open directory").clicked() {
+                if let Some(p) = rfd::FileDialog::new().pick_folder() {
+                    opened = Some(Collection::from_dir(&p));
+                }
+            }
+
+            match opened {
+                Some(Ok(c)) => {
+                    ps.collection = Some(c);
+                    ps.error = None;
+                }
+                Some(Err(e)) => ps.error = Some(format!("failed to open: {:?}", e)),
+                None => {}
+            }
+        });
+
+        if let Some(e) = &ps.error {
+            ui.label(e.as_str());
+        }
+
+        ui.add(
+            egui::DragValue::new(&mut ps.new_per_day)
+                .clamp_range(0..=500)
+                .prefix("new problems per day: "),
+        );
+
+        let new_per_day = ps.new_per_day;
+        let history = std::mem::take(ps.history());
+
+        if let Some(c) = &ps.collection {
+            ui.label(format!("{}: {} problems", c.name, c.entries.len()));
+
+            let queue = history.schedule(c, today(), new_per_day);
+            let start = egui::Button::new(format!("start ({} for today)", queue.len()));
+            if ui.add_enabled(!queue.is_empty(), start).clicked() {
+                started = true;
+                ps.practice = Some(Practice {
+                    queue,
+                    current: 0,
+                    started: Instant::now(),
+                    recorded: false,
+                });
+            }
+
+            ui.label("by difficulty:");
+            stats_grid(ui, "tag stats", &history.tag_stats(c));
+        }
+
+        ui.label("by collection:");
+        stats_grid(ui, "collection stats", &history.collection_stats());
+
+        ps.history = Some(history);
+        if started {
+            game = ps.load_current();
+        }
+    });
+
+    game
+}
+
+fn stats_grid(ui: &mut Ui, id: &str, stats: &BTreeMap<String, Stats>) {
+    egui::Grid::new(id).striped(true).show(ui, |ui| {
+        ui.label("");
+        ui.label("seen");
+        ui.label("attempts");
+        ui.label("solved");
+        ui.label("average time");
+        ui.end_row();
+
+        for (name, s) in stats {
+            ui.label(name);
+            ui.label(s.seen.to_string());
+            ui.label(s.attempts.to_string());
+            ui.label(format!("{:.0}%", 100.0 * s.success_rate()));
+            ui.label(format_duration(s.average_time()));
+            ui.end_row();
+        }
+    });
+}
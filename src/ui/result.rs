@@ -0,0 +1,68 @@
+use crate::game::GameState;
+use crate::{Game, Stone};
+use eframe::egui;
+use egui::Ui;
+
+/// Resign buttons while playing, the count while counting,
+/// and the result once the game has finished.
+/// In a network game only the `local` player can resign, and the count is
+/// accepted in the network panel, since both sides have to agree on it.
+/// Returns the player who resigned.
+pub fn game_state(ui: &mut Ui, game: &mut Game, local: Option<Stone>) -> Option<Stone> {
+    let mut resigned = None;
+
+    match game.state {
+        GameState::InProgress => {
+            ui.horizontal(|ui| match local {
+                Some(c) => {
+                    if ui.button("resign").clicked() {
+                        resigned = Some(c);
+                    }
+                }
+                None => {
+                    if ui.button("Black resigns").clicked() {
+                        resigned = Some(Stone::Black);
+                    }
+                    if ui.button("White resigns").clicked() {
+                        resigned = Some(Stone::White);
+                    }
+                }
+            });
+        }
+
+        GameState::Counting => {
+            ui.label("Counting: click on groups to mark them as dead");
+
+            let score = game.count();
+            ui.label(format!(
+                "Black: {}  White: {} + {} komi",
+                score.black,
+                score.white,
+                game.komi()
+            ));
+
+            if local.is_none() {
+                ui.horizontal(|ui| {
+                    if ui.button("accept").clicked() {
+                        game.accept_count();
+                    }
+                    if ui.button("resume play").clicked() {
+                        game.resume();
+                    }
+                });
+            }
+        }
+
+        GameState::Finished => {
+            let result = game.tree.root().get_prop("RE").unwrap_or("?");
+            ui.label(format!("Result: {}", result));
+            ui.checkbox(&mut game.analysis, "analysis mode");
+        }
+    }
+
+    if let Some(c) = resigned {
+        game.resign(c);
+    }
+
+    resigned
+}
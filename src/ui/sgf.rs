@@ -1,4 +1,5 @@
-use super::UiState;
+use super::keys::Command;
+use super::TabState;
 use crate::sgf::Action;
 use crate::Game;
 use eframe::egui;
@@ -30,13 +31,69 @@ pub fn sgf_arrows(ui: &mut Ui, game: &mut Game) -> bool {
     pressed
 }
 
-pub fn edit_comment(ui: &mut Ui, actions: &mut Vec<Action>, state: &mut UiState) {
+/// Moves around the tree with the navigation shortcuts.
+/// returns if any of them were pressed
+pub fn navigate(game: &mut Game, commands: &[Command]) -> bool {
+    let mut moved = false;
+
+    for c in commands {
+        moved |= match c {
+            Command::Back => back(game, 1),
+            Command::BackMany => back(game, 10),
+            Command::Forward => forward(game, 1),
+            Command::ForwardMany => forward(game, 10),
+            Command::Start => back(game, usize::MAX),
+            Command::End => forward(game, usize::MAX),
+            Command::PreviousVariation => game.tree.select_sibling(-1).is_ok(),
+            Command::NextVariation => game.tree.select_sibling(1).is_ok(),
+            _ => false,
+        };
+    }
+
+    if moved {
+        game.do_to_now();
+    }
+
+    moved
+}
+
+/// Goes up to `n` moves back, returning if it moved at all
+fn back(game: &mut Game, n: usize) -> bool {
+    let mut moved = 0;
+    while moved < n && game.tree.select_parent().is_ok() {
+        moved += 1;
+    }
+
+    moved > 0
+}
+
+/// Goes up to `n` moves forward along the main line, returning if it moved at all
+fn forward(game: &mut Game, n: usize) -> bool {
+    let mut moved = 0;
+    while moved < n && game.tree.select_child(0).is_ok() {
+        moved += 1;
+    }
+
+    moved > 0
+}
+
+/// Shows the comment of the current node, which can only be changed if `editable`
+pub fn edit_comment(ui: &mut Ui, actions: &mut Vec<Action>, state: &mut TabState, editable: bool) {
+    if !editable {
+        for a in actions.iter() {
+            if let Action::Comment(s) = a {
+                ui.label(s);
+            }
+        }
+        return;
+    }
+
     if let Some(i) = state.comment {
         if let Action::Comment(ref mut s) = &mut actions[i] {
             ui.text_edit_multiline(s);
         } else {
             #[cfg(debug_assertions)]
-            crate::log(format!("UiState::comment is not valid!"));
+            crate::log("TabState::comment is not valid!");
 
             state.comment = None;
         }
@@ -3,12 +3,12 @@ use egui::{pos2, Color32, Painter, Pos2, Vec2};
 
 use std::f32::consts::FRAC_PI_4;
 
-pub fn circle(p: &Painter, center: Pos2, stone_radius: f32) {
+pub fn circle(p: &Painter, center: Pos2, stone_radius: f32, color: Color32) {
     let r = 0.75 * stone_radius;
-    p.circle_stroke(center, r, (2.0, Color32::RED));
+    p.circle_stroke(center, r, (2.0, color));
 }
 
-pub fn cross(p: &Painter, center: Pos2, stone_radius: f32) {
+pub fn cross(p: &Painter, center: Pos2, stone_radius: f32, color: Color32) {
     let r = 0.75 * stone_radius;
     let points1 = [
         pos2(center.x - r, center.y - r),
@@ -18,30 +18,30 @@ pub fn cross(p: &Painter, center: Pos2, stone_radius: f32) {
         pos2(center.x + r, center.y - r),
         pos2(center.x - r, center.y + r),
     ];
-    p.line_segment(points1, (2.0, Color32::RED));
-    p.line_segment(points2, (2.0, Color32::RED));
+    p.line_segment(points1, (2.0, color));
+    p.line_segment(points2, (2.0, color));
 }
 
-pub fn square(p: &Painter, center: Pos2, stone_radius: f32) {
+pub fn square(p: &Painter, center: Pos2, stone_radius: f32, color: Color32) {
     let r = 0.75 * stone_radius;
     let rect = egui::Rect {
         min: pos2(center.x - r, center.y - r),
         max: pos2(center.x + r, center.y + r),
     };
 
-    p.rect_stroke(rect, 0.0, (2.0, Color32::RED));
+    p.rect_stroke(rect, 0.0, (2.0, color));
 }
 
-pub fn triangle(p: &Painter, center: Pos2, stone_radius: f32) {
+pub fn triangle(p: &Painter, center: Pos2, stone_radius: f32, color: Color32) {
     let r = 0.75 * stone_radius;
 
     let top = pos2(center.x, center.y - r);
     let left = pos2(center.x - r, center.y + r);
     let right = pos2(center.x + r, center.y + r);
 
-    p.line_segment([top, left], (2.0, Color32::RED));
-    p.line_segment([top, right], (2.0, Color32::RED));
-    p.line_segment([left, right], (2.0, Color32::RED));
+    p.line_segment([top, left], (2.0, color));
+    p.line_segment([top, right], (2.0, color));
+    p.line_segment([left, right], (2.0, color));
 }
 
 pub fn dim(p: &Painter, center: Pos2, spacing: Vec2) {
@@ -55,12 +55,12 @@ pub fn dim(p: &Painter, center: Pos2, spacing: Vec2) {
     p.rect_filled(rect, 0.0, color);
 }
 
-pub fn line(p: &Painter, start: Pos2, end: Pos2) {
-    p.line_segment([start, end], (4.0, Color32::RED));
+pub fn line(p: &Painter, start: Pos2, end: Pos2, color: Color32) {
+    p.line_segment([start, end], (4.0, color));
 }
 
-pub fn arrow(p: &Painter, start: Pos2, end: Pos2) {
-    p.line_segment([start, end], (4.0, Color32::RED));
+pub fn arrow(p: &Painter, start: Pos2, end: Pos2, color: Color32) {
+    p.line_segment([start, end], (4.0, color));
     let angle = egui::vec2(-end.x + start.x, -end.y + start.y).angle();
 
     let points = vec![
@@ -69,15 +69,22 @@ pub fn arrow(p: &Painter, start: Pos2, end: Pos2) {
         end + 15.0 * Vec2::angled(angle - FRAC_PI_4),
     ];
 
-    let triangle = egui::Shape::convex_polygon(points, Color32::RED, (2.0, Color32::RED));
+    let triangle = egui::Shape::convex_polygon(points, color, (2.0, color));
 
     p.add(triangle);
 }
 
-pub fn label(p: &Painter, s: &str, center: Pos2, stone_radius: f32) {
+pub fn label(
+    p: &Painter,
+    s: &str,
+    center: Pos2,
+    stone_radius: f32,
+    family: egui::FontFamily,
+    color: Color32,
+) {
     let font = egui::FontId {
         size: 2.0 * stone_radius,
-        family: egui::FontFamily::Monospace,
+        family,
     };
-    p.text(center, egui::Align2::CENTER_CENTER, s, font, Color32::RED);
+    p.text(center, egui::Align2::CENTER_CENTER, s, font, color);
 }
@@ -0,0 +1,214 @@
+use std::path::{Path, PathBuf};
+
+use super::board::Shading;
+use super::BoardStyle;
+use eframe::egui;
+use egui::{Color32, FontFamily, Ui};
+
+/// The themes that come with the app
+pub fn presets() -> Vec<BoardStyle> {
+    vec![
+        BoardStyle::default(),
+        BoardStyle {
+            name: String::from("Soft"),
+            background_color: Color32::from_rgb(0xF0, 0xD4, 0x9C),
+            line_color: Color32::from_rgb(0x4A, 0x32, 0x1E),
+            line_thickness: 1.5,
+            star_point_radius: 4.0,
+            black_color: Color32::from_rgb(0x1A, 0x1A, 0x1A),
+            white_color: Color32::from_rgb(0xF4, 0xF4, 0xEE),
+            shading: Shading::Gradient,
+            markup_color: Color32::from_rgb(0xC0, 0x30, 0x30),
+            font: FontFamily::Proportional,
+            ..Default::default()
+        },
+        BoardStyle {
+            name: String::from("Dark"),
+            background_color: Color32::from_rgb(0x2A, 0x2A, 0x30),
+            line_color: Color32::from_rgb(0x9A, 0x9A, 0xA0),
+            line_thickness: 1.5,
+            star_point_radius: 4.0,
+            black_color: Color32::from_rgb(0x08, 0x08, 0x08),
+            white_color: Color32::from_rgb(0xE6, 0xE6, 0xE6),
+            outline: 1.0,
+            shading: Shading::Gradient,
+            markup_color: Color32::from_rgb(0xFF, 0x8C, 0x40),
+            font: FontFamily::Proportional,
+            ..Default::default()
+        },
+        BoardStyle {
+            name: String::from("High contrast"),
+            background_color: Color32::from_rgb(0xFF, 0xDD, 0x00),
+            line_color: Color32::BLACK,
+            line_thickness: 3.0,
+            star_point_radius: 6.0,
+            stone_radius: 0.45,
+            outline: 2.0,
+            markup_color: Color32::from_rgb(0xC0, 0x00, 0xC0),
+            ..Default::default()
+        },
+    ]
+}
+
+/// The image used as the board's texture, loaded when its path changes
+#[derive(Default)]
+pub struct Texture {
+    path: Option<PathBuf>,
+    handle: Option<egui::TextureHandle>,
+    error: Option<String>,
+}
+impl Texture {
+    pub fn get(&mut self, ctx: &egui::Context, path: Option<&Path>) -> Option<egui::TextureId> {
+        if self.path.as_deref() != path {
+            self.path = path.map(Path::to_path_buf);
+            self.handle = None;
+            self.error = None;
+
+            if let Some(p) = path {
+                match load_image(p) {
+                    Ok(image) => {
+                        self.handle =
+                            Some(ctx.load_texture("board texture", image, Default::default()))
+                    }
+                    Err(e) => self.error = Some(format!("failed to load the texture: {}", e)),
+                }
+            }
+        }
+
+        self.handle.as_ref().map(|h| h.id())
+    }
+}
+
+fn load_image(path: &Path) -> image::ImageResult<egui::ColorImage> {
+    let image = image::open(path)?.to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+
+    Ok(egui::ColorImage::from_rgba_unmultiplied(
+        size,
+        image.as_raw(),
+    ))
+}
+
+#[derive(Default)]
+pub struct ThemeState {
+    /// Themes made by the user
+    pub saved: Vec<BoardStyle>,
+    pub texture: Texture,
+}
+
+pub fn theme_editor(ui: &mut Ui, style: &mut BoardStyle, ts: &mut ThemeState) {
+    egui::CollapsingHeader::new("Theme").show(ui, |ui| {
+        egui::ComboBox::from_label("theme")
+            .selected_text(style.name.as_str())
+            .show_ui(ui, |ui| {
+                for t in presets().iter().chain(ts.saved.iter()) {
+                    if ui.selectable_label(*style == *t, t.name.as_str()).clicked() {
+                        *style = t.clone();
+                    }
+                }
+            });
+
+        ui.horizontal(|ui| {
+            ui.label("name");
+            ui.text_edit_singleline(&mut style.name);
+        });
+
+        egui::Grid::new("theme editor").show(ui, |ui| {
+            ui.label("background");
+            ui.color_edit_button_srgba(&mut style.background_color);
+            ui.end_row();
+
+            ui.label("texture");
+            ui.horizontal(|ui| {
+                let name = style
+                    .texture
+                    .as_ref()
+                    .and_then(|p| p.file_name())
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| String::from("none"));
+                ui.label(name);
+
+                if ui.button("choose").clicked() {
+                    if let Some(p) = rfd::FileDialog::new()
+                        .add_filter("image", &["png", "jpg", "jpeg"])
+                        .pick_file()
+                    {
+                        style.texture = Some(p);
+                    }
+                }
+                if style.texture.is_some() && ui.button("remove").clicked() {
+                    style.texture = None;
+                }
+            });
+            ui.end_row();
+
+            ui.label("lines");
+            ui.horizontal(|ui| {
+                ui.color_edit_button_srgba(&mut style.line_color);
+                ui.add(egui::Slider::new(&mut style.line_thickness, 0.5..=6.0));
+            });
+            ui.end_row();
+
+            ui.label("star points");
+            ui.add(egui::Slider::new(&mut style.star_point_radius, 0.0..=10.0));
+            ui.end_row();
+
+            ui.label("stones");
+            ui.horizontal(|ui| {
+                ui.color_edit_button_srgba(&mut style.black_color);
+                ui.color_edit_button_srgba(&mut style.white_color);
+                ui.add(egui::Slider::new(&mut style.stone_radius, 0.3..=0.5).text("size"));
+            });
+            ui.end_row();
+
+            ui.label("outline");
+            ui.add(egui::Slider::new(&mut style.outline, 0.0..=4.0));
+            ui.end_row();
+
+            ui.label("shading");
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut style.shading, Shading::Flat, "flat");
+                ui.radio_value(&mut style.shading, Shading::Gradient, "gradient");
+                ui.radio_value(&mut style.shading, Shading::Shadow, "shadow");
+            });
+            ui.end_row();
+
+            ui.label("markup");
+            ui.color_edit_button_srgba(&mut style.markup_color);
+            ui.end_row();
+
+            ui.label("font");
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut style.font, FontFamily::Proportional, "proportional");
+                ui.radio_value(&mut style.font, FontFamily::Monospace, "monospace");
+            });
+            ui.end_row();
+        });
+
+        ui.horizontal(|ui| {
+            let preset = presets().iter().any(|t| t.name == style.name);
+            // presets can't be replaced, so the name has to be changed first
+            if ui
+                .add_enabled(!preset, egui::Button::new("save theme"))
+                .clicked()
+            {
+                match ts.saved.iter_mut().find(|t| t.name == style.name) {
+                    Some(t) => *t = style.clone(),
+                    None => ts.saved.push(style.clone()),
+                }
+            }
+
+            let saved = ts.saved.iter().any(|t| t.name == style.name);
+            if ui
+                .add_enabled(saved, egui::Button::new("delete theme"))
+                .clicked()
+            {
+                ts.saved.retain(|t| t.name != style.name);
+            }
+        });
+
+        if let Some(e) = &ts.texture.error {
+            ui.label(e.as_str());
+        }
+    });
+}
@@ -1,4 +1,6 @@
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum ToolType {
     /// Place alternating black and white stones
     Play,
@@ -25,6 +27,16 @@ pub struct UiTool {
     pub letter: char,
     pub number: u8,
 }
+impl Default for UiTool {
+    fn default() -> Self {
+        Self {
+            tool: ToolType::Play,
+            base: None,
+            letter: 'A',
+            number: 1,
+        }
+    }
+}
 impl UiTool {
     pub fn clear(&mut self) {
         self.base = None;
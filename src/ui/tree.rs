@@ -0,0 +1,186 @@
+use crate::sgf::{Action, SgfNode, TreeLayout};
+use crate::{Game, Stone};
+use eframe::egui;
+use egui::{pos2, vec2, Align2, Color32, FontId, Pos2, Rect, Sense, Stroke, Ui};
+
+/// The width and height of a node on the grid
+const CELL: f32 = 26.0;
+
+/// Properties that judge a position or a move
+const ANNOTATIONS: [&str; 9] = ["GB", "GW", "DM", "UC", "TE", "BM", "DO", "IT", "HO"];
+
+#[derive(Default)]
+pub struct TreeView {
+    pub open: bool,
+    layout: Option<TreeLayout>,
+    /// The number of nodes and address of the root when `layout` was made,
+    /// to tell when the tree has changed
+    key: (usize, usize),
+    /// The node that was scrolled to last
+    scrolled_to: Option<usize>,
+}
+impl TreeView {
+    fn layout(&mut self, game: &Game) -> &TreeLayout {
+        let key = (
+            game.tree.num_nodes(),
+            game.tree.root() as *const SgfNode as usize,
+        );
+        if self.layout.is_none() || self.key != key {
+            self.layout = Some(TreeLayout::new(&game.tree));
+            self.key = key;
+        }
+
+        self.layout
+            .get_or_insert_with(|| TreeLayout::new(&game.tree))
+    }
+}
+
+/// Shows every variation of the game in a window.
+/// Clicking a node goes to it; returns true if one was clicked.
+pub fn tree_window(ui: &mut Ui, view: &mut TreeView, game: &mut Game) -> bool {
+    if !view.open {
+        return false;
+    }
+
+    let mut open = true;
+    let mut clicked = None;
+    let current = game.tree.current_index();
+    let scroll = view.scrolled_to != Some(current);
+    view.scrolled_to = Some(current);
+    let layout = view.layout(game);
+
+    egui::Window::new("game tree")
+        .open(&mut open)
+        .default_size([400.0, 250.0])
+        .show(ui.ctx(), |ui| {
+            egui::ScrollArea::both()
+                .auto_shrink([false, false])
+                .show_viewport(ui, |ui, viewport| {
+                    let size = vec2(layout.columns as f32 * CELL, layout.rows as f32 * CELL);
+                    let (response, painter) = ui.allocate_painter(size, Sense::click());
+                    let origin = response.rect.min;
+                    let visible = viewport.translate(origin.to_vec2()).expand(CELL);
+
+                    let center = |i: usize| {
+                        let (c, r) = layout.positions[i];
+                        origin + vec2((c as f32 + 0.5) * CELL, (r as f32 + 0.5) * CELL)
+                    };
+
+                    // only the nodes and edges that can be seen are drawn,
+                    // so that long games stay fast
+                    for i in 0..game.tree.num_nodes() {
+                        let node = match game.tree.node(i) {
+                            Some(n) => n,
+                            None => break,
+                        };
+                        let to = center(i);
+
+                        if let Some(p) = node.parent() {
+                            let from = center(p);
+                            if visible.intersects(Rect::from_two_pos(from, to)) {
+                                edge(&painter, from, to);
+                            }
+                        }
+
+                        if visible.contains(to) {
+                            draw_node(&painter, node, to, layout.move_numbers[i], i == current);
+                        }
+                    }
+
+                    if scroll {
+                        let c = center(current);
+                        ui.scroll_to_rect(
+                            Rect::from_center_size(c, vec2(CELL, CELL)),
+                            Some(egui::Align::Center),
+                        );
+                    }
+
+                    if let Some(p) = response
+                        .interact_pointer_pos()
+                        .filter(|_| response.clicked())
+                    {
+                        let d = (p - origin) / CELL;
+                        if d.x >= 0.0 && d.y >= 0.0 {
+                            clicked = layout.node_at(d.x as usize, d.y as usize);
+                        }
+                    }
+                });
+        });
+    view.open = open;
+
+    match clicked {
+        Some(i) if i != current => {
+            if game.tree.select_node(i).is_err() {
+                return false;
+            }
+            game.do_to_now();
+            view.scrolled_to = Some(i);
+
+            true
+        }
+        _ => false,
+    }
+}
+
+/// A straight line along a line of play, or an elbow down to a variation
+fn edge(painter: &egui::Painter, from: Pos2, to: Pos2) {
+    let stroke = Stroke::new(1.5, Color32::GRAY);
+
+    if from.y == to.y {
+        painter.line_segment([from, to], stroke);
+    } else {
+        let x = from.x + 0.5 * CELL;
+        painter.line_segment([from, pos2(x, from.y)], stroke);
+        painter.line_segment([pos2(x, from.y), pos2(x, to.y)], stroke);
+        painter.line_segment([pos2(x, to.y), to], stroke);
+    }
+}
+
+fn draw_node(painter: &egui::Painter, node: &SgfNode, center: Pos2, number: usize, current: bool) {
+    let r = 0.38 * CELL;
+    let mover = node.actions.iter().find_map(crate::util::mover);
+
+    match mover {
+        Some(s) => {
+            let (fill, text) = match s {
+                Stone::White => (Color32::WHITE, Color32::BLACK),
+                _ => (Color32::BLACK, Color32::WHITE),
+            };
+            painter.circle(center, r, fill, Stroke::new(1.0, Color32::DARK_GRAY));
+
+            let passed = node
+                .actions
+                .iter()
+                .any(|a| matches!(a, Action::PassBlack | Action::PassWhite));
+            let label = if passed {
+                String::from("P")
+            } else {
+                number.to_string()
+            };
+            painter.text(
+                center,
+                Align2::CENTER_CENTER,
+                label,
+                FontId::proportional(if number < 100 { 11.0 } else { 8.0 }),
+                text,
+            );
+        }
+        // the root, or a node that only sets up the board
+        None => {
+            let rect = Rect::from_center_size(center, vec2(1.4 * r, 1.4 * r));
+            painter.rect_filled(rect, 2.0, Color32::GRAY);
+        }
+    }
+
+    let commented = node.actions.iter().any(|a| matches!(a, Action::Comment(_)));
+    if commented {
+        painter.circle_filled(center + vec2(r, -r), 3.0, Color32::LIGHT_BLUE);
+    }
+    if ANNOTATIONS.iter().any(|p| node.get_prop(p).is_some()) {
+        painter.circle_filled(center + vec2(r, r), 3.0, Color32::GOLD);
+    }
+
+    if current {
+        painter.circle_stroke(center, r + 3.0, Stroke::new(2.0, Color32::RED));
+    }
+}
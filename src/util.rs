@@ -1,7 +1,14 @@
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
 
 use crate::sgf::Action;
+use crate::Stone;
+
+/// Where the app keeps its own files, such as the problem progress
+pub fn data_dir() -> Option<PathBuf> {
+    directories_next::ProjectDirs::from("", "", "go").map(|d| d.data_dir().to_path_buf())
+}
 
 pub fn calculate_hash<T: Hash>(t: &T) -> u64 {
     let mut s = DefaultHasher::new();
@@ -62,6 +69,15 @@ pub fn new_node(a: &Action) -> bool {
     )
 }
 
+/// The player who made a move or pass, or `None` if `a` isn't one
+pub fn mover(a: &Action) -> Option<Stone> {
+    match a {
+        Action::PlayBlack(_, _) | Action::PassBlack => Some(Stone::Black),
+        Action::PlayWhite(_, _) | Action::PassWhite => Some(Stone::White),
+        _ => None,
+    }
+}
+
 /// Expects an uppercase letter.
 /// Returns the next uppercase letter or loops back to A if out of range.
 pub fn next_letter(c: char) -> char {